@@ -0,0 +1,8 @@
+-- Per-endpoint body size limits (NULL falls back to the server-wide setting)
+ALTER TABLE endpoints ADD COLUMN max_body_size INTEGER;
+ALTER TABLE endpoints ADD COLUMN body_overflow TEXT;
+
+-- Body bookkeeping for oversized payloads
+ALTER TABLE requests ADD COLUMN body_size INTEGER;
+ALTER TABLE requests ADD COLUMN body_truncated BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE requests ADD COLUMN body_file TEXT;
@@ -0,0 +1,2 @@
+-- Durable capture: commit the request before responding (NULL uses the server default)
+ALTER TABLE endpoints ADD COLUMN durable_capture BOOLEAN;
@@ -0,0 +1,2 @@
+-- Headers exactly as sent: original order, name casing and raw value bytes
+ALTER TABLE requests ADD COLUMN raw_headers TEXT;
@@ -0,0 +1,8 @@
+-- Connection and protocol metadata recorded for each captured request
+ALTER TABLE requests ADD COLUMN http_version TEXT;
+ALTER TABLE requests ADD COLUMN remote_port INTEGER;
+ALTER TABLE requests ADD COLUMN local_address TEXT;
+ALTER TABLE requests ADD COLUMN local_port INTEGER;
+ALTER TABLE requests ADD COLUMN tls BOOLEAN;
+ALTER TABLE requests ADD COLUMN request_size INTEGER;
+ALTER TABLE requests ADD COLUMN processing_time_us INTEGER;
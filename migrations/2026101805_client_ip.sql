@@ -0,0 +1,2 @@
+-- Socket peer of the request; ip_address holds the client resolved through trusted proxies
+ALTER TABLE requests ADD COLUMN peer_address TEXT;
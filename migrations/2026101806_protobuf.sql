@@ -0,0 +1,4 @@
+-- Compiled FileDescriptorSet and message type selection for decoding protobuf bodies
+ALTER TABLE endpoints ADD COLUMN protobuf_descriptor BLOB;
+ALTER TABLE endpoints ADD COLUMN protobuf_message_type TEXT;
+ALTER TABLE endpoints ADD COLUMN protobuf_type_header TEXT;
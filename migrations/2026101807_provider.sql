@@ -0,0 +1,4 @@
+-- Webhook provider and event type detected from each captured request
+ALTER TABLE requests ADD COLUMN provider TEXT;
+ALTER TABLE requests ADD COLUMN event_type TEXT;
+CREATE INDEX IF NOT EXISTS idx_requests_endpoint_provider ON requests(endpoint_id, provider, event_type);
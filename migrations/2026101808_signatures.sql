@@ -0,0 +1,9 @@
+-- Per-endpoint webhook signature verification and the result for each captured request
+ALTER TABLE endpoints ADD COLUMN signature_scheme TEXT;
+ALTER TABLE endpoints ADD COLUMN signature_secrets TEXT;
+ALTER TABLE endpoints ADD COLUMN signature_header TEXT;
+ALTER TABLE endpoints ADD COLUMN signature_tolerance_secs INTEGER;
+ALTER TABLE endpoints ADD COLUMN signature_reject BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE requests ADD COLUMN signature_status TEXT;
+ALTER TABLE requests ADD COLUMN signature_computed TEXT;
+ALTER TABLE requests ADD COLUMN signature_provided TEXT;
@@ -0,0 +1,9 @@
+-- Per-endpoint JWT verification and the result for each captured request
+ALTER TABLE endpoints ADD COLUMN jwt_key_type TEXT;
+ALTER TABLE endpoints ADD COLUMN jwt_key TEXT;
+ALTER TABLE endpoints ADD COLUMN jwt_header TEXT;
+ALTER TABLE endpoints ADD COLUMN jwt_audience TEXT;
+ALTER TABLE endpoints ADD COLUMN jwt_issuer TEXT;
+ALTER TABLE endpoints ADD COLUMN jwt_leeway_secs INTEGER;
+ALTER TABLE requests ADD COLUMN jwt_status TEXT;
+ALTER TABLE requests ADD COLUMN jwt_error TEXT;
@@ -0,0 +1,8 @@
+-- Credentials required from senders, and whether each captured request presented them
+ALTER TABLE endpoints ADD COLUMN auth_type TEXT;
+ALTER TABLE endpoints ADD COLUMN auth_username TEXT;
+ALTER TABLE endpoints ADD COLUMN auth_secret TEXT;
+ALTER TABLE endpoints ADD COLUMN auth_key_name TEXT;
+ALTER TABLE endpoints ADD COLUMN auth_key_location TEXT;
+ALTER TABLE requests ADD COLUMN auth_status TEXT;
+ALTER TABLE requests ADD COLUMN auth_error TEXT;
@@ -0,0 +1,7 @@
+-- Client address allow and deny lists, and how blocked requests are answered and counted
+ALTER TABLE endpoints ADD COLUMN ip_allow TEXT;
+ALTER TABLE endpoints ADD COLUMN ip_deny TEXT;
+ALTER TABLE endpoints ADD COLUMN ip_block_status INTEGER;
+ALTER TABLE endpoints ADD COLUMN ip_block_record BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE endpoints ADD COLUMN blocked_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE requests ADD COLUMN blocked BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Token-bucket limits per endpoint and per client, and how many requests they refused
+ALTER TABLE endpoints ADD COLUMN rate_limit TEXT;
+ALTER TABLE endpoints ADD COLUMN client_rate_limit TEXT;
+ALTER TABLE endpoints ADD COLUMN throttled_count INTEGER NOT NULL DEFAULT 0;
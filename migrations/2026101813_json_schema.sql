@@ -0,0 +1,5 @@
+-- JSON Schema that captured JSON bodies are validated against, and each request's result
+ALTER TABLE endpoints ADD COLUMN json_schema TEXT;
+ALTER TABLE endpoints ADD COLUMN schema_reject BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE requests ADD COLUMN schema_status TEXT;
+ALTER TABLE requests ADD COLUMN schema_errors TEXT;
@@ -0,0 +1,8 @@
+-- Delivery fingerprints and provider delivery IDs, linking retries to the first delivery
+ALTER TABLE endpoints ADD COLUMN fingerprint_headers TEXT;
+ALTER TABLE requests ADD COLUMN fingerprint TEXT;
+ALTER TABLE requests ADD COLUMN delivery_id TEXT;
+ALTER TABLE requests ADD COLUMN duplicate_of INTEGER;
+ALTER TABLE requests ADD COLUMN retry INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX IF NOT EXISTS idx_requests_endpoint_fingerprint ON requests(endpoint_id, fingerprint);
+CREATE INDEX IF NOT EXISTS idx_requests_endpoint_delivery ON requests(endpoint_id, delivery_id);
@@ -0,0 +1,3 @@
+-- Custom response bodies and header values rendered as templates, with render failures recorded
+ALTER TABLE endpoints ADD COLUMN response_template BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE requests ADD COLUMN response_error TEXT;
@@ -0,0 +1,19 @@
+-- Ordered response rules per endpoint; the first rule whose matchers all pass answers
+CREATE TABLE IF NOT EXISTS response_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    endpoint_id TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    name TEXT,
+    matchers TEXT NOT NULL DEFAULT '[]',
+    status INTEGER NOT NULL DEFAULT 200,
+    headers TEXT,
+    body TEXT,
+    template BOOLEAN NOT NULL DEFAULT FALSE,
+    delay_ms INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (endpoint_id) REFERENCES endpoints(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_response_rules_endpoint ON response_rules(endpoint_id, position);
+
+ALTER TABLE requests ADD COLUMN rule_id INTEGER;
@@ -0,0 +1,3 @@
+-- Scripted response sequences, with the step that answered each request
+ALTER TABLE endpoints ADD COLUMN response_sequence TEXT;
+ALTER TABLE requests ADD COLUMN sequence_position INTEGER;
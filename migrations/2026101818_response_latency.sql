@@ -0,0 +1,3 @@
+-- Injected response latency, with the delay applied to each request
+ALTER TABLE endpoints ADD COLUMN response_latency TEXT;
+ALTER TABLE requests ADD COLUMN response_delay_ms INTEGER;
@@ -0,0 +1,386 @@
+use axum::http::{header, HeaderMap};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
+
+/// Credentials a sender must present to an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthType {
+    /// HTTP Basic username and password
+    Basic,
+    /// `Authorization: Bearer <token>` with a static token
+    Bearer,
+    /// A key in a named header or query parameter
+    ApiKey,
+}
+
+impl AuthType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthType::Basic => "basic",
+            AuthType::Bearer => "bearer",
+            AuthType::ApiKey => "api_key",
+        }
+    }
+}
+
+impl fmt::Display for AuthType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuthType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "basic" => Ok(AuthType::Basic),
+            "bearer" => Ok(AuthType::Bearer),
+            "api_key" => Ok(AuthType::ApiKey),
+            other => Err(format!(
+                "invalid auth type '{}', expected basic, bearer or api_key",
+                other
+            )),
+        }
+    }
+}
+
+/// Where an API key is sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+impl KeyLocation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyLocation::Header => "header",
+            KeyLocation::Query => "query",
+        }
+    }
+}
+
+impl FromStr for KeyLocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "header" => Ok(KeyLocation::Header),
+            "query" => Ok(KeyLocation::Query),
+            other => Err(format!(
+                "invalid key location '{}', expected header or query",
+                other
+            )),
+        }
+    }
+}
+
+/// Outcome of checking a request's credentials
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthResult {
+    Authorized,
+    /// Why the request was refused
+    Unauthorized(&'static str),
+}
+
+impl AuthResult {
+    /// Stored status: "authorized" or "unauthorized"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthResult::Authorized => "authorized",
+            AuthResult::Unauthorized(_) => "unauthorized",
+        }
+    }
+
+    pub fn error(&self) -> Option<&'static str> {
+        match self {
+            AuthResult::Authorized => None,
+            AuthResult::Unauthorized(reason) => Some(reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Requirement {
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer {
+        token: String,
+    },
+    ApiKey {
+        name: String,
+        location: KeyLocation,
+        value: String,
+    },
+    /// The stored config could not be read, so nothing is accepted
+    Invalid,
+}
+
+/// Inbound credentials required by an endpoint
+#[derive(Debug, Clone)]
+pub struct InboundAuth {
+    requirement: Requirement,
+}
+
+impl InboundAuth {
+    /// Build the requirement for an auth type. `secret` is the Basic password, the
+    /// bearer token or the API key; Basic also needs a `username` and API keys a
+    /// `key_name`, the header or query parameter carrying the key.
+    pub fn new(
+        auth_type: AuthType,
+        username: Option<&str>,
+        secret: Option<&str>,
+        key_name: Option<&str>,
+        key_location: Option<KeyLocation>,
+    ) -> Result<Self, String> {
+        let secret = secret
+            .filter(|secret| !secret.is_empty())
+            .ok_or("a secret is required")?
+            .to_string();
+
+        let requirement = match auth_type {
+            AuthType::Basic => {
+                let username = username
+                    .filter(|username| !username.is_empty())
+                    .ok_or("a username is required for basic auth")?;
+                if username.contains(':') {
+                    return Err("basic auth username must not contain ':'".to_string());
+                }
+                Requirement::Basic {
+                    username: username.to_string(),
+                    password: secret,
+                }
+            }
+            AuthType::Bearer => Requirement::Bearer { token: secret },
+            AuthType::ApiKey => {
+                let location = key_location.unwrap_or_default();
+                let name = key_name
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .ok_or("a key name is required for api_key auth")?;
+                let name = match location {
+                    KeyLocation::Header => {
+                        let name = name.to_ascii_lowercase();
+                        axum::http::HeaderName::from_str(&name)
+                            .map_err(|_| format!("invalid key header '{}'", name))?;
+                        name
+                    }
+                    KeyLocation::Query => name.to_string(),
+                };
+                Requirement::ApiKey {
+                    name,
+                    location,
+                    value: secret,
+                }
+            }
+        };
+
+        Ok(Self { requirement })
+    }
+
+    /// Requirement no request meets, standing in for a stored config that no longer parses
+    pub fn invalid() -> Self {
+        Self {
+            requirement: Requirement::Invalid,
+        }
+    }
+
+    /// Check a request's headers and raw query string against the requirement
+    pub fn check(&self, headers: &HeaderMap, query: Option<&str>) -> AuthResult {
+        let presented = match &self.requirement {
+            Requirement::Basic { username, password } => {
+                match authorization(headers, "basic").map(decode_basic) {
+                    None => return AuthResult::Unauthorized("missing credentials"),
+                    Some(None) => {
+                        return AuthResult::Unauthorized("malformed Authorization header")
+                    }
+                    Some(Some((user, pass))) => {
+                        // Compare both halves so a wrong username takes as long as a wrong password
+                        let user_ok = equal(&user, username);
+                        let pass_ok = equal(&pass, password);
+                        user_ok && pass_ok
+                    }
+                }
+            }
+            Requirement::Bearer { token } => match authorization(headers, "bearer") {
+                None => return AuthResult::Unauthorized("missing credentials"),
+                Some(presented) => equal(presented, token),
+            },
+            Requirement::ApiKey {
+                name,
+                location,
+                value,
+            } => {
+                let presented = match location {
+                    KeyLocation::Header => headers
+                        .get(name.as_str())
+                        .and_then(|value| value.to_str().ok())
+                        .map(|value| value.trim().to_string()),
+                    KeyLocation::Query => {
+                        crate::decode::params::parse_pairs(query.unwrap_or_default().as_bytes())
+                            .into_iter()
+                            .find(|pair| &pair.name == name)
+                            .map(|pair| pair.value)
+                    }
+                };
+                match presented {
+                    None => return AuthResult::Unauthorized("missing credentials"),
+                    Some(presented) => equal(&presented, value),
+                }
+            }
+            Requirement::Invalid => return AuthResult::Unauthorized("auth config is invalid"),
+        };
+
+        if presented {
+            AuthResult::Authorized
+        } else {
+            AuthResult::Unauthorized("invalid credentials")
+        }
+    }
+
+    /// `WWW-Authenticate` challenge sent with a 401, if the scheme defines one
+    pub fn challenge(&self) -> Option<&'static str> {
+        match self.requirement {
+            Requirement::Basic { .. } => Some(r#"Basic realm="hookshot""#),
+            Requirement::Bearer { .. } => Some(r#"Bearer realm="hookshot""#),
+            Requirement::ApiKey { .. } | Requirement::Invalid => None,
+        }
+    }
+}
+
+/// Credentials of an `Authorization` header using the given scheme
+fn authorization<'a>(headers: &'a HeaderMap, scheme: &str) -> Option<&'a str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?.trim();
+    let (name, credentials) = value.split_once(' ')?;
+    name.eq_ignore_ascii_case(scheme)
+        .then(|| credentials.trim())
+}
+
+fn decode_basic(credentials: &str) -> Option<(String, String)> {
+    let decoded = general_purpose::STANDARD.decode(credentials).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+fn equal(presented: &str, expected: &str) -> bool {
+    presented.as_bytes().ct_eq(expected.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn basic(credentials: &str) -> String {
+        format!("Basic {}", general_purpose::STANDARD.encode(credentials))
+    }
+
+    #[test]
+    fn test_new_validates_configuration() {
+        assert!(InboundAuth::new(AuthType::Bearer, None, None, None, None).is_err());
+        assert_eq!(
+            InboundAuth::new(AuthType::Basic, None, Some("pw"), None, None).unwrap_err(),
+            "a username is required for basic auth"
+        );
+        assert!(InboundAuth::new(AuthType::Basic, Some("a:b"), Some("pw"), None, None).is_err());
+        assert!(InboundAuth::new(AuthType::ApiKey, None, Some("k"), None, None).is_err());
+        assert!(
+            InboundAuth::new(AuthType::ApiKey, None, Some("k"), Some("bad name"), None).is_err()
+        );
+        assert_eq!("api_key".parse::<AuthType>(), Ok(AuthType::ApiKey));
+    }
+
+    #[test]
+    fn test_basic() {
+        let auth =
+            InboundAuth::new(AuthType::Basic, Some("hook"), Some("s3cret"), None, None).unwrap();
+
+        let check = |value: &str| auth.check(&headers(&[("authorization", value)]), None);
+        assert_eq!(check(&basic("hook:s3cret")), AuthResult::Authorized);
+        assert_eq!(
+            check(&basic("hook:s3cret:extra")),
+            AuthResult::Unauthorized("invalid credentials")
+        );
+        assert_eq!(
+            check(&basic("other:s3cret")),
+            AuthResult::Unauthorized("invalid credentials")
+        );
+        assert_eq!(
+            check("Basic !!!"),
+            AuthResult::Unauthorized("malformed Authorization header")
+        );
+        assert_eq!(
+            check("Bearer s3cret"),
+            AuthResult::Unauthorized("missing credentials")
+        );
+        assert_eq!(auth.challenge(), Some(r#"Basic realm="hookshot""#));
+    }
+
+    #[test]
+    fn test_bearer() {
+        let auth = InboundAuth::new(AuthType::Bearer, None, Some("tok_123"), None, None).unwrap();
+
+        assert_eq!(
+            auth.check(&headers(&[("authorization", "bearer tok_123")]), None),
+            AuthResult::Authorized
+        );
+        assert_eq!(
+            auth.check(&headers(&[("authorization", "Bearer tok_124")]), None),
+            AuthResult::Unauthorized("invalid credentials")
+        );
+        assert_eq!(
+            auth.check(&HeaderMap::new(), None),
+            AuthResult::Unauthorized("missing credentials")
+        );
+    }
+
+    #[test]
+    fn test_api_key() {
+        let auth =
+            InboundAuth::new(AuthType::ApiKey, None, Some("k-1"), Some("X-API-Key"), None).unwrap();
+        assert_eq!(
+            auth.check(&headers(&[("x-api-key", "k-1")]), None),
+            AuthResult::Authorized
+        );
+        assert_eq!(
+            auth.check(&HeaderMap::new(), Some("x-api-key=k-1")),
+            AuthResult::Unauthorized("missing credentials")
+        );
+        assert_eq!(auth.challenge(), None);
+
+        let auth = InboundAuth::new(
+            AuthType::ApiKey,
+            None,
+            Some("k 1"),
+            Some("token"),
+            Some(KeyLocation::Query),
+        )
+        .unwrap();
+        assert_eq!(
+            auth.check(&HeaderMap::new(), Some("a=b&token=k+1")),
+            AuthResult::Authorized
+        );
+        assert_eq!(
+            auth.check(&HeaderMap::new(), Some("token=k2")),
+            AuthResult::Unauthorized("invalid credentials")
+        );
+    }
+}
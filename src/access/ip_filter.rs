@@ -0,0 +1,112 @@
+use crate::config::parse_ip_net;
+use axum::http::StatusCode;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// Status sent to blocked clients unless the endpoint sets another
+pub const DEFAULT_BLOCK_STATUS: StatusCode = StatusCode::FORBIDDEN;
+
+/// CIDR allow and deny lists for an endpoint's client addresses
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+impl IpFilter {
+    /// Parse the allow and deny lists; entries are CIDR blocks or single addresses
+    pub fn new(allow: &[String], deny: &[String]) -> Result<Self, String> {
+        let parse = |entries: &[String]| {
+            entries
+                .iter()
+                .map(|entry| parse_ip_net(entry))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            allow: parse(allow)?,
+            deny: parse(deny)?,
+        })
+    }
+
+    /// Filter refusing every address, standing in for stored lists that no longer parse
+    pub fn deny_all() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: vec![IpNet::V4(Default::default()), IpNet::V6(Default::default())],
+        }
+    }
+
+    pub fn allowed(&self) -> &[IpNet] {
+        &self.allow
+    }
+
+    pub fn denied(&self) -> &[IpNet] {
+        &self.deny
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Whether a client may send to the endpoint. The deny list wins over the allow
+    /// list, and an empty allow list admits every address that is not denied.
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        // IPv4 clients reach dual-stack listeners as ::ffff:a.b.c.d
+        let ip = ip.to_canonical();
+        if self.deny.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|net| net.contains(&ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let filter = IpFilter::new(&list(&["10.0.0.0/8", "2001:db8::/32"]), &[]).unwrap();
+        assert!(filter.permits(ip("10.1.2.3")));
+        assert!(filter.permits(ip("2001:db8::1")));
+        assert!(!filter.permits(ip("192.168.1.1")));
+        assert!(filter.permits(ip("::ffff:10.1.2.3")));
+    }
+
+    #[test]
+    fn test_deny_list_wins() {
+        let filter = IpFilter::new(&list(&["10.0.0.0/8"]), &list(&["10.0.0.5"])).unwrap();
+        assert!(filter.permits(ip("10.0.0.4")));
+        assert!(!filter.permits(ip("10.0.0.5")));
+
+        let filter = IpFilter::new(&[], &list(&["203.0.113.0/24"])).unwrap();
+        assert!(filter.permits(ip("198.51.100.7")));
+        assert!(!filter.permits(ip("203.0.113.9")));
+    }
+
+    #[test]
+    fn test_invalid_entry() {
+        assert_eq!(
+            IpFilter::new(&list(&["10.0.0.0/33"]), &[]).unwrap_err(),
+            "invalid IP address or CIDR block '10.0.0.0/33'"
+        );
+        assert!(IpFilter::new(&[], &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_deny_all() {
+        let filter = IpFilter::deny_all();
+        assert!(!filter.is_empty());
+        for addr in ["10.0.0.1", "::ffff:10.0.0.1", "2001:db8::1", "::1"] {
+            assert!(!filter.permits(ip(addr)), "{}", addr);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod auth;
+pub mod ip_filter;
+pub mod rate_limit;
@@ -0,0 +1,427 @@
+use crate::services::request as request_service;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Number of buckets kept; past it the least recently used bucket is dropped
+const MAX_BUCKETS: usize = 10_000;
+
+/// How often throttled counts are written to the endpoints table
+pub const THROTTLED_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A token-bucket limit written as `<requests>/<s|m|h>`, optionally followed by
+/// `:<burst>`, e.g. `10/s` or `600/m:50`. The burst defaults to the request count.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RateLimit {
+    requests: u32,
+    period_secs: u32,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// One request an hour, applied in place of a stored limit that does not parse
+    pub fn strictest() -> Self {
+        Self {
+            requests: 1,
+            period_secs: 3600,
+            burst: 1,
+        }
+    }
+
+    /// Tokens added per second
+    fn rate(&self) -> f64 {
+        self.requests as f64 / self.period_secs as f64
+    }
+
+    fn capacity(&self) -> f64 {
+        self.burst as f64
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.period_secs {
+            1 => "s",
+            60 => "m",
+            _ => "h",
+        };
+        write!(f, "{}/{}", self.requests, unit)?;
+        if self.burst != self.requests {
+            write!(f, ":{}", self.burst)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid rate limit '{}', expected <requests>/<s|m|h>[:<burst>], e.g. 10/s",
+                s
+            )
+        };
+        let (rate, burst) = match s.trim().split_once(':') {
+            Some((rate, burst)) => (rate, Some(burst)),
+            None => (s.trim(), None),
+        };
+        let (requests, unit) = rate.split_once('/').ok_or_else(invalid)?;
+        let requests: u32 = requests.trim().parse().map_err(|_| invalid())?;
+        let period_secs = match unit.trim() {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            _ => return Err(invalid()),
+        };
+        let burst = match burst {
+            Some(burst) => burst.trim().parse().map_err(|_| invalid())?,
+            None => requests,
+        };
+        if requests == 0 || burst == 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            requests,
+            period_secs,
+            burst,
+        })
+    }
+}
+
+impl TryFrom<String> for RateLimit {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<RateLimit> for String {
+    fn from(limit: RateLimit) -> Self {
+        limit.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Global,
+    Endpoint(String),
+    Client(String, IpAddr),
+}
+
+/// Address a client is limited by. IPv6 clients usually hold a whole /64, so they
+/// share one bucket per /64 rather than getting a fresh one for every address.
+fn client_key(client: IpAddr) -> IpAddr {
+    match client.to_canonical() {
+        IpAddr::V6(ip) => {
+            let prefix = u128::from(ip) & !(u128::MAX >> 64);
+            IpAddr::V6(Ipv6Addr::from(prefix))
+        }
+        ip => ip,
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// When the bucket was last used, as a position in `Buckets::recent`
+    used: u64,
+}
+
+impl Bucket {
+    /// Add the tokens earned since the last update, up to the limit's burst
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.rate()).min(limit.capacity());
+        self.updated = now;
+    }
+}
+
+/// Buckets held in memory, dropping the least recently used once `MAX_BUCKETS` are held
+#[derive(Debug, Default)]
+struct Buckets {
+    buckets: HashMap<BucketKey, Bucket>,
+    /// Keys by last use, oldest first
+    recent: BTreeMap<u64, BucketKey>,
+    next_use: u64,
+}
+
+impl Buckets {
+    /// Bucket for a key, marked as just used. A new bucket starts full.
+    fn touch(&mut self, key: &BucketKey, limit: &RateLimit, now: Instant) -> &mut Bucket {
+        let used = self.next_use;
+        self.next_use += 1;
+
+        if let Some(bucket) = self.buckets.get(key) {
+            self.recent.remove(&bucket.used);
+        } else if self.buckets.len() >= MAX_BUCKETS {
+            if let Some((_, oldest)) = self.recent.pop_first() {
+                self.buckets.remove(&oldest);
+            }
+        }
+        self.recent.insert(used, key.clone());
+
+        let bucket = self.buckets.entry(key.clone()).or_insert(Bucket {
+            tokens: limit.capacity(),
+            updated: now,
+            used,
+        });
+        bucket.used = used;
+        bucket
+    }
+}
+
+/// In-memory token buckets for the server-wide, per-endpoint and per-client limits.
+///
+/// Throttled requests are tallied here too and written to the endpoints table by
+/// [`RateLimiter::flush_throttled`], so limiting never waits on the database.
+pub struct RateLimiter {
+    global: Option<RateLimit>,
+    buckets: Mutex<Buckets>,
+    throttled: Mutex<HashMap<String, i64>>,
+}
+
+impl RateLimiter {
+    pub fn new(global: Option<RateLimit>) -> Self {
+        Self {
+            global,
+            buckets: Mutex::new(Buckets::default()),
+            throttled: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token from every bucket that applies to a request. A request is only
+    /// charged when all of them have a token; otherwise it is counted as throttled and
+    /// the wait until it would be let through is returned.
+    pub fn check(
+        &self,
+        endpoint_id: &str,
+        client: IpAddr,
+        endpoint_limit: Option<RateLimit>,
+        client_limit: Option<RateLimit>,
+    ) -> Result<(), Duration> {
+        let limits = [
+            self.global.map(|limit| (BucketKey::Global, limit)),
+            endpoint_limit.map(|limit| (BucketKey::Endpoint(endpoint_id.to_string()), limit)),
+            client_limit.map(|limit| {
+                (
+                    BucketKey::Client(endpoint_id.to_string(), client_key(client)),
+                    limit,
+                )
+            }),
+        ];
+        if limits.iter().all(Option::is_none) {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let mut wait = Duration::ZERO;
+        for (key, limit) in limits.iter().flatten() {
+            let bucket = buckets.touch(key, limit, now);
+            bucket.refill(limit, now);
+            if bucket.tokens < 1.0 {
+                wait = wait.max(Duration::from_secs_f64(
+                    (1.0 - bucket.tokens) / limit.rate(),
+                ));
+            }
+        }
+
+        if !wait.is_zero() {
+            drop(buckets);
+            *self
+                .throttled
+                .lock()
+                .unwrap()
+                .entry(endpoint_id.to_string())
+                .or_default() += 1;
+            return Err(wait);
+        }
+
+        for (key, _) in limits.iter().flatten() {
+            if let Some(bucket) = buckets.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the buckets and unflushed throttled count of a deleted endpoint
+    pub fn forget(&self, endpoint_id: &str) {
+        {
+            let mut buckets = self.buckets.lock().unwrap();
+            let Buckets {
+                buckets, recent, ..
+            } = &mut *buckets;
+            buckets.retain(|key, bucket| {
+                let keep = match key {
+                    BucketKey::Global => true,
+                    BucketKey::Endpoint(id) | BucketKey::Client(id, _) => id != endpoint_id,
+                };
+                if !keep {
+                    recent.remove(&bucket.used);
+                }
+                keep
+            });
+        }
+        self.throttled.lock().unwrap().remove(endpoint_id);
+    }
+
+    /// Add the requests throttled since the last flush to each endpoint's `throttled_count`
+    pub async fn flush_throttled(&self, pool: &SqlitePool) {
+        let throttled = std::mem::take(&mut *self.throttled.lock().unwrap());
+        for (endpoint_id, count) in throttled {
+            if let Err(e) = request_service::add_throttled_count(pool, &endpoint_id, count).await {
+                tracing::error!(
+                    "Failed to count {} throttled requests for endpoint {}: {}",
+                    count,
+                    endpoint_id,
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_rate_limit() {
+        let limit: RateLimit = "600/m:50".parse().unwrap();
+        assert_eq!(limit.rate(), 10.0);
+        assert_eq!(limit.capacity(), 50.0);
+        assert_eq!(limit.to_string(), "600/m:50");
+        assert_eq!(" 10 / s ".parse::<RateLimit>().unwrap().to_string(), "10/s");
+
+        for invalid in ["10", "10/d", "0/s", "10/s:0", "ten/s", "10/s:x"] {
+            assert!(invalid.parse::<RateLimit>().is_err(), "{}", invalid);
+        }
+        assert_eq!(RateLimit::strictest().to_string(), "1/h");
+    }
+
+    #[test]
+    fn test_endpoint_and_client_buckets() {
+        let limiter = RateLimiter::new(None);
+        let endpoint = Some("2/h".parse().unwrap());
+        let client = Some("1/h".parse().unwrap());
+
+        assert!(limiter.check("a", ip("10.0.0.1"), endpoint, client).is_ok());
+        // Second request from the same client is over its limit
+        let wait = limiter
+            .check("a", ip("10.0.0.1"), endpoint, client)
+            .unwrap_err();
+        assert!(wait > Duration::from_secs(3500));
+        // Another client still fits under the endpoint limit, then the endpoint is full
+        assert!(limiter.check("a", ip("10.0.0.2"), endpoint, client).is_ok());
+        assert!(limiter
+            .check("a", ip("10.0.0.3"), endpoint, client)
+            .is_err());
+        // Buckets are kept per endpoint
+        assert!(limiter.check("b", ip("10.0.0.1"), endpoint, client).is_ok());
+
+        assert_eq!(limiter.throttled.lock().unwrap().get("a"), Some(&2));
+    }
+
+    #[test]
+    fn test_forget_endpoint() {
+        let limiter = RateLimiter::new(Some("10/h".parse().unwrap()));
+        let limit = Some("1/h".parse().unwrap());
+
+        for endpoint in ["a", "b"] {
+            assert!(limiter
+                .check(endpoint, ip("10.0.0.1"), limit, limit)
+                .is_ok());
+            assert!(limiter
+                .check(endpoint, ip("10.0.0.1"), limit, limit)
+                .is_err());
+        }
+        limiter.forget("a");
+
+        assert!(limiter.check("a", ip("10.0.0.1"), limit, limit).is_ok());
+        assert!(limiter.check("b", ip("10.0.0.1"), limit, limit).is_err());
+        // The server-wide bucket still counts requests to the deleted endpoint
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.buckets.len(), 5);
+        assert_eq!(buckets.recent.len(), 5);
+        assert_eq!(buckets.buckets[&BucketKey::Global].tokens.round() as i64, 7);
+    }
+
+    #[test]
+    fn test_global_bucket() {
+        let limiter = RateLimiter::new(Some("1/h".parse().unwrap()));
+        assert!(limiter.check("a", ip("10.0.0.1"), None, None).is_ok());
+        assert!(limiter.check("b", ip("10.0.0.2"), None, None).is_err());
+    }
+
+    #[test]
+    fn test_ipv6_clients_share_a_bucket_per_64() {
+        let limiter = RateLimiter::new(None);
+        let client = Some("1/h".parse().unwrap());
+
+        assert!(limiter.check("a", ip("2001:db8::1"), None, client).is_ok());
+        assert!(limiter
+            .check("a", ip("2001:db8::ffff:1"), None, client)
+            .is_err());
+        assert!(limiter
+            .check("a", ip("2001:db8:0:1::1"), None, client)
+            .is_ok());
+        // IPv4-mapped addresses are limited as the IPv4 address
+        assert!(limiter.check("a", ip("10.0.0.1"), None, client).is_ok());
+        assert!(limiter
+            .check("a", ip("::ffff:10.0.0.1"), None, client)
+            .is_err());
+    }
+
+    #[test]
+    fn test_least_recently_used_bucket_is_dropped() {
+        let limiter = RateLimiter::new(None);
+        let client = Some("1/h".parse().unwrap());
+        let nth = |i: u32| IpAddr::from(std::net::Ipv4Addr::from(i));
+
+        assert!(limiter.check("a", nth(0), None, client).is_ok());
+        assert!(limiter.check("a", nth(1), None, client).is_ok());
+        // Using the first bucket again makes the second one the oldest
+        assert!(limiter.check("a", nth(0), None, client).is_err());
+        for i in 2..=MAX_BUCKETS as u32 {
+            assert!(limiter.check("a", nth(i), None, client).is_ok());
+        }
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.buckets.len(), MAX_BUCKETS);
+        assert_eq!(buckets.recent.len(), MAX_BUCKETS);
+        drop(buckets);
+        assert!(limiter.check("a", nth(0), None, client).is_err());
+        assert!(limiter.check("a", nth(1), None, client).is_ok());
+    }
+
+    #[test]
+    fn test_refill() {
+        let limit: RateLimit = "10/s".parse().unwrap();
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            updated: start,
+            used: 0,
+        };
+        bucket.refill(&limit, start + Duration::from_millis(250));
+        assert!((bucket.tokens - 2.5).abs() < 1e-9);
+        bucket.refill(&limit, start + Duration::from_secs(60));
+        assert_eq!(bucket.tokens, 10.0);
+    }
+}
@@ -0,0 +1,115 @@
+use axum::http::HeaderMap;
+use sha2::{Digest, Sha256};
+
+/// Identifies what was delivered to an endpoint, independent of when and how often
+pub struct Delivery<'a> {
+    pub method: &'a str,
+    pub sub_path: &'a str,
+    pub body_size: u64,
+    pub body_sha256: &'a [u8; 32],
+}
+
+/// Fingerprint of a delivery used to spot retries that carry no delivery ID.
+///
+/// The hex SHA-256 of the method, the sub-path below `/webhook/{id}` and the body hash,
+/// combined with the values of the selected headers in the order they were configured;
+/// a missing header counts as empty. Requests without a body, such as pings and health
+/// checks, are not fingerprinted, since repeating one does not make it a retry.
+pub fn fingerprint(delivery: &Delivery, headers: &HeaderMap, names: &[String]) -> Option<String> {
+    if delivery.body_size == 0 {
+        return None;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(delivery.method.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(delivery.sub_path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(delivery.body_sha256);
+    for name in names {
+        hasher.update(name.as_bytes());
+        hasher.update(b":");
+        for value in headers.get_all(name.as_str()) {
+            hasher.update(value.as_bytes());
+            hasher.update(b"\0");
+        }
+        hasher.update(b"\n");
+    }
+    Some(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn body_hash(body: &[u8]) -> [u8; 32] {
+        Sha256::digest(body).into()
+    }
+
+    fn delivery<'a>(method: &'a str, sub_path: &'a str, hash: &'a [u8; 32]) -> Delivery<'a> {
+        Delivery {
+            method,
+            sub_path,
+            body_size: 1,
+            body_sha256: hash,
+        }
+    }
+
+    #[test]
+    fn test_method_and_sub_path() {
+        let hash = body_hash(b"hello");
+        let post = fingerprint(&delivery("POST", "/", &hash), &HeaderMap::new(), &[]);
+        assert!(post.is_some());
+        assert_eq!(
+            post,
+            fingerprint(&delivery("POST", "/", &hash), &HeaderMap::new(), &[])
+        );
+        assert_ne!(
+            post,
+            fingerprint(&delivery("PUT", "/", &hash), &HeaderMap::new(), &[])
+        );
+        assert_ne!(
+            post,
+            fingerprint(&delivery("POST", "/orders", &hash), &HeaderMap::new(), &[])
+        );
+    }
+
+    #[test]
+    fn test_empty_body_is_not_fingerprinted() {
+        let hash = body_hash(b"");
+        let empty = Delivery {
+            body_size: 0,
+            ..delivery("GET", "/health", &hash)
+        };
+        assert_eq!(fingerprint(&empty, &HeaderMap::new(), &[]), None);
+    }
+
+    #[test]
+    fn test_selected_headers() {
+        let names = vec!["x-event".to_string()];
+        let mut push = HeaderMap::new();
+        push.insert("x-event", HeaderValue::from_static("push"));
+        push.insert("x-request-id", HeaderValue::from_static("1"));
+        let mut retry = push.clone();
+        retry.insert("x-request-id", HeaderValue::from_static("2"));
+        let mut tag = push.clone();
+        tag.insert("x-event", HeaderValue::from_static("tag"));
+
+        let hash = body_hash(b"{}");
+        let hash = &delivery("POST", "/", &hash);
+        // Headers that are not selected do not change the fingerprint
+        assert_eq!(
+            fingerprint(hash, &push, &names),
+            fingerprint(hash, &retry, &names)
+        );
+        assert_ne!(
+            fingerprint(hash, &push, &names),
+            fingerprint(hash, &tag, &names)
+        );
+        assert_ne!(
+            fingerprint(hash, &push, &names),
+            fingerprint(hash, &push, &[])
+        );
+    }
+}
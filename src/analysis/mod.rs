@@ -0,0 +1,5 @@
+pub mod fingerprint;
+pub mod jwt;
+pub mod provider;
+pub mod schema;
+pub mod signature;
@@ -0,0 +1,288 @@
+use axum::http::HeaderMap;
+
+/// Provider, event type and delivery ID recognized from a captured request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    pub provider: &'static str,
+    pub event_type: Option<String>,
+    /// ID the provider keeps across retries of the same delivery
+    pub delivery_id: Option<String>,
+}
+
+/// Where a provider puts the event type or delivery ID
+enum Source {
+    Header(&'static str),
+    /// Path of a string field in a JSON body; the first path present wins
+    JsonField(&'static [&'static [&'static str]]),
+    None,
+}
+
+/// A webhook sender recognized by a header only it sends
+struct Provider {
+    name: &'static str,
+    marker: &'static str,
+    event: Source,
+    delivery: Source,
+}
+
+/// Known providers, checked in order. Gitea also sends `X-GitHub-Event` for
+/// compatibility, so it is listed before GitHub.
+const PROVIDERS: &[Provider] = &[
+    Provider {
+        name: "gitea",
+        marker: "x-gitea-event",
+        event: Source::Header("x-gitea-event"),
+        delivery: Source::Header("x-gitea-delivery"),
+    },
+    Provider {
+        name: "github",
+        marker: "x-github-event",
+        event: Source::Header("x-github-event"),
+        delivery: Source::Header("x-github-delivery"),
+    },
+    Provider {
+        name: "gitlab",
+        marker: "x-gitlab-event",
+        event: Source::Header("x-gitlab-event"),
+        delivery: Source::Header("x-gitlab-event-uuid"),
+    },
+    Provider {
+        name: "bitbucket",
+        marker: "x-hook-uuid",
+        event: Source::Header("x-event-key"),
+        delivery: Source::Header("x-request-uuid"),
+    },
+    Provider {
+        name: "stripe",
+        marker: "stripe-signature",
+        event: Source::JsonField(&[&["type"]]),
+        delivery: Source::JsonField(&[&["id"]]),
+    },
+    Provider {
+        name: "slack",
+        marker: "x-slack-signature",
+        event: Source::JsonField(&[&["event", "type"], &["type"]]),
+        delivery: Source::JsonField(&[&["event_id"]]),
+    },
+    Provider {
+        name: "shopify",
+        marker: "x-shopify-topic",
+        event: Source::Header("x-shopify-topic"),
+        delivery: Source::Header("x-shopify-webhook-id"),
+    },
+    Provider {
+        name: "twilio",
+        marker: "x-twilio-signature",
+        event: Source::None,
+        delivery: Source::Header("i-twilio-idempotency-token"),
+    },
+    Provider {
+        name: "svix",
+        marker: "svix-signature",
+        event: Source::JsonField(&[&["type"], &["eventType"]]),
+        delivery: Source::Header("svix-id"),
+    },
+    Provider {
+        name: "paddle",
+        marker: "paddle-signature",
+        event: Source::JsonField(&[&["event_type"]]),
+        delivery: Source::JsonField(&[&["event_id"]]),
+    },
+    Provider {
+        name: "pagerduty",
+        marker: "x-pagerduty-signature",
+        event: Source::JsonField(&[&["event", "event_type"]]),
+        delivery: Source::JsonField(&[&["event", "id"]]),
+    },
+    Provider {
+        name: "linear",
+        marker: "linear-delivery",
+        event: Source::Header("linear-event"),
+        delivery: Source::Header("linear-delivery"),
+    },
+    Provider {
+        name: "sentry",
+        marker: "sentry-hook-resource",
+        event: Source::Header("sentry-hook-resource"),
+        delivery: Source::Header("request-id"),
+    },
+    // Standard Webhooks (https://www.standardwebhooks.com), used by Svix-powered senders
+    Provider {
+        name: "standard-webhooks",
+        marker: "webhook-signature",
+        event: Source::JsonField(&[&["type"]]),
+        delivery: Source::Header("webhook-id"),
+    },
+];
+
+/// Detect the provider that sent a webhook, the event it reports and its delivery ID
+pub fn detect(headers: &HeaderMap, body: &[u8]) -> Option<Detection> {
+    let provider = find(headers)?;
+
+    Some(Detection {
+        provider: provider.name,
+        event_type: provider.event.read(headers, body),
+        delivery_id: provider.delivery.read(headers, body),
+    })
+}
+
+/// Whether the provider that sent a webhook reports its event or delivery ID in the body
+pub fn reads_body(headers: &HeaderMap) -> bool {
+    find(headers).is_some_and(|provider| {
+        matches!(provider.event, Source::JsonField(_))
+            || matches!(provider.delivery, Source::JsonField(_))
+    })
+}
+
+fn find(headers: &HeaderMap) -> Option<&'static Provider> {
+    PROVIDERS
+        .iter()
+        .find(|provider| headers.contains_key(provider.marker))
+}
+
+impl Source {
+    fn read(&self, headers: &HeaderMap, body: &[u8]) -> Option<String> {
+        let value = match self {
+            Source::Header(name) => headers
+                .get(*name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string()),
+            Source::JsonField(paths) => json_field(body, paths),
+            Source::None => None,
+        };
+        value.filter(|value| !value.is_empty())
+    }
+}
+
+fn json_field(body: &[u8], paths: &[&[&str]]) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_slice(body).ok()?;
+    paths.iter().find_map(|path| {
+        path.iter()
+            .try_fold(&json, |value, key| value.get(key))?
+            .as_str()
+            .map(str::to_string)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn detection(provider: &'static str, event_type: Option<&str>) -> Option<Detection> {
+        Some(Detection {
+            provider,
+            event_type: event_type.map(str::to_string),
+            delivery_id: None,
+        })
+    }
+
+    #[test]
+    fn test_detect_from_headers() {
+        assert_eq!(
+            detect(&headers(&[("x-github-event", "pull_request")]), b""),
+            detection("github", Some("pull_request"))
+        );
+        assert_eq!(
+            detect(
+                &headers(&[("x-gitea-event", "push"), ("x-github-event", "push")]),
+                b""
+            ),
+            detection("gitea", Some("push"))
+        );
+        assert_eq!(
+            detect(&headers(&[("x-shopify-topic", "orders/create")]), b""),
+            detection("shopify", Some("orders/create"))
+        );
+        assert_eq!(
+            detect(&headers(&[("x-twilio-signature", "abc=")]), b"Body=hi"),
+            detection("twilio", None)
+        );
+    }
+
+    #[test]
+    fn test_detect_from_body() {
+        assert_eq!(
+            detect(
+                &headers(&[("stripe-signature", "t=1,v1=abc")]),
+                br#"{"id":"evt_1","type":"invoice.paid"}"#
+            ),
+            Some(Detection {
+                delivery_id: Some("evt_1".to_string()),
+                ..detection("stripe", Some("invoice.paid")).unwrap()
+            })
+        );
+        assert_eq!(
+            detect(
+                &headers(&[("x-slack-signature", "v0=abc")]),
+                br#"{"type":"event_callback","event":{"type":"app_mention"}}"#
+            ),
+            detection("slack", Some("app_mention"))
+        );
+        assert_eq!(
+            detect(
+                &headers(&[("x-slack-signature", "v0=abc")]),
+                br#"{"type":"url_verification"}"#
+            ),
+            detection("slack", Some("url_verification"))
+        );
+        // Provider is still known when the body is not JSON
+        assert_eq!(
+            detect(&headers(&[("stripe-signature", "t=1")]), b"\x1f\x8b"),
+            detection("stripe", None)
+        );
+    }
+
+    #[test]
+    fn test_reads_body() {
+        assert!(reads_body(&headers(&[("stripe-signature", "t=1")])));
+        assert!(reads_body(&headers(&[("svix-signature", "v1,abc")])));
+        assert!(!reads_body(&headers(&[("x-github-event", "push")])));
+        assert!(!reads_body(&headers(&[])));
+    }
+
+    #[test]
+    fn test_delivery_ids() {
+        let delivery_id = |pairs: &[(&'static str, &'static str)], body: &[u8]| {
+            detect(&headers(pairs), body).and_then(|d| d.delivery_id)
+        };
+        assert_eq!(
+            delivery_id(
+                &[
+                    ("x-github-event", "push"),
+                    ("x-github-delivery", "72d3162e-cc78-11e3")
+                ],
+                b""
+            ),
+            Some("72d3162e-cc78-11e3".to_string())
+        );
+        assert_eq!(
+            delivery_id(
+                &[("webhook-signature", "v1,abc"), ("webhook-id", "msg_2")],
+                b"{}"
+            ),
+            Some("msg_2".to_string())
+        );
+        assert_eq!(
+            delivery_id(
+                &[("x-pagerduty-signature", "v1=abc")],
+                br#"{"event":{"id":"01DEN","event_type":"incident.triggered"}}"#
+            ),
+            Some("01DEN".to_string())
+        );
+        assert_eq!(delivery_id(&[("x-github-event", "push")], b""), None);
+    }
+
+    #[test]
+    fn test_detect_unknown() {
+        assert_eq!(detect(&headers(&[("user-agent", "curl/8")]), b"{}"), None);
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Most errors kept for one request; a badly wrong payload can produce thousands
+pub const MAX_ERRORS: usize = 100;
+
+/// Outcome of validating a body against an endpoint's schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Valid,
+    Invalid,
+    /// The body was not checked; the reason is its only error
+    Skipped,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Valid => "valid",
+            Status::Invalid => "invalid",
+            Status::Skipped => "skipped",
+        }
+    }
+}
+
+/// A single validation failure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaError {
+    pub pointer: String, // JSON pointer to the failing value, "" for the whole body
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validation {
+    pub status: Status,
+    pub errors: Vec<SchemaError>,
+}
+
+impl Validation {
+    /// Result for a body that should have been validated but could not be
+    pub fn skipped(reason: impl Into<String>) -> Self {
+        Self {
+            status: Status::Skipped,
+            errors: vec![SchemaError {
+                pointer: String::new(),
+                message: reason.into(),
+            }],
+        }
+    }
+}
+
+/// Validates JSON bodies against an endpoint's JSON Schema
+#[derive(Debug, Clone)]
+pub struct SchemaValidator {
+    validator: Arc<jsonschema::Validator>,
+}
+
+impl SchemaValidator {
+    /// Compile a schema from its JSON text. The draft is taken from `$schema`,
+    /// defaulting to 2020-12; remote `$ref`s are not fetched.
+    pub fn new(schema: &str) -> Result<Self, String> {
+        let schema: serde_json::Value =
+            serde_json::from_str(schema).map_err(|e| format!("schema is not valid JSON: {}", e))?;
+        let validator =
+            jsonschema::validator_for(&schema).map_err(|e| format!("invalid schema: {}", e))?;
+        Ok(Self {
+            validator: Arc::new(validator),
+        })
+    }
+
+    /// Whether bodies of a media type are validated: `application/json` and `+json` ones
+    pub fn applies_to(&self, media_type: &str) -> bool {
+        media_type == "application/json" || media_type.ends_with("+json")
+    }
+
+    /// Validate a body sent with a JSON media type. Other bodies are not validated;
+    /// a JSON body that does not parse is invalid.
+    pub fn validate(&self, media_type: &str, content: &[u8]) -> Option<Validation> {
+        if !self.applies_to(media_type) {
+            return None;
+        }
+
+        let instance: serde_json::Value = match serde_json::from_slice(content) {
+            Ok(instance) => instance,
+            Err(e) => {
+                return Some(Validation {
+                    status: Status::Invalid,
+                    errors: vec![SchemaError {
+                        pointer: String::new(),
+                        message: format!("body is not valid JSON: {}", e),
+                    }],
+                })
+            }
+        };
+
+        let errors: Vec<SchemaError> = self
+            .validator
+            .iter_errors(&instance)
+            .take(MAX_ERRORS)
+            .map(|error| SchemaError {
+                pointer: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect();
+        let status = if errors.is_empty() {
+            Status::Valid
+        } else {
+            Status::Invalid
+        };
+        Some(Validation { status, errors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"{
+        "type": "object",
+        "required": ["id", "items"],
+        "properties": {
+            "id": {"type": "integer"},
+            "items": {"type": "array", "items": {"type": "string"}}
+        }
+    }"#;
+
+    #[test]
+    fn test_valid_body() {
+        let validator = SchemaValidator::new(SCHEMA).unwrap();
+        let validation = validator
+            .validate("application/json", br#"{"id": 1, "items": ["a"]}"#)
+            .unwrap();
+        assert_eq!(validation.status, Status::Valid);
+        assert!(validation.errors.is_empty());
+    }
+
+    #[test]
+    fn test_errors_have_pointers() {
+        let validator = SchemaValidator::new(SCHEMA).unwrap();
+        let validation = validator
+            .validate(
+                "application/vnd.api+json",
+                br#"{"id": "1", "items": ["a", 2]}"#,
+            )
+            .unwrap();
+        assert_eq!(validation.status, Status::Invalid);
+        let pointers: Vec<&str> = validation
+            .errors
+            .iter()
+            .map(|error| error.pointer.as_str())
+            .collect();
+        assert_eq!(pointers, vec!["/id", "/items/1"]);
+        assert!(validation.errors[0].message.contains("integer"));
+    }
+
+    #[test]
+    fn test_non_json_bodies() {
+        let validator = SchemaValidator::new(SCHEMA).unwrap();
+        assert!(validator.validate("text/plain", b"hello").is_none());
+
+        let validation = validator.validate("application/json", b"{").unwrap();
+        assert_eq!(validation.status, Status::Invalid);
+        assert_eq!(validation.errors[0].pointer, "");
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        assert!(SchemaValidator::new("{").is_err());
+        assert!(SchemaValidator::new(r#"{"type": "banana"}"#).is_err());
+    }
+}
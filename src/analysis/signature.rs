@@ -0,0 +1,717 @@
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::convert::Infallible;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
+
+/// Seconds a signed timestamp may differ from the current time when none is configured
+pub const DEFAULT_TOLERANCE_SECS: i64 = 300;
+
+/// How a sender signs its webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scheme {
+    /// `X-Hub-Signature-256: sha256=<hex>` over the body
+    Github,
+    /// `Stripe-Signature: t=<ts>,v1=<hex>` over `<ts>.<body>`
+    Stripe,
+    /// `X-Slack-Signature: v0=<hex>` over `v0:<ts>:<body>`
+    Slack,
+    /// `X-Shopify-Hmac-Sha256: <base64>` over the body
+    Shopify,
+    /// `webhook-signature: v1,<base64>` over `<id>.<ts>.<body>`, also sent as `svix-*`
+    StandardWebhooks,
+    /// HMAC-SHA256 of the body, hex or base64, in a configured header
+    HmacSha256,
+}
+
+impl Scheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scheme::Github => "github",
+            Scheme::Stripe => "stripe",
+            Scheme::Slack => "slack",
+            Scheme::Shopify => "shopify",
+            Scheme::StandardWebhooks => "standard-webhooks",
+            Scheme::HmacSha256 => "hmac-sha256",
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "github" => Ok(Scheme::Github),
+            "stripe" => Ok(Scheme::Stripe),
+            "slack" => Ok(Scheme::Slack),
+            "shopify" => Ok(Scheme::Shopify),
+            "standard-webhooks" | "svix" => Ok(Scheme::StandardWebhooks),
+            "hmac-sha256" => Ok(Scheme::HmacSha256),
+            other => Err(format!(
+                "invalid signature scheme '{}', expected github, stripe, slack, shopify, \
+                 standard-webhooks or hmac-sha256",
+                other
+            )),
+        }
+    }
+}
+
+/// Outcome of checking a request's signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Valid,
+    /// No secret produces the provided signature, or the signature header is malformed
+    Invalid,
+    /// The request carries no signature
+    Missing,
+    /// The signature matches but its timestamp is outside the tolerance
+    Expired,
+    /// The endpoint's stored signature config could not be read, so nothing was checked
+    Error,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Valid => "valid",
+            Status::Invalid => "invalid",
+            Status::Missing => "missing",
+            Status::Expired => "expired",
+            Status::Error => "error",
+        }
+    }
+}
+
+/// Result of verifying a request, with signatures rendered the way the sender writes them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub status: Status,
+    /// Signature of the matching secret, or of the first secret when none match
+    pub computed: Option<String>,
+    /// Signature header value as sent
+    pub provided: Option<String>,
+}
+
+impl Verification {
+    /// Result for every request to an endpoint whose signature config does not parse
+    pub fn config_error() -> Self {
+        Self {
+            status: Status::Error,
+            computed: None,
+            provided: None,
+        }
+    }
+}
+
+/// Checks signatures for an endpoint's scheme and secrets
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    scheme: Scheme,
+    secrets: Vec<String>,
+    /// Lowercased signature header for `hmac-sha256`
+    header: Option<String>,
+    tolerance_secs: i64,
+}
+
+impl Verifier {
+    /// Build a verifier. At least one secret is required, and `hmac-sha256` needs the
+    /// header carrying the signature; `header` is ignored by the other schemes.
+    pub fn new(
+        scheme: Scheme,
+        secrets: Vec<String>,
+        header: Option<&str>,
+        tolerance_secs: Option<i64>,
+    ) -> Result<Self, String> {
+        if secrets.is_empty() || secrets.iter().any(|secret| secret.is_empty()) {
+            return Err("at least one non-empty secret is required".to_string());
+        }
+        if tolerance_secs.is_some_and(|secs| secs <= 0) {
+            return Err("tolerance must be greater than 0".to_string());
+        }
+
+        let header = match scheme {
+            Scheme::HmacSha256 => {
+                let header = header
+                    .map(|name| name.trim().to_ascii_lowercase())
+                    .filter(|name| !name.is_empty())
+                    .ok_or("a signature header is required for hmac-sha256")?;
+                axum::http::HeaderName::from_str(&header)
+                    .map_err(|_| format!("invalid signature header '{}'", header))?;
+                Some(header)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            scheme,
+            secrets,
+            header,
+            tolerance_secs: tolerance_secs.unwrap_or(DEFAULT_TOLERANCE_SECS),
+        })
+    }
+
+    /// Verify a request received at `now` (Unix seconds) against every secret
+    pub fn verify(&self, headers: &HeaderMap, body: &[u8], now: i64) -> Verification {
+        let Ok(verification) = self.check(headers, now, |macs| {
+            for mac in macs {
+                mac.update(body);
+            }
+            Ok::<_, Infallible>(())
+        });
+        verification
+    }
+
+    /// Verify a request whose body was spilled to `path`, hashing the file as it is read
+    /// rather than loading it into memory. This blocks, so async callers should run it on
+    /// a blocking thread.
+    pub fn verify_file(
+        &self,
+        headers: &HeaderMap,
+        path: &Path,
+        now: i64,
+    ) -> io::Result<Verification> {
+        self.check(headers, now, |macs| {
+            let mut file = std::fs::File::open(path)?;
+            let mut chunk = vec![0u8; 64 * 1024];
+            loop {
+                let read = file.read(&mut chunk)?;
+                if read == 0 {
+                    return Ok(());
+                }
+                for mac in macs.iter_mut() {
+                    mac.update(&chunk[..read]);
+                }
+            }
+        })
+    }
+
+    /// Check the signature headers, with `feed_body` adding the body to one MAC per secret
+    fn check<E>(
+        &self,
+        headers: &HeaderMap,
+        now: i64,
+        feed_body: impl FnOnce(&mut [Hmac<Sha256>]) -> Result<(), E>,
+    ) -> Result<Verification, E> {
+        let signed = match self.scheme {
+            Scheme::Github => github(headers),
+            Scheme::Stripe => stripe(headers),
+            Scheme::Slack => slack(headers),
+            Scheme::Shopify => shopify(headers),
+            Scheme::StandardWebhooks => standard_webhooks(headers),
+            Scheme::HmacSha256 => hmac_header(headers, self.header.as_deref()),
+        };
+        let signed = match signed {
+            Err(provided) => {
+                return Ok(Verification {
+                    status: if provided.is_some() {
+                        Status::Invalid
+                    } else {
+                        Status::Missing
+                    },
+                    computed: None,
+                    provided,
+                })
+            }
+            Ok(signed) => signed,
+        };
+
+        let mut macs: Vec<Hmac<Sha256>> = self
+            .secrets
+            .iter()
+            .map(|secret| {
+                let mut mac = new_mac(&self.key(secret));
+                mac.update(&signed.preamble);
+                mac
+            })
+            .collect();
+        feed_body(&mut macs)?;
+        let macs: Vec<Vec<u8>> = macs
+            .into_iter()
+            .map(|mac| mac.finalize().into_bytes().to_vec())
+            .collect();
+        let matching = macs.iter().find(|mac| {
+            signed
+                .candidates
+                .iter()
+                .any(|candidate| bool::from(candidate.as_slice().ct_eq(mac.as_slice())))
+        });
+
+        let status = match (matching, signed.timestamp) {
+            (None, _) => Status::Invalid,
+            (Some(_), Some(timestamp)) if (now - timestamp).abs() > self.tolerance_secs => {
+                Status::Expired
+            }
+            (Some(_), _) => Status::Valid,
+        };
+        let computed = matching.or(macs.first()).map(|mac| signed.render(mac));
+
+        Ok(Verification {
+            status,
+            computed,
+            provided: Some(signed.provided),
+        })
+    }
+
+    /// HMAC key for a secret. Standard Webhooks secrets are base64 with a `whsec_` prefix;
+    /// anything that does not decode is used as-is.
+    fn key(&self, secret: &str) -> Vec<u8> {
+        if self.scheme == Scheme::StandardWebhooks {
+            if let Ok(key) =
+                general_purpose::STANDARD.decode(secret.strip_prefix("whsec_").unwrap_or(secret))
+            {
+                return key;
+            }
+        }
+        secret.as_bytes().to_vec()
+    }
+}
+
+/// How a signature is written in its header
+#[derive(Debug, Clone, Copy)]
+enum Encoding {
+    Hex,
+    Base64,
+}
+
+impl Encoding {
+    fn decode(&self, value: &str) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Hex => hex::decode(value).ok(),
+            Encoding::Base64 => general_purpose::STANDARD.decode(value).ok(),
+        }
+    }
+
+    fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Hex => hex::encode(bytes),
+            Encoding::Base64 => general_purpose::STANDARD.encode(bytes),
+        }
+    }
+}
+
+/// What a request signs and the signatures it claims
+struct Signed {
+    /// Signed bytes that come before the body, e.g. Stripe's "<ts>."
+    preamble: Vec<u8>,
+    /// Decoded signatures; a request may carry several during secret rotation
+    candidates: Vec<Vec<u8>>,
+    provided: String,
+    timestamp: Option<i64>,
+    encoding: Encoding,
+    /// Written before the computed signature, e.g. "sha256="
+    prefix: &'static str,
+}
+
+impl Signed {
+    fn render(&self, mac: &[u8]) -> String {
+        format!("{}{}", self.prefix, self.encoding.encode(mac))
+    }
+}
+
+/// A request that cannot be checked: `Some(value)` when the signature header is present
+/// but malformed, `None` when it is missing
+type Unsigned = Option<String>;
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+}
+
+fn github(headers: &HeaderMap) -> Result<Signed, Unsigned> {
+    let provided = header(headers, "x-hub-signature-256").ok_or(None)?;
+    let candidate = provided
+        .strip_prefix("sha256=")
+        .and_then(|sig| Encoding::Hex.decode(sig))
+        .ok_or_else(|| Some(provided.to_string()))?;
+    Ok(Signed {
+        preamble: Vec::new(),
+        candidates: vec![candidate],
+        provided: provided.to_string(),
+        timestamp: None,
+        encoding: Encoding::Hex,
+        prefix: "sha256=",
+    })
+}
+
+fn stripe(headers: &HeaderMap) -> Result<Signed, Unsigned> {
+    let provided = header(headers, "stripe-signature").ok_or(None)?;
+    let malformed = || Some(provided.to_string());
+
+    let mut timestamp = None;
+    let mut candidates = Vec::new();
+    for (key, value) in provided.split(',').filter_map(|item| item.split_once('=')) {
+        match key.trim() {
+            "t" => timestamp = value.trim().parse::<i64>().ok(),
+            "v1" => candidates.extend(Encoding::Hex.decode(value.trim())),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or_else(malformed)?;
+    if candidates.is_empty() {
+        return Err(malformed());
+    }
+
+    Ok(Signed {
+        preamble: format!("{}.", timestamp).into_bytes(),
+        candidates,
+        provided: provided.to_string(),
+        timestamp: Some(timestamp),
+        encoding: Encoding::Hex,
+        prefix: "v1=",
+    })
+}
+
+fn slack(headers: &HeaderMap) -> Result<Signed, Unsigned> {
+    let provided = header(headers, "x-slack-signature").ok_or(None)?;
+    let malformed = || Some(provided.to_string());
+    let timestamp = header(headers, "x-slack-request-timestamp").ok_or(None)?;
+    let parsed = timestamp.parse::<i64>().map_err(|_| malformed())?;
+    let candidate = provided
+        .strip_prefix("v0=")
+        .and_then(|sig| Encoding::Hex.decode(sig))
+        .ok_or_else(malformed)?;
+
+    Ok(Signed {
+        preamble: format!("v0:{}:", timestamp).into_bytes(),
+        candidates: vec![candidate],
+        provided: provided.to_string(),
+        timestamp: Some(parsed),
+        encoding: Encoding::Hex,
+        prefix: "v0=",
+    })
+}
+
+fn shopify(headers: &HeaderMap) -> Result<Signed, Unsigned> {
+    let provided = header(headers, "x-shopify-hmac-sha256").ok_or(None)?;
+    let candidate = Encoding::Base64
+        .decode(provided)
+        .ok_or_else(|| Some(provided.to_string()))?;
+    Ok(Signed {
+        preamble: Vec::new(),
+        candidates: vec![candidate],
+        provided: provided.to_string(),
+        timestamp: None,
+        encoding: Encoding::Base64,
+        prefix: "",
+    })
+}
+
+fn standard_webhooks(headers: &HeaderMap) -> Result<Signed, Unsigned> {
+    // Svix sends the same headers with its own prefix
+    let prefix = if headers.contains_key("webhook-signature") {
+        "webhook"
+    } else {
+        "svix"
+    };
+    let get = |name: &str| header(headers, &format!("{}-{}", prefix, name));
+
+    let provided = get("signature").ok_or(None)?;
+    let malformed = || Some(provided.to_string());
+    let id = get("id").ok_or(None)?;
+    let timestamp = get("timestamp").ok_or(None)?;
+    let parsed = timestamp.parse::<i64>().map_err(|_| malformed())?;
+    // Space-separated "v1,<base64>" entries, one per active secret
+    let candidates: Vec<Vec<u8>> = provided
+        .split_whitespace()
+        .filter_map(|entry| entry.strip_prefix("v1,"))
+        .filter_map(|sig| Encoding::Base64.decode(sig))
+        .collect();
+    if candidates.is_empty() {
+        return Err(malformed());
+    }
+
+    Ok(Signed {
+        preamble: format!("{}.{}.", id, timestamp).into_bytes(),
+        candidates,
+        provided: provided.to_string(),
+        timestamp: Some(parsed),
+        encoding: Encoding::Base64,
+        prefix: "v1,",
+    })
+}
+
+fn hmac_header(headers: &HeaderMap, name: Option<&str>) -> Result<Signed, Unsigned> {
+    let provided = name.and_then(|name| header(headers, name)).ok_or(None)?;
+    let (prefix, signature) = match provided.strip_prefix("sha256=") {
+        Some(signature) => ("sha256=", signature),
+        None => ("", provided),
+    };
+    // A SHA-256 MAC is 64 hex digits; anything else is read as base64
+    let encoding = if signature.len() == 64 && signature.bytes().all(|b| b.is_ascii_hexdigit()) {
+        Encoding::Hex
+    } else {
+        Encoding::Base64
+    };
+    let candidate = encoding
+        .decode(signature)
+        .ok_or_else(|| Some(provided.to_string()))?;
+
+    Ok(Signed {
+        preamble: Vec::new(),
+        candidates: vec![candidate],
+        provided: provided.to_string(),
+        timestamp: None,
+        encoding,
+        prefix,
+    })
+}
+
+fn new_mac(key: &[u8]) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const NOW: i64 = 1_700_000_000;
+    const BODY: &[u8] = br#"{"event":"ping"}"#;
+
+    fn headers(pairs: &[(&'static str, String)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn verifier(scheme: Scheme, secrets: &[&str]) -> Verifier {
+        let secrets = secrets.iter().map(|s| s.to_string()).collect();
+        Verifier::new(scheme, secrets, Some("X-Signature"), None).unwrap()
+    }
+
+    fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+        let mut mac = new_mac(key);
+        mac.update(message);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    fn sign_hex(secret: &[u8], message: &[u8]) -> String {
+        hex::encode(hmac_sha256(secret, message))
+    }
+
+    #[test]
+    fn test_hmac_sha256_known_answer() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_hex(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_new_validates_configuration() {
+        assert!(Verifier::new(Scheme::Github, vec![], None, None).is_err());
+        assert!(Verifier::new(Scheme::Github, vec![String::new()], None, None).is_err());
+        assert!(Verifier::new(Scheme::Stripe, vec!["s".into()], None, Some(0)).is_err());
+        assert_eq!(
+            Verifier::new(Scheme::HmacSha256, vec!["s".into()], None, None).unwrap_err(),
+            "a signature header is required for hmac-sha256"
+        );
+        assert!(Verifier::new(
+            Scheme::HmacSha256,
+            vec!["s".into()],
+            Some("bad header"),
+            None
+        )
+        .is_err());
+        assert_eq!("svix".parse::<Scheme>(), Ok(Scheme::StandardWebhooks));
+        assert!("sha1".parse::<Scheme>().is_err());
+    }
+
+    #[test]
+    fn test_github() {
+        let verifier = verifier(Scheme::Github, &["old", "new"]);
+        let signature = format!("sha256={}", sign_hex(b"new", BODY));
+
+        let result = verifier.verify(
+            &headers(&[("x-hub-signature-256", signature.clone())]),
+            BODY,
+            NOW,
+        );
+        assert_eq!(result.status, Status::Valid);
+        assert_eq!(result.computed.as_deref(), Some(signature.as_str()));
+        assert_eq!(result.provided.as_deref(), Some(signature.as_str()));
+
+        let result = verifier.verify(
+            &headers(&[("x-hub-signature-256", signature.clone())]),
+            b"tampered",
+            NOW,
+        );
+        assert_eq!(result.status, Status::Invalid);
+        assert_eq!(
+            result.computed,
+            Some(format!("sha256={}", sign_hex(b"old", b"tampered")))
+        );
+
+        let result = verifier.verify(&HeaderMap::new(), BODY, NOW);
+        assert_eq!(result.status, Status::Missing);
+        assert_eq!(result.provided, None);
+    }
+
+    #[test]
+    fn test_stripe() {
+        let verifier = verifier(Scheme::Stripe, &["whsec_test"]);
+        let sign = |t: i64| {
+            let message = [t.to_string().as_bytes(), b".", BODY].concat();
+            format!(
+                "t={},v1={},v0=ignored",
+                t,
+                sign_hex(b"whsec_test", &message)
+            )
+        };
+
+        let result = verifier.verify(&headers(&[("stripe-signature", sign(NOW - 10))]), BODY, NOW);
+        assert_eq!(result.status, Status::Valid);
+        assert!(result.computed.unwrap().starts_with("v1="));
+
+        let result = verifier.verify(
+            &headers(&[("stripe-signature", sign(NOW - 301))]),
+            BODY,
+            NOW,
+        );
+        assert_eq!(result.status, Status::Expired);
+
+        let result = verifier.verify(
+            &headers(&[("stripe-signature", "v1=abc".to_string())]),
+            BODY,
+            NOW,
+        );
+        assert_eq!(result.status, Status::Invalid);
+        assert_eq!(result.provided.as_deref(), Some("v1=abc"));
+    }
+
+    #[test]
+    fn test_verify_file_matches_verify() {
+        let verifier = verifier(Scheme::Stripe, &["whsec_test"]);
+        // Larger than one read so the body is hashed in several chunks
+        let body = vec![b'x'; 200 * 1024];
+        let message = [format!("{}.", NOW).as_bytes(), &body].concat();
+        let signed = headers(&[(
+            "stripe-signature",
+            format!("t={},v1={}", NOW, sign_hex(b"whsec_test", &message)),
+        )]);
+        let path =
+            std::env::temp_dir().join(format!("hookshot_signature_{}.bin", std::process::id()));
+        std::fs::write(&path, &body).unwrap();
+
+        let from_file = verifier.verify_file(&signed, &path, NOW).unwrap();
+        assert_eq!(from_file.status, Status::Valid);
+        assert_eq!(from_file, verifier.verify(&signed, &body, NOW));
+        std::fs::remove_file(&path).unwrap();
+
+        // Without a signature the file is never opened
+        let unsigned = verifier.verify_file(&HeaderMap::new(), &path, NOW).unwrap();
+        assert_eq!(unsigned.status, Status::Missing);
+        assert!(verifier.verify_file(&signed, &path, NOW).is_err());
+    }
+
+    #[test]
+    fn test_slack() {
+        let verifier = verifier(Scheme::Slack, &["8f742231b10e8888abcd99yyyzzz85a5"]);
+        let timestamp = NOW.to_string();
+        let message = [b"v0:", timestamp.as_bytes(), b":", BODY].concat();
+        let signature = format!(
+            "v0={}",
+            sign_hex(b"8f742231b10e8888abcd99yyyzzz85a5", &message)
+        );
+
+        let signed = headers(&[
+            ("x-slack-signature", signature),
+            ("x-slack-request-timestamp", timestamp),
+        ]);
+        assert_eq!(verifier.verify(&signed, BODY, NOW).status, Status::Valid);
+        assert_eq!(
+            verifier.verify(&signed, BODY, NOW + 600).status,
+            Status::Expired
+        );
+    }
+
+    #[test]
+    fn test_shopify() {
+        let verifier = verifier(Scheme::Shopify, &["shpss_secret"]);
+        let signature = general_purpose::STANDARD.encode(hmac_sha256(b"shpss_secret", BODY));
+
+        let result = verifier.verify(
+            &headers(&[("x-shopify-hmac-sha256", signature.clone())]),
+            BODY,
+            NOW,
+        );
+        assert_eq!(result.status, Status::Valid);
+        assert_eq!(result.computed, Some(signature));
+    }
+
+    #[test]
+    fn test_standard_webhooks() {
+        let key = b"standard webhooks key";
+        let secret = format!("whsec_{}", general_purpose::STANDARD.encode(key));
+        let verifier = verifier(Scheme::StandardWebhooks, &[&secret]);
+        let timestamp = NOW.to_string();
+        let message = [b"msg_1.", timestamp.as_bytes(), b".", BODY].concat();
+        let signature = format!(
+            "v1,{} v1,{}",
+            general_purpose::STANDARD.encode([0u8; 32]),
+            general_purpose::STANDARD.encode(hmac_sha256(key, &message))
+        );
+
+        let webhook = headers(&[
+            ("webhook-id", "msg_1".to_string()),
+            ("webhook-timestamp", timestamp.clone()),
+            ("webhook-signature", signature.clone()),
+        ]);
+        assert_eq!(verifier.verify(&webhook, BODY, NOW).status, Status::Valid);
+
+        let svix = headers(&[
+            ("svix-id", "msg_1".to_string()),
+            ("svix-timestamp", timestamp),
+            ("svix-signature", signature),
+        ]);
+        assert_eq!(verifier.verify(&svix, BODY, NOW).status, Status::Valid);
+        assert_eq!(
+            verifier.verify(&svix, BODY, NOW - 1000).status,
+            Status::Expired
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha256_header() {
+        let verifier = verifier(Scheme::HmacSha256, &["secret"]);
+        let mac = hmac_sha256(b"secret", BODY);
+
+        for signature in [
+            hex::encode(&mac),
+            format!("sha256={}", hex::encode(&mac)),
+            general_purpose::STANDARD.encode(&mac),
+        ] {
+            let result =
+                verifier.verify(&headers(&[("x-signature", signature.clone())]), BODY, NOW);
+            assert_eq!(result.status, Status::Valid);
+            // Computed signature is written the way it was provided
+            assert_eq!(result.computed, Some(signature));
+        }
+
+        let result = verifier.verify(
+            &headers(&[("x-signature", "not a signature!".to_string())]),
+            BODY,
+            NOW,
+        );
+        assert_eq!(result.status, Status::Invalid);
+    }
+}
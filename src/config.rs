@@ -0,0 +1,230 @@
+use crate::access::rate_limit::RateLimit;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Default maximum body size kept in the database (10MB)
+pub const DEFAULT_MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Default hard cap for bodies spilled to disk (1GB)
+pub const DEFAULT_MAX_SPILL_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Default number of captured requests that can wait to be written
+pub const DEFAULT_INGEST_QUEUE_SIZE: usize = 10_000;
+
+/// Default number of requests written per transaction
+pub const DEFAULT_INGEST_BATCH_SIZE: usize = 256;
+
+/// Default maximum number of cached endpoint lookups
+pub const DEFAULT_ENDPOINT_CACHE_SIZE: usize = 10_000;
+
+/// What to do with a request body that exceeds the configured limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyOverflow {
+    /// Reject the request with 413 Payload Too Large
+    Reject,
+    /// Keep the first `max_body_size` bytes and discard the rest
+    Truncate,
+    /// Stream the whole body to a file in the spill directory
+    Spill,
+}
+
+impl BodyOverflow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BodyOverflow::Reject => "reject",
+            BodyOverflow::Truncate => "truncate",
+            BodyOverflow::Spill => "spill",
+        }
+    }
+}
+
+impl fmt::Display for BodyOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BodyOverflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "reject" => Ok(BodyOverflow::Reject),
+            "truncate" => Ok(BodyOverflow::Truncate),
+            "spill" => Ok(BodyOverflow::Spill),
+            other => Err(format!(
+                "invalid body overflow mode '{}', expected reject, truncate or spill",
+                other
+            )),
+        }
+    }
+}
+
+/// What the capture path does when the ingest queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backpressure {
+    /// Respond 503 Service Unavailable immediately
+    Reject,
+    /// Wait for room in the queue before responding
+    Block,
+}
+
+impl Backpressure {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Backpressure::Reject => "reject",
+            Backpressure::Block => "block",
+        }
+    }
+}
+
+impl fmt::Display for Backpressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Backpressure {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "reject" => Ok(Backpressure::Reject),
+            "block" => Ok(Backpressure::Block),
+            other => Err(format!(
+                "invalid backpressure mode '{}', expected reject or block",
+                other
+            )),
+        }
+    }
+}
+
+/// Server-wide capture settings, set from the command line.
+/// Per-endpoint settings stored on the `endpoints` row take precedence.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum body size kept in memory and stored in the database, in bytes
+    pub max_body_size: u64,
+    /// How bodies larger than `max_body_size` are handled
+    pub body_overflow: BodyOverflow,
+    /// Directory where spilled bodies are written
+    pub spill_dir: PathBuf,
+    /// Hard cap on a spilled body; anything past it is discarded and the body marked truncated
+    pub max_spill_size: u64,
+    /// Commit each request to the database before responding to the sender
+    pub durable_capture: bool,
+    /// Maximum number of captured requests waiting to be written
+    pub ingest_queue_size: usize,
+    /// Maximum number of requests written per transaction
+    pub ingest_batch_size: usize,
+    /// Behavior when the ingest queue is full
+    pub backpressure: Backpressure,
+    /// Maximum number of endpoint lookups kept in memory, 0 to always read the database
+    pub endpoint_cache_size: usize,
+    /// Proxies whose `Forwarded`, `X-Forwarded-For` and `X-Real-IP` headers are believed
+    pub trusted_proxies: Vec<IpNet>,
+    /// Limit on webhook requests across all endpoints
+    pub global_rate_limit: Option<RateLimit>,
+    /// Limit on requests to each endpoint
+    pub endpoint_rate_limit: Option<RateLimit>,
+    /// Limit on requests from each client IP to an endpoint
+    pub client_rate_limit: Option<RateLimit>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            body_overflow: BodyOverflow::Reject,
+            spill_dir: PathBuf::from("./hookshot-bodies"),
+            max_spill_size: DEFAULT_MAX_SPILL_SIZE,
+            durable_capture: false,
+            ingest_queue_size: DEFAULT_INGEST_QUEUE_SIZE,
+            ingest_batch_size: DEFAULT_INGEST_BATCH_SIZE,
+            backpressure: Backpressure::Block,
+            endpoint_cache_size: DEFAULT_ENDPOINT_CACHE_SIZE,
+            trusted_proxies: Vec::new(),
+            global_rate_limit: None,
+            endpoint_rate_limit: None,
+            client_rate_limit: None,
+        }
+    }
+}
+
+/// Parse a CIDR block, or a single address as a host-sized block
+pub fn parse_ip_net(s: &str) -> Result<IpNet, String> {
+    let s = s.trim();
+    s.parse::<IpNet>()
+        .or_else(|_| s.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("invalid IP address or CIDR block '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_overflow_from_str() {
+        assert_eq!(
+            "reject".parse::<BodyOverflow>().unwrap(),
+            BodyOverflow::Reject
+        );
+        assert_eq!(
+            "Truncate".parse::<BodyOverflow>().unwrap(),
+            BodyOverflow::Truncate
+        );
+        assert_eq!(
+            "SPILL".parse::<BodyOverflow>().unwrap(),
+            BodyOverflow::Spill
+        );
+        assert!("drop".parse::<BodyOverflow>().is_err());
+    }
+
+    #[test]
+    fn test_backpressure_from_str() {
+        assert_eq!(
+            "reject".parse::<Backpressure>().unwrap(),
+            Backpressure::Reject
+        );
+        assert_eq!(
+            "BLOCK".parse::<Backpressure>().unwrap(),
+            Backpressure::Block
+        );
+        assert!("drop".parse::<Backpressure>().is_err());
+    }
+
+    #[test]
+    fn test_parse_ip_net() {
+        assert_eq!(
+            parse_ip_net("10.0.0.0/8").unwrap(),
+            "10.0.0.0/8".parse::<IpNet>().unwrap()
+        );
+        assert_eq!(
+            parse_ip_net(" 192.168.1.10 ").unwrap(),
+            "192.168.1.10/32".parse::<IpNet>().unwrap()
+        );
+        assert_eq!(
+            parse_ip_net("::1").unwrap(),
+            "::1/128".parse::<IpNet>().unwrap()
+        );
+        assert!(parse_ip_net("10.0.0.0/33").is_err());
+        assert!(parse_ip_net("proxy.local").is_err());
+    }
+
+    #[test]
+    fn test_body_overflow_round_trip() {
+        for mode in [
+            BodyOverflow::Reject,
+            BodyOverflow::Truncate,
+            BodyOverflow::Spill,
+        ] {
+            assert_eq!(mode.as_str().parse::<BodyOverflow>().unwrap(), mode);
+        }
+    }
+}
@@ -23,12 +23,136 @@ pub async fn init_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     Ok(pool)
 }
 
-/// Run database migrations
+/// Schema migrations in the order they are applied
+const MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "20240129_initial_schema",
+        include_str!("../../migrations/20240129_initial_schema.sql"),
+    ),
+    (
+        "2026101801_body_limits",
+        include_str!("../../migrations/2026101801_body_limits.sql"),
+    ),
+    (
+        "2026101802_durable_capture",
+        include_str!("../../migrations/2026101802_durable_capture.sql"),
+    ),
+    (
+        "2026101803_raw_headers",
+        include_str!("../../migrations/2026101803_raw_headers.sql"),
+    ),
+    (
+        "2026101804_connection_metadata",
+        include_str!("../../migrations/2026101804_connection_metadata.sql"),
+    ),
+    (
+        "2026101805_client_ip",
+        include_str!("../../migrations/2026101805_client_ip.sql"),
+    ),
+    (
+        "2026101806_protobuf",
+        include_str!("../../migrations/2026101806_protobuf.sql"),
+    ),
+    (
+        "2026101807_provider",
+        include_str!("../../migrations/2026101807_provider.sql"),
+    ),
+    (
+        "2026101808_signatures",
+        include_str!("../../migrations/2026101808_signatures.sql"),
+    ),
+    (
+        "2026101809_jwt",
+        include_str!("../../migrations/2026101809_jwt.sql"),
+    ),
+    (
+        "2026101810_inbound_auth",
+        include_str!("../../migrations/2026101810_inbound_auth.sql"),
+    ),
+    (
+        "2026101811_ip_filter",
+        include_str!("../../migrations/2026101811_ip_filter.sql"),
+    ),
+    (
+        "2026101812_rate_limit",
+        include_str!("../../migrations/2026101812_rate_limit.sql"),
+    ),
+    (
+        "2026101813_json_schema",
+        include_str!("../../migrations/2026101813_json_schema.sql"),
+    ),
+    (
+        "2026101814_duplicates",
+        include_str!("../../migrations/2026101814_duplicates.sql"),
+    ),
+    (
+        "2026101815_response_template",
+        include_str!("../../migrations/2026101815_response_template.sql"),
+    ),
+    (
+        "2026101816_response_rules",
+        include_str!("../../migrations/2026101816_response_rules.sql"),
+    ),
+    (
+        "2026101817_response_sequence",
+        include_str!("../../migrations/2026101817_response_sequence.sql"),
+    ),
+    (
+        "2026101818_response_latency",
+        include_str!("../../migrations/2026101818_response_latency.sql"),
+    ),
+];
+
+/// Run database migrations, skipping the ones already recorded in `schema_migrations`
 async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    // Read migration file
-    let migration_sql = include_str!("../../migrations/20240129_initial_schema.sql");
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version TEXT PRIMARY KEY,
+            applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    for (version, migration_sql) in MIGRATIONS {
+        let applied: Option<(String,)> =
+            sqlx::query_as("SELECT version FROM schema_migrations WHERE version = ?")
+                .bind(version)
+                .fetch_optional(pool)
+                .await?;
+        if applied.is_some() {
+            continue;
+        }
+
+        apply_migration(pool, version, migration_sql).await?;
+        tracing::debug!("Applied migration {}", version);
+    }
+
+    tracing::info!("Database migrations completed successfully");
+    Ok(())
+}
 
-    // Parse and execute SQL statements
+/// Apply a migration script and record it in one transaction, so a failure leaves neither behind
+async fn apply_migration(
+    pool: &SqlitePool,
+    version: &str,
+    migration_sql: &str,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for stmt in split_statements(migration_sql) {
+        sqlx::query(&stmt).execute(&mut *tx).await?;
+    }
+
+    sqlx::query("INSERT INTO schema_migrations (version) VALUES (?)")
+        .bind(version)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// Split a migration script into individual SQL statements
+fn split_statements(migration_sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
     let mut current_statement = String::new();
 
     for line in migration_sql.lines() {
@@ -45,24 +169,23 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             current_statement.push('\n');
         }
 
-        // If line ends with semicolon, execute the statement
+        // If line ends with semicolon, the statement is complete
         if trimmed.ends_with(';') {
             let stmt = current_statement.trim().trim_end_matches(';').trim();
             if !stmt.is_empty() {
-                sqlx::query(stmt).execute(pool).await?;
+                statements.push(stmt.to_string());
             }
             current_statement.clear();
         }
     }
 
-    // Execute any remaining statement
-    if !current_statement.trim().is_empty() {
-        let stmt = current_statement.trim().trim_end_matches(';').trim();
-        sqlx::query(stmt).execute(pool).await?;
+    // Keep any remaining statement
+    let stmt = current_statement.trim().trim_end_matches(';').trim();
+    if !stmt.is_empty() {
+        statements.push(stmt.to_string());
     }
 
-    tracing::info!("Database migrations completed successfully");
-    Ok(())
+    statements
 }
 
 #[cfg(test)]
@@ -93,6 +216,67 @@ mod tests {
         assert!(result.is_ok(), "Requests table should exist");
     }
 
+    #[tokio::test]
+    async fn test_migrations_are_recorded_once() {
+        // Use a file database so the pool can be re-opened against the same schema
+        let temp_file = format!("sqlite:/tmp/test_migrations_{}.db", std::process::id());
+        let pool = init_pool(&temp_file).await.unwrap();
+        drop(pool);
+
+        // Re-running migrations must not re-apply ALTER TABLE statements
+        let pool = init_pool(&temp_file).await.unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_migrations")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count as usize, MIGRATIONS.len());
+
+        drop(pool);
+        let path = temp_file.replace("sqlite:", "");
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(format!("{}-wal", path));
+        let _ = std::fs::remove_file(format!("{}-shm", path));
+    }
+
+    #[tokio::test]
+    async fn test_failed_migration_is_rolled_back() {
+        // Each in-memory connection is its own database, so use a file
+        let temp_file = format!("sqlite:/tmp/test_rollback_{}.db", std::process::id());
+        let pool = init_pool(&temp_file).await.unwrap();
+
+        let sql = "ALTER TABLE endpoints ADD COLUMN half_done TEXT;\nALTER TABLE missing ADD COLUMN x TEXT;";
+        assert!(apply_migration(&pool, "broken", sql).await.is_err());
+
+        let columns: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('endpoints') WHERE name = 'half_done'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(columns, 0);
+        let recorded: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM schema_migrations WHERE version = 'broken'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(recorded, 0);
+
+        drop(pool);
+        let path = temp_file.replace("sqlite:", "");
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(format!("{}-wal", path));
+        let _ = std::fs::remove_file(format!("{}-shm", path));
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let sql = "-- comment\nCREATE TABLE a (\n  id INTEGER -- inline\n);\n\nALTER TABLE a ADD COLUMN b TEXT;";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].starts_with("CREATE TABLE a"));
+        assert_eq!(statements[1], "ALTER TABLE a ADD COLUMN b TEXT");
+    }
+
     #[tokio::test]
     async fn test_wal_mode() {
         // Use a temporary file database for this test since :memory: doesn't support WAL
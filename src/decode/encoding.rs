@@ -0,0 +1,205 @@
+use std::io::Read;
+
+/// Largest body produced by decoding; anything past it is dropped to guard against
+/// decompression bombs
+pub const MAX_DECODED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Largest body decoded for each request of a list page, which holds up to 100 of them.
+/// The full body is decoded, up to `MAX_DECODED_SIZE`, when one request is fetched.
+pub const MAX_LIST_DECODED_SIZE: usize = 256 * 1024;
+
+/// Result of removing a Content-Encoding
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Decoded {
+    pub bytes: Vec<u8>,
+    /// Output stopped at the size cap
+    pub truncated: bool,
+    /// Decoding failed; `bytes` holds whatever was decoded before the failure
+    pub error: Option<String>,
+}
+
+/// Normalized codings from a Content-Encoding header value, in the order they were applied.
+/// `identity` is dropped.
+pub fn parse_codings(header: &str) -> Vec<String> {
+    header
+        .split(',')
+        .map(|coding| coding.trim().to_ascii_lowercase())
+        .filter(|coding| !coding.is_empty() && coding != "identity")
+        .map(|coding| match coding.as_str() {
+            "x-gzip" => "gzip".to_string(),
+            _ => coding,
+        })
+        .collect()
+}
+
+/// Undo `codings` (in the order they were applied), capping every step at `max_size` bytes
+pub fn decode(body: &[u8], codings: &[String], max_size: usize) -> Decoded {
+    let mut decoded = Decoded {
+        bytes: body.to_vec(),
+        ..Default::default()
+    };
+
+    for coding in codings.iter().rev() {
+        let step = decode_one(&decoded.bytes, coding, max_size);
+        decoded.bytes = step.bytes;
+        decoded.truncated |= step.truncated;
+        if step.error.is_some() {
+            decoded.error = step.error;
+            break;
+        }
+    }
+
+    decoded
+}
+
+fn decode_one(body: &[u8], coding: &str, max_size: usize) -> Decoded {
+    match coding {
+        "gzip" => read_capped(flate2::read::MultiGzDecoder::new(body), max_size),
+        "deflate" => {
+            // HTTP deflate is zlib-wrapped, but raw deflate streams are common in practice
+            let zlib = read_capped(flate2::read::ZlibDecoder::new(body), max_size);
+            if zlib.error.is_some() && zlib.bytes.is_empty() {
+                read_capped(flate2::read::DeflateDecoder::new(body), max_size)
+            } else {
+                zlib
+            }
+        }
+        "br" => read_capped(brotli_decompressor::Decompressor::new(body, 4096), max_size),
+        "zstd" => match ruzstd::decoding::StreamingDecoder::new(body) {
+            Ok(decoder) => read_capped(decoder, max_size),
+            Err(e) => failed(format!("invalid zstd frame: {}", e)),
+        },
+        other => failed(format!("unsupported content encoding '{}'", other)),
+    }
+}
+
+/// Read a decoder to the end or until `max_size` bytes, keeping partial output on error
+fn read_capped<R: Read>(mut reader: R, max_size: usize) -> Decoded {
+    let mut decoded = Decoded::default();
+    let mut chunk = [0u8; 8192];
+
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => {
+                let room = max_size - decoded.bytes.len();
+                if n > room {
+                    decoded.bytes.extend_from_slice(&chunk[..room]);
+                    decoded.truncated = true;
+                    break;
+                }
+                decoded.bytes.extend_from_slice(&chunk[..n]);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                decoded.error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+
+    decoded
+}
+
+fn failed(error: String) -> Decoded {
+    Decoded {
+        error: Some(error),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const TEXT: &[u8] = b"hello hello hello hello hello hello";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn codings(header: &str) -> Vec<String> {
+        parse_codings(header)
+    }
+
+    #[test]
+    fn test_parse_codings() {
+        assert_eq!(codings("x-gzip, identity, BR"), ["gzip", "br"]);
+        assert!(codings("identity").is_empty());
+    }
+
+    #[test]
+    fn test_decode_gzip_and_deflate() {
+        assert_eq!(decode(&gzip(TEXT), &codings("gzip"), 1024).bytes, TEXT);
+
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(TEXT).unwrap();
+        assert_eq!(
+            decode(&zlib.finish().unwrap(), &codings("deflate"), 1024).bytes,
+            TEXT
+        );
+
+        let mut raw =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        raw.write_all(TEXT).unwrap();
+        assert_eq!(
+            decode(&raw.finish().unwrap(), &codings("deflate"), 1024).bytes,
+            TEXT
+        );
+    }
+
+    #[test]
+    fn test_decode_brotli_and_zstd() {
+        let mut brotli = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        brotli.write_all(TEXT).unwrap();
+        assert_eq!(
+            decode(&brotli.into_inner(), &codings("br"), 1024).bytes,
+            TEXT
+        );
+
+        let zstd =
+            ruzstd::encoding::compress_to_vec(TEXT, ruzstd::encoding::CompressionLevel::Fastest);
+        assert_eq!(decode(&zstd, &codings("zstd"), 1024).bytes, TEXT);
+    }
+
+    #[test]
+    fn test_decode_stacked_codings() {
+        let mut brotli = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        brotli.write_all(&gzip(TEXT)).unwrap();
+
+        // gzip was applied first, then br
+        let decoded = decode(&brotli.into_inner(), &codings("gzip, br"), 1024);
+        assert_eq!(decoded.bytes, TEXT);
+        assert!(decoded.error.is_none());
+    }
+
+    #[test]
+    fn test_decode_caps_output() {
+        let bomb = gzip(&vec![0u8; 1024 * 1024]);
+        let decoded = decode(&bomb, &codings("gzip"), 1000);
+
+        assert_eq!(decoded.bytes.len(), 1000);
+        assert!(decoded.truncated);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let decoded = decode(b"not gzip", &codings("gzip"), 1024);
+        assert!(decoded.error.is_some());
+
+        let decoded = decode(TEXT, &codings("compress"), 1024);
+        assert_eq!(
+            decoded.error.as_deref(),
+            Some("unsupported content encoding 'compress'")
+        );
+
+        // A truncated stream keeps what was decoded
+        let gzipped = gzip(TEXT);
+        let decoded = decode(&gzipped[..gzipped.len() - 8], &codings("gzip"), 1024);
+        assert_eq!(decoded.bytes, TEXT);
+        assert!(decoded.error.is_some());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::models::RawHeader;
+use std::ops::Range;
+
+/// Most headers accepted on a single multipart part
+const MAX_PART_HEADERS: usize = 32;
+
+/// One part of a `multipart/form-data` body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub headers: Vec<RawHeader>,
+    pub name: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    /// Location of the part's content within the body
+    pub data: Range<usize>,
+}
+
+/// Media type of a Content-Type value, lowercased and without parameters
+pub fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Boundary of a `multipart/form-data` Content-Type, or `None` for any other type
+pub fn multipart_boundary(content_type: &str) -> Option<String> {
+    if media_type(content_type) != "multipart/form-data" {
+        return None;
+    }
+    let (_, params) = content_type.split_once(';')?;
+    parameter(params, "boundary").filter(|boundary| !boundary.is_empty())
+}
+
+/// Split a `multipart/form-data` body into its parts (RFC 7578)
+pub fn parse_multipart(body: &[u8], boundary: &str) -> Result<Vec<Part>, String> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+    let mut pos = find(body, delimiter, 0).ok_or("multipart boundary not found")? + delimiter.len();
+    let mut parts = Vec::new();
+
+    loop {
+        // The close delimiter ends the body; anything after it is epilogue
+        if body[pos..].starts_with(b"--") {
+            return Ok(parts);
+        }
+        while matches!(body.get(pos), Some(b' ' | b'\t')) {
+            pos += 1;
+        }
+        pos += line_break(&body[pos..]).ok_or("malformed multipart boundary line")?;
+
+        let mut headers = [httparse::EMPTY_HEADER; MAX_PART_HEADERS];
+        let (head_len, headers) = match httparse::parse_headers(&body[pos..], &mut headers) {
+            Ok(httparse::Status::Complete((len, headers))) => (len, headers),
+            Ok(httparse::Status::Partial) => return Err("truncated multipart part headers".into()),
+            Err(e) => return Err(format!("invalid multipart part headers: {}", e)),
+        };
+        let headers: Vec<RawHeader> = headers
+            .iter()
+            .map(|header| RawHeader {
+                name: header.name.to_string(),
+                value: header.value.to_vec(),
+            })
+            .collect();
+        pos += head_len;
+
+        let end = find_delimiter(body, delimiter, pos).ok_or("unterminated multipart part")?;
+        parts.push(part(headers, pos..end.start));
+        pos = end.end;
+    }
+}
+
+/// Build a part, reading its name and filename from Content-Disposition
+fn part(headers: Vec<RawHeader>, data: Range<usize>) -> Part {
+    let header_value = |name: &str| {
+        headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| String::from_utf8_lossy(&header.value).trim().to_string())
+    };
+    let disposition = header_value("content-disposition").unwrap_or_default();
+    let params = disposition.split_once(';').map_or("", |(_, params)| params);
+
+    Part {
+        name: parameter(params, "name"),
+        filename: parameter(params, "filename"),
+        content_type: header_value("content-type"),
+        headers,
+        data,
+    }
+}
+
+/// Value of a `;`-separated header parameter, unquoting quoted strings
+fn parameter(params: &str, name: &str) -> Option<String> {
+    let mut rest = params;
+    loop {
+        rest = rest.trim_start_matches([';', ' ', '\t']);
+        if rest.is_empty() {
+            return None;
+        }
+
+        let key_end = rest.find(['=', ';']).unwrap_or(rest.len());
+        let key = rest[..key_end].trim();
+        rest = &rest[key_end..];
+        let value = match rest.strip_prefix('=') {
+            Some(value) => {
+                let (value, remainder) = parameter_value(value.trim_start());
+                rest = remainder;
+                value
+            }
+            None => String::new(),
+        };
+
+        if key.eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+    }
+}
+
+/// Read a token or quoted string, returning it with the unread remainder
+fn parameter_value(input: &str) -> (String, &str) {
+    let Some(quoted) = input.strip_prefix('"') else {
+        let end = input.find(';').unwrap_or(input.len());
+        return (input[..end].trim().to_string(), &input[end..]);
+    };
+
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (value, &quoted[i + 1..]),
+            '\\' => {
+                if let Some((_, escaped)) = chars.next() {
+                    value.push(escaped);
+                }
+            }
+            _ => value.push(c),
+        }
+    }
+    (value, "")
+}
+
+/// Find the line break and delimiter that end a part's content, returning the range
+/// to skip. Bare LF line breaks are accepted from sloppy clients.
+fn find_delimiter(body: &[u8], delimiter: &[u8], from: usize) -> Option<Range<usize>> {
+    let mut search = from;
+    loop {
+        let start = find(body, delimiter, search)?;
+        if body[..start].ends_with(b"\r\n") && start - 2 >= from {
+            return Some(start - 2..start + delimiter.len());
+        }
+        if body[..start].ends_with(b"\n") && start > from {
+            return Some(start - 1..start + delimiter.len());
+        }
+        search = start + 1;
+    }
+}
+
+fn line_break(input: &[u8]) -> Option<usize> {
+    if input.starts_with(b"\r\n") {
+        Some(2)
+    } else if input.starts_with(b"\n") {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| from + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"preamble\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        Hello\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"upload\"; filename=\"a \\\"b\\\".txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        line one\r\nline two\r\n\
+        --XyZ--\r\n\
+        epilogue";
+
+    #[test]
+    fn test_multipart_boundary() {
+        assert_eq!(
+            multipart_boundary("multipart/form-data; boundary=XyZ").as_deref(),
+            Some("XyZ")
+        );
+        assert_eq!(
+            multipart_boundary("Multipart/Form-Data; charset=utf-8; boundary=\"a;b\"").as_deref(),
+            Some("a;b")
+        );
+        assert!(multipart_boundary("application/json; boundary=XyZ").is_none());
+        assert!(multipart_boundary("multipart/form-data").is_none());
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let parts = parse_multipart(BODY, "XyZ").unwrap();
+        assert_eq!(parts.len(), 2);
+
+        assert_eq!(parts[0].name.as_deref(), Some("title"));
+        assert_eq!(parts[0].filename, None);
+        assert_eq!(&BODY[parts[0].data.clone()], b"Hello");
+
+        assert_eq!(parts[1].name.as_deref(), Some("upload"));
+        assert_eq!(parts[1].filename.as_deref(), Some("a \"b\".txt"));
+        assert_eq!(parts[1].content_type.as_deref(), Some("text/plain"));
+        assert_eq!(parts[1].headers.len(), 2);
+        assert_eq!(parts[1].headers[1].name, "Content-Type");
+        assert_eq!(&BODY[parts[1].data.clone()], b"line one\r\nline two");
+    }
+
+    #[test]
+    fn test_parse_multipart_empty_and_bare_lf() {
+        let body = b"--b\n\n\n--b\nContent-Disposition: form-data; name=x\n\nv\n--b--";
+        let parts = parse_multipart(body, "b").unwrap();
+
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].headers.is_empty());
+        assert!(parts[0].data.is_empty());
+        assert_eq!(parts[1].name.as_deref(), Some("x"));
+        assert_eq!(&body[parts[1].data.clone()], b"v");
+    }
+
+    #[test]
+    fn test_parse_multipart_errors() {
+        assert!(parse_multipart(b"no boundary here", "XyZ").is_err());
+        let hello = BODY.windows(5).position(|w| w == b"Hello").unwrap();
+        assert_eq!(
+            parse_multipart(&BODY[..hello + 3], "XyZ").unwrap_err(),
+            "unterminated multipart part"
+        );
+        assert_eq!(
+            parse_multipart(&BODY[..hello - 10], "XyZ").unwrap_err(),
+            "truncated multipart part headers"
+        );
+    }
+}
@@ -0,0 +1,300 @@
+pub mod encoding;
+pub mod form;
+pub mod params;
+pub mod protobuf;
+pub mod structured;
+
+use crate::models::{body_to_string, DecodedBody, FormPart, ProtobufBody, StructuredBody};
+use protobuf::ProtobufDecoder;
+
+/// Content-Encoding of a request, read from its stored headers map
+pub fn content_encoding(headers: &serde_json::Value) -> Option<String> {
+    let value = match headers.get("content-encoding")? {
+        serde_json::Value::String(value) => value.clone(),
+        serde_json::Value::Array(values) => values
+            .iter()
+            .filter_map(|v| v.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Read-time views of a captured body. Decoding happens on read so the stored body
+/// stays exactly as sent.
+#[derive(Debug, Default)]
+pub struct BodyViews {
+    /// Body with its Content-Encoding removed, when one was applied
+    pub decoded: Option<DecodedBody>,
+    /// Fields of an `application/x-www-form-urlencoded` body, structured like `query_params`
+    pub form_params: Option<serde_json::Value>,
+    /// Parts of a `multipart/form-data` body
+    pub parts: Option<Vec<FormPart>>,
+    /// Protobuf body rendered with the endpoint's descriptor set
+    pub protobuf: Option<ProtobufBody>,
+    /// MessagePack, CBOR or XML body rendered as JSON
+    pub structured: Option<StructuredBody>,
+}
+
+/// Build the read-time views of a body from the request's headers map and Content-Type,
+/// decoding at most `max_decoded` bytes. Decoding is CPU-bound, so async callers should
+/// run this on a blocking thread.
+pub fn body_views(
+    headers: &serde_json::Value,
+    content_type: Option<&str>,
+    body: Option<&[u8]>,
+    protobuf: Option<&ProtobufDecoder>,
+    max_decoded: usize,
+) -> BodyViews {
+    let Some(body) = body else {
+        return BodyViews::default();
+    };
+    let decoded = remove_encoding(headers, body, max_decoded);
+    let content = decoded.as_ref().map_or(body, |(_, decoded)| &decoded.bytes);
+
+    let mut views = BodyViews {
+        protobuf: protobuf.and_then(|decoder| decoder.decode(headers, content_type, content)),
+        ..Default::default()
+    };
+    if let Some(content_type) = content_type {
+        let media_type = form::media_type(content_type);
+        if media_type == "application/x-www-form-urlencoded" {
+            views.form_params = Some(params::structure(&params::parse_pairs(content)));
+        } else if let Some(boundary) = form::multipart_boundary(content_type) {
+            views.parts = form::parse_multipart(content, &boundary)
+                .ok()
+                .map(|parts| parts.iter().map(|part| form_part(content, part)).collect());
+        } else if let Some(format) = structured::Format::from_media_type(&media_type) {
+            views.structured = Some(structured_body(format, content));
+        }
+    }
+
+    views.decoded = decoded.map(|(codings, decoded)| DecodedBody {
+        encoding: codings.join(", "),
+        size: decoded.bytes.len() as i64,
+        body: (!decoded.bytes.is_empty()).then(|| body_to_string(decoded.bytes)),
+        truncated: decoded.truncated,
+        error: decoded.error,
+    });
+    views
+}
+
+/// Body bytes as the sender's application produced them, with any Content-Encoding removed.
+/// A body that fails to decode is returned as sent.
+pub fn content_bytes(headers: &serde_json::Value, body: Vec<u8>) -> Vec<u8> {
+    match content_encoding(headers) {
+        Some(value) => decode_content(&value, body).unwrap_or_else(|(body, _)| body),
+        None => body,
+    }
+}
+
+/// Remove the codings of a Content-Encoding header value from a body, up to
+/// `MAX_DECODED_SIZE` bytes. A body that fails to decode is handed back as sent, with the
+/// reason. Decoding is CPU-bound, so async callers should run this on a blocking thread.
+pub fn decode_content(content_encoding: &str, body: Vec<u8>) -> Result<Vec<u8>, (Vec<u8>, String)> {
+    let codings = encoding::parse_codings(content_encoding);
+    if codings.is_empty() {
+        return Ok(body);
+    }
+    let decoded = encoding::decode(&body, &codings, encoding::MAX_DECODED_SIZE);
+    match decoded.error {
+        Some(error) => Err((body, error)),
+        None => Ok(decoded.bytes),
+    }
+}
+
+fn remove_encoding(
+    headers: &serde_json::Value,
+    body: &[u8],
+    max_decoded: usize,
+) -> Option<(Vec<String>, encoding::Decoded)> {
+    let codings = encoding::parse_codings(&content_encoding(headers)?);
+    if codings.is_empty() {
+        return None;
+    }
+    let decoded = encoding::decode(body, &codings, max_decoded);
+    Some((codings, decoded))
+}
+
+fn structured_body(format: structured::Format, content: &[u8]) -> StructuredBody {
+    let (json, error) = match format.decode(content) {
+        Ok(json) => (Some(json), None),
+        Err(error) => (None, Some(error)),
+    };
+    StructuredBody {
+        format: format.as_str().to_string(),
+        json,
+        error,
+    }
+}
+
+fn form_part(content: &[u8], part: &form::Part) -> FormPart {
+    let data = &content[part.data.clone()];
+    FormPart {
+        name: part.name.clone(),
+        filename: part.filename.clone(),
+        content_type: part.content_type.clone(),
+        size: data.len() as i64,
+        headers: part.headers.clone(),
+        // File contents are downloaded from /api/requests/:id/parts/:n instead
+        value: part
+            .filename
+            .is_none()
+            .then(|| std::str::from_utf8(data).ok().map(str::to_string))
+            .flatten(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_decode_body_from_headers() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(br#"{"event":"push"}"#).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let headers = serde_json::json!({"content-encoding": "GZIP"});
+        let decoded = body_views(
+            &headers,
+            None,
+            Some(&gzipped),
+            None,
+            encoding::MAX_DECODED_SIZE,
+        )
+        .decoded
+        .unwrap();
+
+        assert_eq!(decoded.encoding, "gzip");
+        assert_eq!(decoded.body.as_deref(), Some(r#"{"event":"push"}"#));
+        assert_eq!(decoded.size, 16);
+        assert!(!decoded.truncated);
+        assert!(decoded.error.is_none());
+
+        // Lists decode less of each body
+        let decoded = body_views(&headers, None, Some(&gzipped), None, 8)
+            .decoded
+            .unwrap();
+        assert_eq!(decoded.body.as_deref(), Some(r#"{"event""#));
+        assert!(decoded.truncated);
+    }
+
+    #[test]
+    fn test_decode_body_not_encoded() {
+        let body = b"plain".as_slice();
+        let decoded = |headers| {
+            body_views(&headers, None, Some(body), None, encoding::MAX_DECODED_SIZE).decoded
+        };
+        assert!(decoded(serde_json::json!({})).is_none());
+        assert!(decoded(serde_json::json!({"content-encoding": "identity"})).is_none());
+
+        let headers = serde_json::json!({"content-encoding": "gzip"});
+        assert!(
+            body_views(&headers, None, None, None, encoding::MAX_DECODED_SIZE)
+                .decoded
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_decode_content_falls_back_to_body() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"hello").unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        assert_eq!(
+            decode_content("gzip", gzipped.clone()),
+            Ok(b"hello".to_vec())
+        );
+        assert_eq!(
+            decode_content("identity", b"plain".to_vec()),
+            Ok(b"plain".to_vec())
+        );
+
+        let (body, error) = decode_content("compress", gzipped.clone()).unwrap_err();
+        assert_eq!(body, gzipped);
+        assert!(error.contains("compress"), "{}", error);
+        // Bytes a failed decode produced are not passed off as the body
+        let headers = serde_json::json!({"content-encoding": "gzip"});
+        assert_eq!(
+            content_bytes(&headers, gzipped[..12].to_vec()),
+            gzipped[..12]
+        );
+    }
+
+    #[test]
+    fn test_form_views() {
+        let headers = serde_json::json!({});
+        let views = body_views(
+            &headers,
+            Some("application/x-www-form-urlencoded; charset=utf-8"),
+            Some(b"a=1&b=two+words&a=2"),
+            None,
+            encoding::MAX_DECODED_SIZE,
+        );
+        assert_eq!(
+            views.form_params,
+            Some(serde_json::json!({"a": ["1", "2"], "b": "two words"}))
+        );
+        assert!(views.parts.is_none());
+
+        let body = b"--b\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nhi\r\n\
+            --b\r\nContent-Disposition: form-data; name=\"f\"; filename=\"x.bin\"\r\n\r\n\x00\xff\r\n\
+            --b--";
+        let parts = body_views(
+            &headers,
+            Some("multipart/form-data; boundary=b"),
+            Some(body),
+            None,
+            encoding::MAX_DECODED_SIZE,
+        )
+        .parts
+        .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].value.as_deref(), Some("hi"));
+        assert_eq!(parts[1].filename.as_deref(), Some("x.bin"));
+        assert_eq!(parts[1].size, 2);
+        assert_eq!(parts[1].value, None);
+    }
+
+    #[test]
+    fn test_structured_view() {
+        let headers = serde_json::json!({});
+        let views = body_views(
+            &headers,
+            Some("text/xml; charset=utf-8"),
+            Some(b"<ping seq=\"1\"/>"),
+            None,
+            encoding::MAX_DECODED_SIZE,
+        );
+        let structured = views.structured.unwrap();
+        assert_eq!(structured.format, "xml");
+        assert_eq!(
+            structured.json,
+            Some(serde_json::json!({"ping": {"@seq": "1"}}))
+        );
+
+        let views = body_views(
+            &headers,
+            Some("application/cbor"),
+            Some(b"\xff"),
+            None,
+            encoding::MAX_DECODED_SIZE,
+        );
+        let structured = views.structured.unwrap();
+        assert!(structured.json.is_none());
+        assert!(structured.error.is_some());
+
+        let views = body_views(
+            &headers,
+            Some("application/json"),
+            Some(b"{}"),
+            None,
+            encoding::MAX_DECODED_SIZE,
+        );
+        assert!(views.structured.is_none());
+    }
+}
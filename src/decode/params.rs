@@ -0,0 +1,186 @@
+use crate::models::QueryPair;
+use serde_json::{Map, Value};
+
+/// Deepest bracket nesting expanded into objects; deeper keys are kept flat
+const MAX_DEPTH: usize = 8;
+
+/// Decode `application/x-www-form-urlencoded` data (a query string or form body)
+/// into its name/value pairs, in the order they were sent
+pub fn parse_pairs(input: &[u8]) -> Vec<QueryPair> {
+    form_urlencoded::parse(input)
+        .map(|(name, value)| QueryPair {
+            name: name.into_owned(),
+            value: value.into_owned(),
+        })
+        .collect()
+}
+
+/// Structured view of decoded pairs: a repeated name becomes an array of its values,
+/// `name[]` always collects an array, and bracket notation like `filter[status]` becomes
+/// nested objects. A name that conflicts with an earlier one (`a=1&a[b]=2`) or whose
+/// brackets are malformed is kept as a flat key.
+pub fn structure(pairs: &[QueryPair]) -> Value {
+    let mut root = Map::new();
+    for pair in pairs {
+        let inserted =
+            key_path(&pair.name).is_some_and(|path| insert(&mut root, &path, &pair.value).is_ok());
+        if !inserted {
+            let flat = [Segment::Key(pair.name.clone())];
+            let _ = insert(&mut root, &flat, &pair.value);
+        }
+    }
+    Value::Object(root)
+}
+
+/// One step of a bracketed name
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    /// `[]`: append to an array
+    Push,
+}
+
+/// Split `a[b][c]` into its segments, or `None` when the name should be kept flat
+fn key_path(name: &str) -> Option<Vec<Segment>> {
+    let Some(open) = name.find('[') else {
+        return Some(vec![Segment::Key(name.to_string())]);
+    };
+    if open == 0 {
+        return None;
+    }
+
+    let mut path = vec![Segment::Key(name[..open].to_string())];
+    let mut rest = &name[open..];
+    while !rest.is_empty() {
+        let inner = rest.strip_prefix('[')?;
+        let close = inner.find(']')?;
+        let key = &inner[..close];
+        // Only a trailing `[]` is supported; `a[][b]` is kept flat
+        if path.last() == Some(&Segment::Push) || key.contains('[') {
+            return None;
+        }
+        path.push(if key.is_empty() {
+            Segment::Push
+        } else {
+            Segment::Key(key.to_string())
+        });
+        rest = &inner[close + 1..];
+    }
+
+    (path.len() <= MAX_DEPTH + 1).then_some(path)
+}
+
+/// Insert a value at `path`, failing without changes if an existing value is in the way
+fn insert(map: &mut Map<String, Value>, path: &[Segment], value: &str) -> Result<(), ()> {
+    let (Segment::Key(key), rest) = path.split_first().ok_or(())? else {
+        return Err(());
+    };
+
+    match rest.first() {
+        None => match map.get_mut(key) {
+            None => {
+                map.insert(key.clone(), Value::String(value.to_string()));
+            }
+            Some(existing) => append(existing, value)?,
+        },
+        Some(Segment::Push) => match map.get_mut(key) {
+            None => {
+                map.insert(key.clone(), Value::Array(vec![value.into()]));
+            }
+            Some(existing) => append(existing, value)?,
+        },
+        Some(Segment::Key(_)) => {
+            let child = map
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            match child {
+                Value::Object(child) => insert(child, rest, value)?,
+                _ => return Err(()),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add another value for a name that was already seen
+fn append(existing: &mut Value, value: &str) -> Result<(), ()> {
+    match existing {
+        Value::Array(values) => values.push(value.into()),
+        Value::String(first) => {
+            *existing = Value::Array(vec![std::mem::take(first).into(), value.into()]);
+        }
+        _ => return Err(()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn structured(query: &str) -> Value {
+        structure(&parse_pairs(query.as_bytes()))
+    }
+
+    #[test]
+    fn test_parse_pairs_keeps_order_and_repeats() {
+        let pairs = parse_pairs(b"b=2&a=1&b=3&flag&sp=a+b%21");
+        let pairs: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|p| (p.name.as_str(), p.value.as_str()))
+            .collect();
+
+        assert_eq!(
+            pairs,
+            [
+                ("b", "2"),
+                ("a", "1"),
+                ("b", "3"),
+                ("flag", ""),
+                ("sp", "a b!")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeated_keys_become_arrays() {
+        assert_eq!(
+            structured("tag=a&tag=b&tag=c&one=1&list[]=x"),
+            json!({"tag": ["a", "b", "c"], "one": "1", "list": ["x"]})
+        );
+    }
+
+    #[test]
+    fn test_brackets_become_nested_objects() {
+        assert_eq!(
+            structured("filter[status]=open&filter[owner][name]=sam&filter[labels][]=bug&filter[labels][]=ui"),
+            json!({
+                "filter": {
+                    "status": "open",
+                    "owner": {"name": "sam"},
+                    "labels": ["bug", "ui"]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_conflicts_and_malformed_names_stay_flat() {
+        assert_eq!(
+            structured("a=1&a[b]=2&[x]=3&c[d=4&e[][f]=5&g[h]x=6"),
+            json!({
+                "a": "1",
+                "a[b]": "2",
+                "[x]": "3",
+                "c[d": "4",
+                "e[][f]": "5",
+                "g[h]x": "6"
+            })
+        );
+
+        let deep = format!("k{}=v", "[x]".repeat(MAX_DEPTH + 1));
+        let flat_key = deep.trim_end_matches("=v");
+        assert_eq!(structured(&deep), json!({ flat_key: "v" }));
+    }
+}
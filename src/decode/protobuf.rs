@@ -0,0 +1,227 @@
+use crate::models::ProtobufBody;
+use prost_reflect::{DescriptorPool, DynamicMessage};
+
+/// Decodes protobuf bodies with an endpoint's uploaded `FileDescriptorSet`
+#[derive(Debug, Clone)]
+pub struct ProtobufDecoder {
+    pool: DescriptorPool,
+    /// Message type used when the request does not name one
+    message_type: Option<String>,
+    /// Lowercased header whose value names the message type of each request
+    type_header: Option<String>,
+}
+
+impl ProtobufDecoder {
+    /// Build a decoder from a compiled descriptor set. At least one of `message_type`
+    /// and `type_header` is required, and `message_type` must exist in the set.
+    pub fn new(
+        descriptor_set: &[u8],
+        message_type: Option<&str>,
+        type_header: Option<&str>,
+    ) -> Result<Self, String> {
+        let pool = DescriptorPool::decode(descriptor_set)
+            .map_err(|e| format!("invalid descriptor set: {}", e))?;
+
+        if message_type.is_none() && type_header.is_none() {
+            return Err("a message type or a type header is required".to_string());
+        }
+        if let Some(name) = message_type {
+            if pool.get_message_by_name(name).is_none() {
+                return Err(format!(
+                    "message type '{}' not found in descriptor set",
+                    name
+                ));
+            }
+        }
+
+        Ok(Self {
+            pool,
+            message_type: message_type.map(str::to_string),
+            type_header: type_header.map(str::to_ascii_lowercase),
+        })
+    }
+
+    /// Decode a captured body, or `None` when the request is not protobuf: it neither
+    /// has a protobuf Content-Type nor names a message type in the type header.
+    pub fn decode(
+        &self,
+        headers: &serde_json::Value,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Option<ProtobufBody> {
+        let named = self
+            .type_header
+            .as_deref()
+            .and_then(|name| header_value(headers, name))
+            // Accept type URLs such as "type.googleapis.com/acme.Event"
+            .map(|value| {
+                value
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            });
+        let is_protobuf =
+            content_type.is_some_and(|ct| super::form::media_type(ct).contains("protobuf"));
+        if named.is_none() && !is_protobuf {
+            return None;
+        }
+
+        let Some(message_type) = named.or_else(|| self.message_type.clone()) else {
+            return Some(ProtobufBody {
+                message_type: None,
+                json: None,
+                error: Some("request does not name a message type".to_string()),
+            });
+        };
+
+        let result = match self.pool.get_message_by_name(&message_type) {
+            Some(descriptor) => DynamicMessage::decode(descriptor, body)
+                .map_err(|e| format!("failed to decode {}: {}", message_type, e))
+                .and_then(|message| serde_json::to_value(&message).map_err(|e| e.to_string())),
+            None => Err(format!(
+                "message type '{}' not found in descriptor set",
+                message_type
+            )),
+        };
+
+        Some(match result {
+            Ok(json) => ProtobufBody {
+                message_type: Some(message_type),
+                json: Some(json),
+                error: None,
+            },
+            Err(error) => ProtobufBody {
+                message_type: Some(message_type),
+                json: None,
+                error: Some(error),
+            },
+        })
+    }
+}
+
+/// First value of a header in the stored headers map
+fn header_value(headers: &serde_json::Value, name: &str) -> Option<String> {
+    match headers.get(name)? {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Array(values) => values.first()?.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost::Message;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+
+    /// Descriptor set for `package acme; message Event { string name = 1; int32 count = 2; }`
+    fn descriptor_set() -> Vec<u8> {
+        let field = |name: &str, number, kind: Type| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(kind as i32),
+            json_name: Some(name.to_string()),
+            ..Default::default()
+        };
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("acme.proto".to_string()),
+                package: Some("acme".to_string()),
+                syntax: Some("proto3".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Event".to_string()),
+                    field: vec![
+                        field("name", 1, Type::String),
+                        field("count", 2, Type::Int32),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    /// Encoded `acme.Event { name: "deploy", count: 3 }`
+    fn event() -> Vec<u8> {
+        // Field 1 (length-delimited) "deploy", field 2 (varint) 3
+        let mut bytes = vec![0x0a, 6];
+        bytes.extend_from_slice(b"deploy");
+        bytes.extend_from_slice(&[0x10, 3]);
+        bytes
+    }
+
+    #[test]
+    fn test_new_validates_configuration() {
+        assert!(ProtobufDecoder::new(b"\xff\xff", Some("acme.Event"), None).is_err());
+        assert_eq!(
+            ProtobufDecoder::new(&descriptor_set(), None, None).unwrap_err(),
+            "a message type or a type header is required"
+        );
+        assert_eq!(
+            ProtobufDecoder::new(&descriptor_set(), Some("acme.Missing"), None).unwrap_err(),
+            "message type 'acme.Missing' not found in descriptor set"
+        );
+    }
+
+    #[test]
+    fn test_decode_with_configured_type() {
+        let decoder = ProtobufDecoder::new(&descriptor_set(), Some("acme.Event"), None).unwrap();
+        let headers = serde_json::json!({});
+
+        let decoded = decoder
+            .decode(&headers, Some("application/x-protobuf"), &event())
+            .unwrap();
+        assert_eq!(decoded.message_type.as_deref(), Some("acme.Event"));
+        assert_eq!(
+            decoded.json,
+            Some(serde_json::json!({"name": "deploy", "count": 3}))
+        );
+        assert!(decoded.error.is_none());
+
+        // Bodies that are not protobuf are left alone
+        assert!(decoder
+            .decode(&headers, Some("application/json"), b"{}")
+            .is_none());
+    }
+
+    #[test]
+    fn test_decode_with_type_header() {
+        let decoder = ProtobufDecoder::new(&descriptor_set(), None, Some("X-Proto-Type")).unwrap();
+
+        let headers = serde_json::json!({"x-proto-type": "type.googleapis.com/acme.Event"});
+        let decoded = decoder.decode(&headers, None, &event()).unwrap();
+        assert_eq!(decoded.json.unwrap()["name"], "deploy");
+
+        let headers = serde_json::json!({});
+        let decoded = decoder
+            .decode(&headers, Some("application/protobuf"), &event())
+            .unwrap();
+        assert_eq!(
+            decoded.error.as_deref(),
+            Some("request does not name a message type")
+        );
+    }
+
+    #[test]
+    fn test_decode_errors_are_reported() {
+        let decoder = ProtobufDecoder::new(&descriptor_set(), Some("acme.Event"), None).unwrap();
+        let headers = serde_json::json!({});
+
+        let decoded = decoder
+            .decode(&headers, Some("application/x-protobuf"), &[0x0a, 0xff])
+            .unwrap();
+        assert_eq!(decoded.message_type.as_deref(), Some("acme.Event"));
+        assert!(decoded.json.is_none());
+        assert!(decoded
+            .error
+            .unwrap()
+            .starts_with("failed to decode acme.Event"));
+    }
+}
@@ -0,0 +1,402 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{Map, Value};
+
+/// Deepest XML element nesting rendered; deeper documents are refused rather than
+/// built into values too deep to serialize or drop
+const MAX_XML_DEPTH: usize = 128;
+
+/// Binary and markup formats rendered as JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    MessagePack,
+    Cbor,
+    Xml,
+}
+
+impl Format {
+    /// Format of a body from its media type, e.g. `application/msgpack` or `application/soap+xml`
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Format::MessagePack)
+            }
+            "application/cbor" => Some(Format::Cbor),
+            "application/xml" | "text/xml" => Some(Format::Xml),
+            _ if media_type.ends_with("+cbor") => Some(Format::Cbor),
+            _ if media_type.ends_with("+xml") => Some(Format::Xml),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::MessagePack => "msgpack",
+            Format::Cbor => "cbor",
+            Format::Xml => "xml",
+        }
+    }
+
+    /// Decode a body into a JSON value
+    pub fn decode(&self, body: &[u8]) -> Result<Value, String> {
+        match self {
+            Format::MessagePack => decode_msgpack(body),
+            Format::Cbor => decode_cbor(body),
+            Format::Xml => decode_xml(body),
+        }
+    }
+}
+
+/// Binary values are rendered as base64 strings and non-string map keys by their JSON text
+fn decode_msgpack(mut body: &[u8]) -> Result<Value, String> {
+    let value = rmpv::decode::read_value(&mut body).map_err(|e| e.to_string())?;
+    if !body.is_empty() {
+        return Err(format!("{} trailing bytes after value", body.len()));
+    }
+    Ok(msgpack_to_json(value))
+}
+
+fn msgpack_to_json(value: rmpv::Value) -> Value {
+    use rmpv::Value as Msgpack;
+    match value {
+        Msgpack::Nil => Value::Null,
+        Msgpack::Boolean(b) => Value::Bool(b),
+        Msgpack::Integer(i) => match (i.as_i64(), i.as_u64()) {
+            (Some(n), _) => n.into(),
+            (None, Some(n)) => n.into(),
+            (None, None) => Value::Null,
+        },
+        Msgpack::F32(f) => number(f as f64),
+        Msgpack::F64(f) => number(f),
+        Msgpack::String(s) => match s.into_str() {
+            Some(s) => Value::String(s),
+            None => Value::Null,
+        },
+        Msgpack::Binary(bytes) => Value::String(general_purpose::STANDARD.encode(bytes)),
+        Msgpack::Array(values) => Value::Array(values.into_iter().map(msgpack_to_json).collect()),
+        Msgpack::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (map_key(msgpack_to_json(key)), msgpack_to_json(value)))
+                .collect(),
+        ),
+        Msgpack::Ext(kind, data) => serde_json::json!({
+            "ext": kind,
+            "data": general_purpose::STANDARD.encode(data),
+        }),
+    }
+}
+
+/// Byte strings are rendered as base64 and tags are dropped, keeping the tagged value
+fn decode_cbor(body: &[u8]) -> Result<Value, String> {
+    let mut reader = body;
+    let value: ciborium::Value =
+        ciborium::de::from_reader(&mut reader).map_err(|e| e.to_string())?;
+    if !reader.is_empty() {
+        return Err(format!("{} trailing bytes after value", reader.len()));
+    }
+    Ok(cbor_to_json(value))
+}
+
+fn cbor_to_json(value: ciborium::Value) -> Value {
+    use ciborium::Value as Cbor;
+    match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Integer(i) => {
+            let n = i128::from(i);
+            i64::try_from(n)
+                .map(Value::from)
+                .or_else(|_| u64::try_from(n).map(Value::from))
+                .unwrap_or_else(|_| Value::String(n.to_string()))
+        }
+        Cbor::Float(f) => number(f),
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Bytes(bytes) => Value::String(general_purpose::STANDARD.encode(bytes)),
+        Cbor::Array(values) => Value::Array(values.into_iter().map(cbor_to_json).collect()),
+        Cbor::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (map_key(cbor_to_json(key)), cbor_to_json(value)))
+                .collect(),
+        ),
+        Cbor::Tag(_, value) => cbor_to_json(*value),
+        _ => Value::Null,
+    }
+}
+
+/// Floats without a JSON representation (NaN, infinities) become strings
+fn number(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(f.to_string()))
+}
+
+fn map_key(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+/// An element being built while its children are read
+struct XmlElement {
+    name: String,
+    children: Map<String, Value>,
+    text: String,
+}
+
+/// Render XML as JSON: the document becomes `{"root": ...}`, attributes become `@name`
+/// keys and repeated child elements become arrays. An element with only text becomes
+/// a string; text next to attributes or children is kept under `#text`.
+fn decode_xml(body: &[u8]) -> Result<Value, String> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_reader(body);
+    reader.config_mut().trim_text(true);
+    let mut stack: Vec<XmlElement> = Vec::new();
+    let mut root = Map::new();
+    let error = |e: quick_xml::Error, reader: &quick_xml::Reader<&[u8]>| {
+        format!("{} at byte {}", e, reader.error_position())
+    };
+
+    loop {
+        match reader.read_event().map_err(|e| error(e, &reader))? {
+            Event::Start(start) => {
+                if stack.len() >= MAX_XML_DEPTH {
+                    return Err(format!("elements nested deeper than {}", MAX_XML_DEPTH));
+                }
+                stack.push(xml_element(&start)?)
+            }
+            Event::Empty(start) => {
+                if stack.len() >= MAX_XML_DEPTH {
+                    return Err(format!("elements nested deeper than {}", MAX_XML_DEPTH));
+                }
+                let (name, value) = xml_element(&start)?.finish();
+                add_child(
+                    stack.last_mut().map_or(&mut root, |e| &mut e.children),
+                    name,
+                    value,
+                );
+            }
+            Event::End(_) => {
+                let (name, value) = stack.pop().ok_or("unexpected closing tag")?.finish();
+                add_child(
+                    stack.last_mut().map_or(&mut root, |e| &mut e.children),
+                    name,
+                    value,
+                );
+            }
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    let text = text.unescape().map_err(|e| error(e, &reader))?;
+                    element.text.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Eof => break,
+            // Declarations, comments, processing instructions and doctypes carry no data
+            _ => {}
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(format!("unclosed element <{}>", open.name));
+    }
+    if root.is_empty() {
+        return Err("no root element".to_string());
+    }
+    Ok(Value::Object(root))
+}
+
+fn xml_element(start: &quick_xml::events::BytesStart) -> Result<XmlElement, String> {
+    let mut children = Map::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|e| e.to_string())?;
+        let value = attribute.unescape_value().map_err(|e| e.to_string())?;
+        children.insert(
+            format!("@{}", String::from_utf8_lossy(attribute.key.as_ref())),
+            Value::String(value.into_owned()),
+        );
+    }
+
+    Ok(XmlElement {
+        name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+        children,
+        text: String::new(),
+    })
+}
+
+impl XmlElement {
+    fn finish(self) -> (String, Value) {
+        let value = if self.children.is_empty() {
+            Value::String(self.text)
+        } else {
+            let mut children = self.children;
+            if !self.text.is_empty() {
+                children.insert("#text".to_string(), Value::String(self.text));
+            }
+            Value::Object(children)
+        };
+        (self.name, value)
+    }
+}
+
+/// Add a child element, turning repeated names into an array
+fn add_child(children: &mut Map<String, Value>, name: String, value: Value) {
+    match children.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            children.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_from_media_type() {
+        assert_eq!(
+            Format::from_media_type("application/x-msgpack"),
+            Some(Format::MessagePack)
+        );
+        assert_eq!(
+            Format::from_media_type("application/senml+cbor"),
+            Some(Format::Cbor)
+        );
+        assert_eq!(
+            Format::from_media_type("application/soap+xml"),
+            Some(Format::Xml)
+        );
+        assert_eq!(Format::from_media_type("text/xml"), Some(Format::Xml));
+        assert_eq!(Format::from_media_type("application/json"), None);
+    }
+
+    #[test]
+    fn test_decode_msgpack() {
+        use rmpv::Value as Msgpack;
+        let value = Msgpack::Map(vec![
+            (Msgpack::from("event"), Msgpack::from("push")),
+            (
+                Msgpack::from(7),
+                Msgpack::Array(vec![Msgpack::from(-1), Msgpack::from(2.5)]),
+            ),
+            (Msgpack::from("raw"), Msgpack::Binary(vec![0xff, 0x00])),
+        ]);
+        let mut body = Vec::new();
+        rmpv::encode::write_value(&mut body, &value).unwrap();
+
+        assert_eq!(
+            Format::MessagePack.decode(&body).unwrap(),
+            json!({"event": "push", "7": [-1, 2.5], "raw": "/wA="})
+        );
+
+        body.push(0xc0);
+        assert_eq!(
+            Format::MessagePack.decode(&body).unwrap_err(),
+            "1 trailing bytes after value"
+        );
+        assert!(Format::MessagePack.decode(&[0x92, 0x01]).is_err());
+    }
+
+    #[test]
+    fn test_decode_cbor() {
+        use ciborium::Value as Cbor;
+        let value = Cbor::Map(vec![
+            (Cbor::from("id"), Cbor::from(u64::MAX)),
+            (Cbor::from("ok"), Cbor::from(true)),
+            (Cbor::from("sig"), Cbor::Bytes(vec![1, 2, 3])),
+            (
+                Cbor::from("when"),
+                Cbor::Tag(1, Box::new(Cbor::from(1_700_000_000))),
+            ),
+        ]);
+        let mut body = Vec::new();
+        ciborium::ser::into_writer(&value, &mut body).unwrap();
+
+        assert_eq!(
+            Format::Cbor.decode(&body).unwrap(),
+            json!({"id": u64::MAX, "ok": true, "sig": "AQID", "when": 1_700_000_000})
+        );
+        assert!(Format::Cbor.decode(&body[..body.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_decode_xml() {
+        let body = br#"<?xml version="1.0"?>
+            <soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope">
+              <soap:Body>
+                <order id="42" status="new">
+                  <item sku="a1">Widget &amp; co</item>
+                  <item sku="b2"/>
+                  <note><![CDATA[<fragile>]]></note>
+                  <empty></empty>
+                </order>
+              </soap:Body>
+            </soap:Envelope>"#;
+
+        assert_eq!(
+            Format::Xml.decode(body).unwrap(),
+            json!({
+                "soap:Envelope": {
+                    "@xmlns:soap": "http://www.w3.org/2003/05/soap-envelope",
+                    "soap:Body": {
+                        "order": {
+                            "@id": "42",
+                            "@status": "new",
+                            "item": [
+                                {"@sku": "a1", "#text": "Widget & co"},
+                                {"@sku": "b2"}
+                            ],
+                            "note": "<fragile>",
+                            "empty": ""
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_xml_errors() {
+        assert!(Format::Xml.decode(b"<a><b></a>").is_err());
+        assert_eq!(
+            Format::Xml.decode(b"<a><b></b>").unwrap_err(),
+            "unclosed element <a>"
+        );
+        assert_eq!(
+            Format::Xml.decode(b"just text").unwrap_err(),
+            "no root element"
+        );
+    }
+
+    #[test]
+    fn test_decode_xml_depth_limit() {
+        let nested = |depth: usize| format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(Format::Xml.decode(nested(MAX_XML_DEPTH).as_bytes()).is_ok());
+        assert_eq!(
+            Format::Xml
+                .decode(nested(MAX_XML_DEPTH + 1).as_bytes())
+                .unwrap_err(),
+            format!("elements nested deeper than {}", MAX_XML_DEPTH)
+        );
+        // Deep enough to overflow the stack when the value is serialized or dropped
+        assert!(Format::Xml.decode(nested(20_000).as_bytes()).is_err());
+        let empty = format!(
+            "{}<b/>{}",
+            "<a>".repeat(MAX_XML_DEPTH),
+            "</a>".repeat(MAX_XML_DEPTH)
+        );
+        assert!(Format::Xml.decode(empty.as_bytes()).is_err());
+    }
+}
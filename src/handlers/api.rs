@@ -1,134 +1,101 @@
+use crate::access::auth::{AuthType, InboundAuth};
+use crate::access::ip_filter::IpFilter;
+use crate::analysis::jwt::JwtVerifier;
+use crate::analysis::schema::SchemaValidator;
+use crate::analysis::signature::{Scheme, Verifier};
+use crate::decode::protobuf::ProtobufDecoder;
+use crate::decode::{self, encoding, form};
 use crate::models::{
-    Endpoint, Request, RequestListResponse, RequestQueryParams, RequestResponse,
-    UpdateResponseConfig,
+    webhook_base_path, Endpoint, Request, RequestListResponse, RequestQueryParams, RequestResponse,
+    ResetSequenceParams, UpdateAuthConfig, UpdateCaptureConfig, UpdateFingerprintConfig,
+    UpdateIpFilterConfig, UpdateJwtConfig, UpdateProtobufConfig, UpdateRateLimitConfig,
+    UpdateResponseConfig, UpdateSchemaConfig, UpdateSignatureConfig,
 };
-use crate::websocket::WebSocketManager;
+use crate::response::latency::ResponseLatency;
+use crate::response::sequence::{CompiledSequence, ResponseSequence};
+use crate::response::template::ResponseTemplate;
+use crate::services::body::endpoint_spill_dir;
+use crate::state::AppState;
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use sqlx::SqlitePool;
-use std::sync::Arc;
+use sqlx::{QueryBuilder, Sqlite};
+use std::future::Future;
 
 /// Handler for GET /api/endpoints/:id/requests
-/// Returns paginated list of requests for an endpoint with optional method filtering
+/// Returns paginated list of requests for an endpoint, filtered by method, sub-path,
+/// provider, event type, signature, JWT, auth and schema status, IP blocking, duplicates,
+/// response rule and connection metadata
 pub async fn get_endpoint_requests(
     Path(endpoint_id): Path<String>,
     Query(params): Query<RequestQueryParams>,
-    State((pool, _ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
+    State(AppState {
+        pool, endpoints, ..
+    }): State<AppState>,
 ) -> Result<Json<RequestListResponse>, StatusCode> {
     // Validate pagination parameters
     let page = params.page.max(1);
     let limit = params.limit.clamp(1, 100); // Cap between 1 and 100
     let offset = (page - 1) * limit;
 
-    // Check if endpoint exists
-    let endpoint_exists: Option<(i64,)> = sqlx::query_as("SELECT 1 FROM endpoints WHERE id = ?")
-        .bind(&endpoint_id)
-        .fetch_optional(&pool)
+    // Check if endpoint exists; its configuration is needed to decode bodies
+    let endpoint = endpoints
+        .get(&pool, &endpoint_id)
         .await
         .map_err(|e| {
             tracing::error!("Database error checking endpoint: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-    if endpoint_exists.is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
-
-    // Build query with optional method filter
-    let (query, count_query) = if let Some(methods) = &params.method {
-        // Parse comma-separated methods
-        let method_list: Vec<&str> = methods.split(',').map(|s| s.trim()).collect();
-        let placeholders = method_list
-            .iter()
-            .map(|_| "?")
-            .collect::<Vec<_>>()
-            .join(",");
-
-        // Build SQL with IN clause for method filtering
-        let query = format!(
-            "SELECT * FROM requests
-             WHERE endpoint_id = ? AND method IN ({})
-             ORDER BY received_at DESC
-             LIMIT ? OFFSET ?",
-            placeholders
-        );
-
-        let count_query = format!(
-            "SELECT COUNT(*) FROM requests
-             WHERE endpoint_id = ? AND method IN ({})",
-            placeholders
-        );
-
-        (query, count_query)
-    } else {
-        // No method filter
-        (
-            "SELECT * FROM requests
-             WHERE endpoint_id = ?
-             ORDER BY received_at DESC
-             LIMIT ? OFFSET ?"
-                .to_string(),
-            "SELECT COUNT(*) FROM requests
-             WHERE endpoint_id = ?"
-                .to_string(),
-        )
-    };
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
     // Execute count query to get total
-    let total: i64 = if let Some(methods) = &params.method {
-        let method_list: Vec<&str> = methods.split(',').map(|s| s.trim()).collect();
-        let mut count_builder = sqlx::query_scalar(&count_query);
-        count_builder = count_builder.bind(&endpoint_id);
-        for method in method_list {
-            count_builder = count_builder.bind(method);
-        }
-        count_builder.fetch_one(&pool).await.map_err(|e| {
+    let mut count_builder = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM requests");
+    push_request_filters(&mut count_builder, &endpoint_id, &params);
+    let total: i64 = count_builder
+        .build_query_scalar()
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
             tracing::error!("Database error counting requests: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
-        })?
-    } else {
-        sqlx::query_scalar(&count_query)
-            .bind(&endpoint_id)
-            .fetch_one(&pool)
-            .await
-            .map_err(|e| {
-                tracing::error!("Database error counting requests: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-    };
+        })?;
 
     // Execute main query to get requests
-    let requests: Vec<Request> = if let Some(methods) = &params.method {
-        let method_list: Vec<&str> = methods.split(',').map(|s| s.trim()).collect();
-        let mut query_builder = sqlx::query_as(&query);
-        query_builder = query_builder.bind(&endpoint_id);
-        for method in method_list {
-            query_builder = query_builder.bind(method);
-        }
-        query_builder = query_builder.bind(limit as i64).bind(offset as i64);
-        query_builder.fetch_all(&pool).await.map_err(|e| {
+    let mut query_builder = QueryBuilder::<Sqlite>::new("SELECT * FROM requests");
+    push_request_filters(&mut query_builder, &endpoint_id, &params);
+    query_builder
+        .push(" ORDER BY received_at DESC LIMIT ")
+        .push_bind(limit as i64)
+        .push(" OFFSET ")
+        .push_bind(offset as i64);
+    let requests: Vec<Request> = query_builder
+        .build_query_as()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
             tracing::error!("Database error fetching requests: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
-        })?
-    } else {
-        sqlx::query_as(&query)
-            .bind(&endpoint_id)
-            .bind(limit as i64)
-            .bind(offset as i64)
-            .fetch_all(&pool)
-            .await
-            .map_err(|e| {
-                tracing::error!("Database error fetching requests: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-    };
+        })?;
 
-    // Convert to response format
-    let request_responses: Vec<RequestResponse> =
-        requests.into_iter().map(RequestResponse::from).collect();
+    // Convert to response format, decoding bodies off the async runtime
+    let request_responses: Vec<RequestResponse> = tokio::task::spawn_blocking(move || {
+        let protobuf = endpoint.protobuf_decoder();
+        requests
+            .into_iter()
+            .map(|request| {
+                RequestResponse::with_views(request, protobuf, encoding::MAX_LIST_DECODED_SIZE)
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to decode request bodies: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
     Ok(Json(RequestListResponse {
         requests: request_responses,
@@ -138,11 +105,105 @@ pub async fn get_endpoint_requests(
     }))
 }
 
+/// Append the WHERE clause shared by the request list and count queries
+fn push_request_filters(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    endpoint_id: &str,
+    params: &RequestQueryParams,
+) {
+    builder
+        .push(" WHERE endpoint_id = ")
+        .push_bind(endpoint_id.to_string());
+
+    if let Some(methods) = &params.method {
+        // Parse comma-separated methods into an IN clause
+        builder.push(" AND method IN (");
+        let mut separated = builder.separated(", ");
+        for method in methods.split(',').map(|s| s.trim()) {
+            separated.push_bind(method.to_string());
+        }
+        separated.push_unseparated(")");
+    }
+
+    // Providers, event types and check results filter like methods: comma-separated, matched exactly
+    for (column, values) in [
+        ("provider", &params.provider),
+        ("event_type", &params.event_type),
+        ("signature_status", &params.signature_status),
+        ("jwt_status", &params.jwt_status),
+        ("auth_status", &params.auth_status),
+        ("schema_status", &params.schema_status),
+    ] {
+        if let Some(values) = values {
+            builder.push(format!(" AND {} IN (", column));
+            let mut separated = builder.separated(", ");
+            for value in values.split(',').map(|s| s.trim()) {
+                separated.push_bind(value.to_string());
+            }
+            separated.push_unseparated(")");
+        }
+    }
+
+    if let Some(prefix) = params.path_prefix.as_deref().map(str::trim) {
+        // A bare "/" matches every sub-path, so only filter on longer prefixes
+        if !prefix.is_empty() && prefix != "/" {
+            let prefix = if prefix.starts_with('/') {
+                prefix.to_string()
+            } else {
+                format!("/{}", prefix)
+            };
+            // Compare with substr() rather than LIKE, which is case-insensitive and treats % and _ as wildcards
+            let full_prefix = format!("{}{}", webhook_base_path(endpoint_id), prefix);
+            builder
+                .push(" AND substr(path, 1, ")
+                .push_bind(full_prefix.chars().count() as i64)
+                .push(") = ")
+                .push_bind(full_prefix);
+        }
+    }
+
+    if let Some(version) = params.http_version.as_deref().map(str::trim) {
+        builder
+            .push(" AND http_version = ")
+            .push_bind(version.to_ascii_uppercase());
+    }
+
+    if let Some(tls) = params.tls {
+        builder.push(" AND tls = ").push_bind(tls);
+    }
+
+    if let Some(blocked) = params.blocked {
+        builder.push(" AND blocked = ").push_bind(blocked);
+    }
+
+    if params.hide_duplicates == Some(true) {
+        builder.push(" AND duplicate_of IS NULL");
+    }
+
+    if let Some(rule_id) = params.rule_id {
+        builder.push(" AND rule_id = ").push_bind(rule_id);
+    }
+
+    if let Some(port) = params.local_port {
+        builder.push(" AND local_port = ").push_bind(port);
+    }
+
+    if let Some(min_size) = params.min_size {
+        builder.push(" AND request_size >= ").push_bind(min_size);
+    }
+
+    if let Some(max_size) = params.max_size {
+        builder.push(" AND request_size <= ").push_bind(max_size);
+    }
+}
+
 /// Handler for GET /api/requests/:id
 /// Returns full details of a single request
 pub async fn get_request_by_id(
     Path(request_id): Path<i64>,
-    State((pool, _ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
+    State(AppState {
+        pool, endpoints, ..
+    }): State<AppState>,
 ) -> Result<Json<RequestResponse>, StatusCode> {
     let request: Request = sqlx::query_as("SELECT * FROM requests WHERE id = ?")
         .bind(request_id)
@@ -154,14 +215,173 @@ pub async fn get_request_by_id(
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    Ok(Json(RequestResponse::from(request)))
+    let endpoint = endpoints
+        .get(&pool, &request.endpoint_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error fetching endpoint: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Decoding is CPU-bound, so it runs off the async runtime
+    let response = tokio::task::spawn_blocking(move || {
+        let protobuf = endpoint.as_deref().and_then(|e| e.protobuf_decoder());
+        RequestResponse::with_views(request, protobuf, encoding::MAX_DECODED_SIZE)
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to decode request body: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(response))
+}
+
+/// Handler for GET /api/requests/:id/body
+/// Returns the raw captured body, reading spilled bodies back from disk
+pub async fn get_request_body(
+    Path(request_id): Path<i64>,
+    State(AppState { pool, .. }): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let request: Request = sqlx::query_as("SELECT * FROM requests WHERE id = ?")
+        .bind(request_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error fetching request: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let content_type = request
+        .content_type
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    let body = match request.body_file {
+        Some(path) => {
+            let file = tokio::fs::File::open(&path).await.map_err(|e| {
+                tracing::error!("Failed to open spilled body {}: {}", path, e);
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    StatusCode::GONE
+                } else {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            })?;
+            Body::from_stream(tokio_util::io::ReaderStream::new(file))
+        }
+        None => Body::from(request.body.unwrap_or_default()),
+    };
+
+    Ok(download_response(&content_type, None, body))
+}
+
+/// Captured content served as a download. Senders choose the content type, so browsers
+/// are told not to sniff or render it, and to run any script in it sandboxed.
+fn download_response(content_type: &str, filename: Option<&str>, body: Body) -> Response {
+    let disposition = filename
+        .map(|filename| {
+            format!(
+                "attachment; filename=\"{}\"",
+                filename.replace(['"', '\\'], "_")
+            )
+        })
+        .and_then(|disposition| header::HeaderValue::from_str(&disposition).ok())
+        // Names that cannot be sent in a header are left out rather than failing the download
+        .unwrap_or(header::HeaderValue::from_static("attachment"));
+    let content_type = header::HeaderValue::from_str(content_type)
+        .unwrap_or(header::HeaderValue::from_static("application/octet-stream"));
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, disposition),
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                header::HeaderValue::from_static("nosniff"),
+            ),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                header::HeaderValue::from_static("sandbox"),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Handler for GET /api/requests/:id/parts/:n
+/// Returns the content of one part of a multipart/form-data body, counting from 0
+pub async fn get_request_part(
+    Path((request_id, index)): Path<(i64, usize)>,
+    State(AppState { pool, .. }): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let request: Request = sqlx::query_as("SELECT * FROM requests WHERE id = ?")
+        .bind(request_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error fetching request: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let boundary = request
+        .content_type
+        .as_deref()
+        .and_then(form::multipart_boundary)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let body = match &request.body_file {
+        Some(path) => tokio::fs::read(path).await.map_err(|e| {
+            tracing::error!("Failed to read spilled body {}: {}", path, e);
+            if e.kind() == std::io::ErrorKind::NotFound {
+                StatusCode::GONE
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?,
+        None => request.body.unwrap_or_default(),
+    };
+    let headers = serde_json::from_str(&request.headers).unwrap_or_else(|_| serde_json::json!({}));
+    let body = tokio::task::spawn_blocking(move || decode::content_bytes(&headers, body))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to decode request body: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let parts = form::parse_multipart(&body, &boundary).map_err(|e| {
+        tracing::debug!(
+            "Request {} has an unparseable multipart body: {}",
+            request_id,
+            e
+        );
+        StatusCode::UNPROCESSABLE_ENTITY
+    })?;
+    let part = parts.get(index).ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(download_response(
+        part.content_type
+            .as_deref()
+            .unwrap_or("application/octet-stream"),
+        part.filename.as_deref(),
+        Body::from(body[part.data.clone()].to_vec()),
+    ))
 }
 
 /// Handler for DELETE /api/endpoints/:id
-/// Deletes an endpoint and all associated requests (cascade)
+/// Deletes an endpoint and all associated requests (cascade), including spilled bodies,
+/// its response sequence positions and its rate-limit buckets
 pub async fn delete_endpoint(
     Path(endpoint_id): Path<String>,
-    State((pool, _ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
+    State(AppState {
+        pool,
+        config,
+        endpoints,
+        rate_limiter,
+        sequences,
+        ..
+    }): State<AppState>,
 ) -> Result<StatusCode, StatusCode> {
     // Check if endpoint exists
     let endpoint_exists: Option<(i64,)> = sqlx::query_as("SELECT 1 FROM endpoints WHERE id = ?")
@@ -186,6 +406,21 @@ pub async fn delete_endpoint(
             tracing::error!("Database error deleting endpoint: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+    endpoints.invalidate(&endpoint_id);
+    sequences.reset(&endpoint_id, None);
+    rate_limiter.forget(&endpoint_id);
+
+    // Remove bodies spilled to disk for this endpoint
+    let spill_dir = endpoint_spill_dir(&config.spill_dir, &endpoint_id);
+    if let Err(e) = tokio::fs::remove_dir_all(&spill_dir).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!(
+                "Failed to remove spilled bodies in {}: {}",
+                spill_dir.display(),
+                e
+            );
+        }
+    }
 
     tracing::info!(
         "Deleted endpoint {} and all associated requests",
@@ -195,11 +430,44 @@ pub async fn delete_endpoint(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Store one part of an endpoint's configuration with `update`, drop the cached copy and
+/// return the endpoint as it now is. `what` names the configuration in error logs.
+async fn apply_endpoint_update(
+    state: &AppState,
+    endpoint_id: &str,
+    what: &str,
+    update: impl Future<Output = Result<bool, sqlx::Error>>,
+) -> Result<Endpoint, (StatusCode, String)> {
+    let internal_error = || {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    let updated = update.await.map_err(|e| {
+        tracing::error!("Database error updating endpoint {}: {}", what, e);
+        internal_error()
+    })?;
+    if !updated {
+        return Err((StatusCode::NOT_FOUND, "Endpoint not found".to_string()));
+    }
+    state.endpoints.invalidate(endpoint_id);
+
+    crate::services::endpoint::get_endpoint(&state.pool, endpoint_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error fetching updated endpoint: {}", e);
+            internal_error()
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Endpoint not found".to_string()))
+}
+
 /// Handler for PUT /api/endpoints/:id/response
 /// Updates custom response configuration for an endpoint
 pub async fn update_endpoint_response(
     Path(endpoint_id): Path<String>,
-    State((pool, _ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
+    State(state): State<AppState>,
     Json(config): Json<UpdateResponseConfig>,
 ) -> Result<Json<Endpoint>, (StatusCode, String)> {
     // Validate status code (100-599)
@@ -220,33 +488,487 @@ pub async fn update_endpoint_response(
         }
     }
 
+    // Template errors are reported now rather than on every capture
+    if config.template {
+        ResponseTemplate::new(config.headers.as_deref(), config.body.as_deref())
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
     // Update endpoint response configuration
-    let updated = crate::services::endpoint::update_response_config(
-        &pool,
+    let endpoint = apply_endpoint_update(
+        &state,
         &endpoint_id,
+        "response",
+        crate::services::endpoint::update_response_config(
+            &state.pool,
+            &endpoint_id,
+            config.enabled,
+            config.status,
+            config.headers,
+            config.body,
+            config.template,
+        ),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated response config for endpoint {}: enabled={}, status={}, template={}",
+        endpoint_id,
         config.enabled,
         config.status,
-        config.headers,
-        config.body,
+        config.template
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/capture
+/// Updates body size limits and durability for an endpoint
+pub async fn update_endpoint_capture(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateCaptureConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    // Validate body size limit if provided
+    if config.max_body_size.is_some_and(|size| size <= 0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Max body size must be greater than 0".to_string(),
+        ));
+    }
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "capture config",
+        crate::services::endpoint::update_capture_config(
+            &state.pool,
+            &endpoint_id,
+            config.max_body_size,
+            config.body_overflow,
+            config.durable_capture,
+        ),
     )
-    .await
-    .map_err(|e| {
-        tracing::error!("Database error updating endpoint response: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Internal server error".to_string(),
-        )
-    })?;
+    .await?;
 
-    if !updated {
-        return Err((StatusCode::NOT_FOUND, "Endpoint not found".to_string()));
+    tracing::info!(
+        "Updated capture config for endpoint {}: max_body_size={:?}, body_overflow={:?}, durable_capture={:?}",
+        endpoint_id,
+        config.max_body_size,
+        config.body_overflow,
+        config.durable_capture
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/protobuf
+/// Uploads a compiled descriptor set used to decode protobuf bodies, or removes it
+pub async fn update_endpoint_protobuf(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateProtobufConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    let descriptor = match &config.descriptor_set {
+        Some(encoded) => {
+            use base64::{engine::general_purpose, Engine as _};
+            let descriptor = general_purpose::STANDARD.decode(encoded).map_err(|_| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Descriptor set must be base64 encoded".to_string(),
+                )
+            })?;
+            // Reject configurations that could never decode a body
+            ProtobufDecoder::new(
+                &descriptor,
+                config.message_type.as_deref(),
+                config.type_header.as_deref(),
+            )
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            Some(descriptor)
+        }
+        None => None,
+    };
+    let (message_type, type_header) = match descriptor {
+        Some(_) => (config.message_type, config.type_header),
+        None => (None, None),
+    };
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "protobuf config",
+        crate::services::endpoint::update_protobuf_config(
+            &state.pool,
+            &endpoint_id,
+            descriptor,
+            message_type,
+            type_header,
+        ),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated protobuf config for endpoint {}: message_type={:?}, type_header={:?}",
+        endpoint_id,
+        endpoint.protobuf_message_type,
+        endpoint.protobuf_type_header
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/signature
+/// Configures webhook signature verification, or turns it off
+pub async fn update_endpoint_signature(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateSignatureConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    let (secrets, header, tolerance_secs, reject) = match config.scheme {
+        Some(scheme) => {
+            // Reject configurations that could never verify a request
+            Verifier::new(
+                scheme,
+                config.secrets.clone(),
+                config.header.as_deref(),
+                config.tolerance_secs,
+            )
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            let secrets = serde_json::to_string(&config.secrets).map_err(|e| {
+                tracing::error!("Failed to serialize signature secrets: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            })?;
+            let header = match scheme {
+                Scheme::HmacSha256 => config.header.map(|name| name.trim().to_ascii_lowercase()),
+                _ => None,
+            };
+            (Some(secrets), header, config.tolerance_secs, config.reject)
+        }
+        None => (None, None, None, false),
+    };
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "signature config",
+        crate::services::endpoint::update_signature_config(
+            &state.pool,
+            &endpoint_id,
+            config.scheme,
+            secrets,
+            header,
+            tolerance_secs,
+            reject,
+        ),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated signature config for endpoint {}: scheme={:?}, reject={}",
+        endpoint_id,
+        endpoint.signature_scheme,
+        endpoint.signature_reject
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/jwt
+/// Configures bearer token verification, or turns it off
+pub async fn update_endpoint_jwt(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateJwtConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    let config = match config.key_type {
+        Some(key_type) => {
+            // Reject keys that could never verify a token
+            JwtVerifier::new(
+                key_type,
+                config.key.as_deref().unwrap_or_default(),
+                config.header.as_deref(),
+                config.audience.as_deref(),
+                config.issuer.as_deref(),
+                config.leeway_secs,
+            )
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            UpdateJwtConfig {
+                header: config
+                    .header
+                    .map(|name| name.trim().to_ascii_lowercase())
+                    .filter(|name| !name.is_empty()),
+                ..config
+            }
+        }
+        None => UpdateJwtConfig::default(),
+    };
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "JWT config",
+        crate::services::endpoint::update_jwt_config(&state.pool, &endpoint_id, &config),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated JWT config for endpoint {}: key_type={:?}, header={:?}",
+        endpoint_id,
+        endpoint.jwt_key_type,
+        endpoint.jwt_header
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/auth
+/// Sets the credentials senders must present, or opens the endpoint to any sender
+pub async fn update_endpoint_auth(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateAuthConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    let config = match config.auth_type {
+        Some(auth_type) => {
+            InboundAuth::new(
+                auth_type,
+                config.username.as_deref(),
+                config.secret.as_deref(),
+                config.key_name.as_deref(),
+                config.key_location,
+            )
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            // Keep only the fields the auth type uses
+            UpdateAuthConfig {
+                username: config.username.filter(|_| auth_type == AuthType::Basic),
+                key_name: config
+                    .key_name
+                    .filter(|_| auth_type == AuthType::ApiKey)
+                    .map(|name| name.trim().to_string()),
+                key_location: (auth_type == AuthType::ApiKey)
+                    .then(|| config.key_location.unwrap_or_default()),
+                ..config
+            }
+        }
+        None => UpdateAuthConfig::default(),
+    };
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "auth config",
+        crate::services::endpoint::update_auth_config(&state.pool, &endpoint_id, &config),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated auth config for endpoint {}: auth_type={:?}",
+        endpoint_id,
+        endpoint.auth_type
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/ip-filter
+/// Sets the client addresses allowed and denied, and how blocked requests are answered
+pub async fn update_endpoint_ip_filter(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateIpFilterConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    let filter =
+        IpFilter::new(&config.allow, &config.deny).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if let Some(status) = config.block_status {
+        if !(400..=599).contains(&status) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("invalid block status {}, expected 400-599", status),
+            ));
+        }
+    }
+
+    // Store the lists as parsed, e.g. "10.0.0.1" as "10.0.0.1/32"
+    let normalize = |nets: &[ipnet::IpNet]| nets.iter().map(ToString::to_string).collect();
+    let config = UpdateIpFilterConfig {
+        allow: normalize(filter.allowed()),
+        deny: normalize(filter.denied()),
+        ..config
+    };
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "IP filter",
+        crate::services::endpoint::update_ip_filter_config(&state.pool, &endpoint_id, &config),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated IP filter for endpoint {}: {} allowed, {} denied",
+        endpoint_id,
+        config.allow.len(),
+        config.deny.len()
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/schema
+/// Sets the JSON Schema that captured JSON bodies are validated against
+pub async fn update_endpoint_schema(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateSchemaConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    let config = match &config.schema {
+        Some(schema) => {
+            SchemaValidator::new(&schema.to_string()).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            config
+        }
+        None => UpdateSchemaConfig::default(),
+    };
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "schema",
+        crate::services::endpoint::update_schema_config(&state.pool, &endpoint_id, &config),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated schema for endpoint {}: enabled={}, reject={}",
+        endpoint_id,
+        endpoint.json_schema.is_some(),
+        endpoint.schema_reject
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/fingerprint
+/// Sets the headers hashed with the body to recognise repeated deliveries
+pub async fn update_endpoint_fingerprint(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateFingerprintConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    // Header names are matched case-insensitively, so store them lowercased
+    let headers = config
+        .headers
+        .iter()
+        .map(|name| {
+            header::HeaderName::from_bytes(name.trim().as_bytes())
+                .map(|name| name.as_str().to_string())
+                .map_err(|_| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("invalid header name '{}'", name),
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let config = UpdateFingerprintConfig { headers };
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "fingerprint",
+        crate::services::endpoint::update_fingerprint_config(&state.pool, &endpoint_id, &config),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated fingerprint headers for endpoint {}: {:?}",
+        endpoint_id,
+        config.headers
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/sequence
+/// Replaces the endpoint's response sequence and starts it over; no steps turns it off
+pub async fn update_endpoint_sequence(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(sequence): Json<ResponseSequence>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    if !sequence.steps.is_empty() {
+        CompiledSequence::new(&sequence).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
     }
 
-    // Fetch and return the updated endpoint
-    let endpoint = crate::services::endpoint::get_endpoint(&pool, &endpoint_id)
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "sequence",
+        crate::services::endpoint::update_sequence_config(&state.pool, &endpoint_id, &sequence),
+    )
+    .await?;
+    state.sequences.reset(&endpoint_id, None);
+
+    tracing::info!(
+        "Updated response sequence for endpoint {}: {} steps, {:?}, per_sender={}",
+        endpoint_id,
+        sequence.steps.len(),
+        sequence.mode,
+        sequence.per_sender
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for PUT /api/endpoints/:id/latency
+/// Sets the delay before the endpoint's own response and the drip of its body
+pub async fn update_endpoint_latency(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(latency): Json<ResponseLatency>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    latency
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "latency",
+        crate::services::endpoint::update_latency_config(&state.pool, &endpoint_id, &latency),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated response latency for endpoint {}: delay={:?}, drip={:?}",
+        endpoint_id,
+        latency.delay,
+        latency.drip
+    );
+
+    Ok(Json(endpoint))
+}
+
+/// Handler for POST /api/endpoints/:id/sequence/reset
+/// Starts the endpoint's response sequence over, for one sender or for everyone
+pub async fn reset_endpoint_sequence(
+    Path(endpoint_id): Path<String>,
+    Query(params): Query<ResetSequenceParams>,
+    State(AppState {
+        pool,
+        endpoints,
+        sequences,
+        ..
+    }): State<AppState>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    endpoints
+        .get(&pool, &endpoint_id)
         .await
         .map_err(|e| {
-            tracing::error!("Database error fetching updated endpoint: {}", e);
+            tracing::error!("Database error fetching endpoint: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
@@ -254,11 +976,36 @@ pub async fn update_endpoint_response(
         })?
         .ok_or((StatusCode::NOT_FOUND, "Endpoint not found".to_string()))?;
 
+    sequences.reset(&endpoint_id, params.sender.as_deref());
     tracing::info!(
-        "Updated response config for endpoint {}: enabled={}, status={}",
+        "Reset response sequence for endpoint {} (sender: {})",
         endpoint_id,
-        config.enabled,
-        config.status
+        params.sender.as_deref().unwrap_or("all")
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for PUT /api/endpoints/:id/rate-limit
+/// Sets the request limits for the endpoint and for each client, or restores the server defaults
+pub async fn update_endpoint_rate_limit(
+    Path(endpoint_id): Path<String>,
+    State(state): State<AppState>,
+    Json(config): Json<UpdateRateLimitConfig>,
+) -> Result<Json<Endpoint>, (StatusCode, String)> {
+    let endpoint = apply_endpoint_update(
+        &state,
+        &endpoint_id,
+        "rate limit",
+        crate::services::endpoint::update_rate_limit_config(&state.pool, &endpoint_id, &config),
+    )
+    .await?;
+
+    tracing::info!(
+        "Updated rate limits for endpoint {}: limit={:?}, client_limit={:?}",
+        endpoint_id,
+        endpoint.rate_limit,
+        endpoint.client_rate_limit
     );
 
     Ok(Json(endpoint))
@@ -267,16 +1014,20 @@ pub async fn update_endpoint_response(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{BodyOverflow, Config};
     use crate::db;
     use crate::models::Endpoint;
+    use crate::websocket::WebSocketManager;
     use axum::extract::Query;
+    use sqlx::SqlitePool;
+    use std::sync::Arc;
 
     async fn setup_test_db() -> SqlitePool {
         db::init_pool("sqlite::memory:").await.unwrap()
     }
 
-    fn create_test_state(pool: SqlitePool) -> (SqlitePool, Arc<WebSocketManager>) {
-        (pool, Arc::new(WebSocketManager::new()))
+    fn create_test_state(pool: SqlitePool) -> AppState {
+        AppState::new(pool, Arc::new(WebSocketManager::new()), Config::default())
     }
 
     async fn create_test_endpoint(pool: &SqlitePool) -> String {
@@ -314,6 +1065,7 @@ mod tests {
             page: 1,
             limit: 50,
             method: None,
+            ..Default::default()
         };
 
         let result = get_endpoint_requests(
@@ -343,6 +1095,7 @@ mod tests {
             page: 1,
             limit: 50,
             method: None,
+            ..Default::default()
         };
 
         let result = get_endpoint_requests(
@@ -373,6 +1126,7 @@ mod tests {
             page: 1,
             limit: 10,
             method: None,
+            ..Default::default()
         };
 
         let result = get_endpoint_requests(
@@ -393,6 +1147,7 @@ mod tests {
             page: 2,
             limit: 10,
             method: None,
+            ..Default::default()
         };
 
         let result = get_endpoint_requests(
@@ -430,6 +1185,7 @@ mod tests {
             page: 1,
             limit: 50,
             method: Some("POST".to_string()),
+            ..Default::default()
         };
 
         let result = get_endpoint_requests(
@@ -450,6 +1206,7 @@ mod tests {
             page: 1,
             limit: 50,
             method: Some("POST,PUT".to_string()),
+            ..Default::default()
         };
 
         let result = get_endpoint_requests(
@@ -465,6 +1222,135 @@ mod tests {
         assert_eq!(response.total, 7);
     }
 
+    #[tokio::test]
+    async fn test_get_endpoint_requests_connection_filters() {
+        let pool = setup_test_db().await;
+        let endpoint_id = create_test_endpoint(&pool).await;
+
+        for (version, tls, local_port, size) in [
+            ("HTTP/1.1", false, 3000, 100),
+            ("HTTP/1.1", true, 3443, 5000),
+            ("HTTP/2.0", true, 3443, 200),
+        ] {
+            sqlx::query(
+                "INSERT INTO requests (endpoint_id, method, path, headers, http_version, tls, local_port, request_size)
+                 VALUES (?, 'POST', '/test', '{}', ?, ?, ?, ?)",
+            )
+            .bind(&endpoint_id)
+            .bind(version)
+            .bind(tls)
+            .bind(local_port)
+            .bind(size)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let list = |params: RequestQueryParams| {
+            let endpoint_id = endpoint_id.clone();
+            let pool = pool.clone();
+            async move {
+                get_endpoint_requests(
+                    Path(endpoint_id),
+                    Query(params),
+                    State(create_test_state(pool)),
+                )
+                .await
+                .unwrap()
+                .0
+            }
+        };
+
+        let response = list(RequestQueryParams {
+            http_version: Some("http/1.1".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(response.total, 2);
+
+        let response = list(RequestQueryParams {
+            tls: Some(true),
+            local_port: Some(3443),
+            max_size: Some(1000),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(response.total, 1);
+        assert_eq!(
+            response.requests[0].http_version.as_deref(),
+            Some("HTTP/2.0")
+        );
+
+        let response = list(RequestQueryParams {
+            min_size: Some(150),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(response.total, 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_endpoint_requests_provider_filters() {
+        let pool = setup_test_db().await;
+        let endpoint_id = create_test_endpoint(&pool).await;
+
+        for (provider, event_type) in [
+            (Some("github"), Some("push")),
+            (Some("github"), Some("pull_request")),
+            (Some("stripe"), Some("invoice.paid")),
+            (None, None),
+        ] {
+            sqlx::query(
+                "INSERT INTO requests (endpoint_id, method, path, headers, provider, event_type)
+                 VALUES (?, 'POST', '/test', '{}', ?, ?)",
+            )
+            .bind(&endpoint_id)
+            .bind(provider)
+            .bind(event_type)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let list = |params: RequestQueryParams| {
+            let endpoint_id = endpoint_id.clone();
+            let pool = pool.clone();
+            async move {
+                get_endpoint_requests(
+                    Path(endpoint_id),
+                    Query(params),
+                    State(create_test_state(pool)),
+                )
+                .await
+                .unwrap()
+                .0
+            }
+        };
+
+        let response = list(RequestQueryParams {
+            provider: Some("github".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(response.total, 2);
+
+        let response = list(RequestQueryParams {
+            provider: Some("github, stripe".to_string()),
+            event_type: Some("push,invoice.paid".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(response.total, 2);
+
+        let response = list(RequestQueryParams {
+            event_type: Some("pull_request".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(response.total, 1);
+        assert_eq!(response.requests[0].provider.as_deref(), Some("github"));
+    }
+
     #[tokio::test]
     async fn test_get_request_by_id() {
         let pool = setup_test_db().await;
@@ -552,6 +1438,7 @@ mod tests {
             status: 404,
             headers: Some(r#"{"x-custom":"header"}"#.to_string()),
             body: Some(r#"{"error":"Custom error"}"#.to_string()),
+            template: false,
         };
 
         let result: Result<Json<Endpoint>, (StatusCode, String)> = update_endpoint_response(
@@ -589,6 +1476,7 @@ mod tests {
             status: 99,
             headers: None,
             body: None,
+            template: false,
         };
 
         let result: Result<Json<Endpoint>, (StatusCode, String)> = update_endpoint_response(
@@ -609,6 +1497,7 @@ mod tests {
             status: 600,
             headers: None,
             body: None,
+            template: false,
         };
 
         let result: Result<Json<Endpoint>, (StatusCode, String)> = update_endpoint_response(
@@ -634,6 +1523,7 @@ mod tests {
             status: 200,
             headers: Some("not valid json".to_string()),
             body: None,
+            template: false,
         };
 
         let result: Result<Json<Endpoint>, (StatusCode, String)> = update_endpoint_response(
@@ -658,6 +1548,7 @@ mod tests {
             status: 200,
             headers: None,
             body: None,
+            template: false,
         };
 
         let result: Result<Json<Endpoint>, (StatusCode, String)> = update_endpoint_response(
@@ -683,6 +1574,7 @@ mod tests {
             status: 500,
             headers: Some(r#"{"x-error":"true"}"#.to_string()),
             body: Some("Error".to_string()),
+            template: false,
         };
 
         let result: Result<Json<Endpoint>, (StatusCode, String)> = update_endpoint_response(
@@ -703,6 +1595,7 @@ mod tests {
             status: 200,
             headers: None,
             body: None,
+            template: false,
         };
 
         let result: Result<Json<Endpoint>, (StatusCode, String)> = update_endpoint_response(
@@ -717,4 +1610,102 @@ mod tests {
         assert!(!updated_endpoint.custom_response_enabled);
         assert_eq!(updated_endpoint.response_status, 200);
     }
+
+    #[tokio::test]
+    async fn test_update_endpoint_capture() {
+        let pool = setup_test_db().await;
+        let endpoint_id = create_test_endpoint(&pool).await;
+
+        let config = UpdateCaptureConfig {
+            max_body_size: Some(1024),
+            body_overflow: Some(BodyOverflow::Truncate),
+            durable_capture: Some(true),
+        };
+
+        let result = update_endpoint_capture(
+            Path(endpoint_id.clone()),
+            State(create_test_state(pool.clone())),
+            Json(config),
+        )
+        .await;
+
+        let Json(endpoint) = result.unwrap();
+        assert_eq!(endpoint.max_body_size, Some(1024));
+        assert_eq!(endpoint.body_overflow, Some("truncate".to_string()));
+        assert_eq!(endpoint.durable_capture, Some(true));
+
+        // Zero is not a valid limit
+        let config = UpdateCaptureConfig {
+            max_body_size: Some(0),
+            ..Default::default()
+        };
+        let result = update_endpoint_capture(
+            Path(endpoint_id),
+            State(create_test_state(pool)),
+            Json(config),
+        )
+        .await;
+        let (status, _) = result.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_request_body() {
+        let pool = setup_test_db().await;
+        let endpoint_id = create_test_endpoint(&pool).await;
+
+        let result = sqlx::query(
+            "INSERT INTO requests (endpoint_id, method, path, headers, body, content_type)
+             VALUES (?, 'POST', '/test', '{}', ?, 'application/octet-stream')",
+        )
+        .bind(&endpoint_id)
+        .bind(vec![0u8, 159, 146, 150])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let response = get_request_body(
+            Path(result.last_insert_rowid()),
+            State(create_test_state(pool)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(bytes.to_vec(), vec![0u8, 159, 146, 150]);
+    }
+
+    #[tokio::test]
+    async fn test_get_request_body_is_not_rendered() {
+        let pool = setup_test_db().await;
+        let endpoint_id = create_test_endpoint(&pool).await;
+
+        let result = sqlx::query(
+            "INSERT INTO requests (endpoint_id, method, path, headers, body, content_type)
+             VALUES (?, 'POST', '/test', '{}', ?, 'text/html')",
+        )
+        .bind(&endpoint_id)
+        .bind(b"<script>alert(1)</script>".to_vec())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let response = get_request_body(
+            Path(result.last_insert_rowid()),
+            State(create_test_state(pool)),
+        )
+        .await
+        .unwrap();
+
+        let headers = response.headers();
+        assert_eq!(headers[header::CONTENT_DISPOSITION], "attachment");
+        assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(headers[header::CONTENT_SECURITY_POLICY], "sandbox");
+    }
 }
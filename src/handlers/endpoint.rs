@@ -1,20 +1,24 @@
 use crate::models::{CreateEndpointResponse, Endpoint};
 use crate::services::endpoint;
-use crate::websocket::WebSocketManager;
+use crate::state::AppState;
 use axum::{
     extract::{Path, State},
     http::StatusCode,
     Json,
 };
-use sqlx::SqlitePool;
-use std::sync::Arc;
 
 /// Handler for POST /api/endpoints - Create a new endpoint
 pub async fn create_endpoint(
-    State((pool, _ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
+    State(AppState {
+        pool, endpoints, ..
+    }): State<AppState>,
 ) -> Result<Json<CreateEndpointResponse>, StatusCode> {
     match endpoint::create_endpoint(&pool).await {
-        Ok(response) => Ok(Json(response)),
+        Ok(response) => {
+            // Drop any cached "not found" lookup for the new ID
+            endpoints.invalidate(&response.id);
+            Ok(Json(response))
+        }
         Err(e) => {
             tracing::error!("Failed to create endpoint: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -24,7 +28,7 @@ pub async fn create_endpoint(
 
 /// Handler for GET /api/endpoints - List all endpoints
 pub async fn list_endpoints(
-    State((pool, _ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
+    State(AppState { pool, .. }): State<AppState>,
 ) -> Result<Json<Vec<Endpoint>>, StatusCode> {
     match endpoint::list_endpoints(&pool).await {
         Ok(endpoints) => Ok(Json(endpoints)),
@@ -38,7 +42,7 @@ pub async fn list_endpoints(
 /// Handler for GET /api/endpoints/:id - Get a single endpoint
 pub async fn get_endpoint(
     Path(id): Path<String>,
-    State((pool, _ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
+    State(AppState { pool, .. }): State<AppState>,
 ) -> Result<Json<Endpoint>, StatusCode> {
     match endpoint::get_endpoint(&pool, &id).await {
         Ok(Some(endpoint)) => Ok(Json(endpoint)),
@@ -1,15 +1,31 @@
 pub mod api;
 pub mod endpoint;
+pub mod rule;
 pub mod webhook;
 pub mod websocket;
 
-use axum::Json;
+use crate::state::AppState;
+use axum::{extract::State, Json};
 use serde_json::{json, Value};
 
-/// Health check endpoint
-pub async fn health_check() -> Json<Value> {
+/// Health check endpoint, including the depth of the ingest queue and endpoint cache use
+pub async fn health_check(
+    State(AppState {
+        ingest, endpoints, ..
+    }): State<AppState>,
+) -> Json<Value> {
+    let (hits, misses) = endpoints.hits_and_misses();
     Json(json!({
         "status": "ok",
-        "message": "Hookshot server is running"
+        "message": "Hookshot server is running",
+        "ingest": {
+            "queue_depth": ingest.depth(),
+            "queue_capacity": ingest.capacity()
+        },
+        "endpoint_cache": {
+            "entries": endpoints.size(),
+            "hits": hits,
+            "misses": misses
+        }
     }))
 }
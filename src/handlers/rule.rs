@@ -0,0 +1,162 @@
+use crate::models::{ResponseRule, ResponseRuleConfig};
+use crate::response::rules::{self, RuleResponse, MAX_DELAY_MS};
+use crate::services::{endpoint, rule};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+/// Refuse rules that would not compile when the endpoint is next loaded
+fn validate_rule(config: &ResponseRuleConfig) -> Result<(), (StatusCode, String)> {
+    if !(100..=599).contains(&config.status) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Status code must be between 100 and 599".to_string(),
+        ));
+    }
+    if config.delay_ms > MAX_DELAY_MS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Delay must be at most {} ms", MAX_DELAY_MS),
+        ));
+    }
+    rules::validate_matchers(&config.matchers).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    RuleResponse::new(
+        rule::headers_json(config).as_deref(),
+        config.body.as_deref(),
+        config.template,
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    Ok(())
+}
+
+/// Handler for GET /api/endpoints/:id/rules
+/// Lists the endpoint's response rules in evaluation order
+pub async fn list_rules(
+    Path(endpoint_id): Path<String>,
+    State(AppState { pool, .. }): State<AppState>,
+) -> Result<Json<Vec<ResponseRule>>, (StatusCode, String)> {
+    let endpoint = endpoint::get_endpoint(&pool, &endpoint_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error fetching endpoint rules: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Endpoint not found".to_string()))?;
+
+    Ok(Json(endpoint.response_rules))
+}
+
+/// Handler for POST /api/endpoints/:id/rules
+/// Adds a response rule, after the existing ones unless a position is given
+pub async fn create_rule(
+    Path(endpoint_id): Path<String>,
+    State(AppState {
+        pool, endpoints, ..
+    }): State<AppState>,
+    Json(config): Json<ResponseRuleConfig>,
+) -> Result<Json<ResponseRule>, (StatusCode, String)> {
+    validate_rule(&config)?;
+
+    let exists = endpoints
+        .get(&pool, &endpoint_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error checking endpoint: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?
+        .is_some();
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "Endpoint not found".to_string()));
+    }
+
+    let created = rule::create_rule(&pool, &endpoint_id, &config)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error creating response rule: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+    endpoints.invalidate(&endpoint_id);
+
+    tracing::info!(
+        "Created response rule {} for endpoint {} at position {}",
+        created.id,
+        endpoint_id,
+        created.position
+    );
+
+    Ok(Json(created))
+}
+
+/// Handler for PUT /api/endpoints/:id/rules/:rule_id
+/// Replaces a response rule, keeping its position unless a new one is given
+pub async fn update_rule(
+    Path((endpoint_id, rule_id)): Path<(String, i64)>,
+    State(AppState {
+        pool, endpoints, ..
+    }): State<AppState>,
+    Json(config): Json<ResponseRuleConfig>,
+) -> Result<Json<ResponseRule>, (StatusCode, String)> {
+    validate_rule(&config)?;
+
+    let updated = rule::update_rule(&pool, &endpoint_id, rule_id, &config)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error updating response rule: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Rule not found".to_string()))?;
+    endpoints.invalidate(&endpoint_id);
+
+    tracing::info!(
+        "Updated response rule {} for endpoint {}",
+        rule_id,
+        endpoint_id
+    );
+
+    Ok(Json(updated))
+}
+
+/// Handler for DELETE /api/endpoints/:id/rules/:rule_id
+pub async fn delete_rule(
+    Path((endpoint_id, rule_id)): Path<(String, i64)>,
+    State(AppState {
+        pool, endpoints, ..
+    }): State<AppState>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let deleted = rule::delete_rule(&pool, &endpoint_id, rule_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error deleting response rule: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+    if !deleted {
+        return Err((StatusCode::NOT_FOUND, "Rule not found".to_string()));
+    }
+    endpoints.invalidate(&endpoint_id);
+
+    tracing::info!(
+        "Deleted response rule {} for endpoint {}",
+        rule_id,
+        endpoint_id
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,48 +1,74 @@
-use crate::models::Endpoint;
-use crate::websocket::{RequestData, WebSocketManager, WebSocketMessage};
+use crate::access::{auth::AuthResult, ip_filter};
+use crate::analysis::{fingerprint, jwt, provider, schema, signature};
+use crate::config::BodyOverflow;
+use crate::decode::{self, form, params};
+use crate::models::webhook_sub_path;
+use crate::net::{client_ip, ConnectionInfo};
+use crate::response::latency::Drip;
+use crate::response::template::{Rendered, TemplateRequest};
+use crate::services::body::{self, BodyError, BodyLimits};
+use crate::services::request::{self as request_service, StoreRequestData};
+use crate::state::AppState;
 use axum::{
-    body::Bytes,
-    extract::{ConnectInfo, Path, State},
-    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri},
+    extract::{Path, Request, State},
+    http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
+    routing::any,
+    Json, Router,
 };
-use sqlx::SqlitePool;
-use std::net::SocketAddr;
+use serde::Deserialize;
+use std::borrow::Cow;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{error, info};
 
-const MAX_BODY_SIZE: usize = 10 * 1024 * 1024; // 10MB
+/// Path parameters for `/webhook/{id}` and `/webhook/{id}/{*path}`
+#[derive(Debug, Deserialize)]
+pub struct WebhookPath {
+    pub id: String,
+}
+
+/// Webhook capture routes - accept all HTTP methods and any sub-path.
+///
+/// These are served without response compression: a compressor buffers its output,
+/// which would hold back a dripped body until the end and change the configured response.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/webhook/{id}", any(webhook_handler))
+        .route("/webhook/{id}/", any(webhook_handler))
+        .route("/webhook/{id}/{*path}", any(webhook_handler))
+}
 
-/// Webhook capture handler - accepts any HTTP method and stores the request
+/// Webhook capture handler - accepts any HTTP method and stores the request.
+/// The full request path, including any sub-path, is stored on the captured request.
 pub async fn webhook_handler(
-    Path(endpoint_id): Path<String>,
-    method: Method,
-    uri: Uri,
-    headers: HeaderMap,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    State((pool, ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
-    body: Bytes,
+    Path(WebhookPath { id: endpoint_id }): Path<WebhookPath>,
+    connection: ConnectionInfo,
+    State(AppState {
+        pool,
+        config,
+        ingest,
+        endpoints,
+        rate_limiter,
+        sequences,
+        ..
+    }): State<AppState>,
+    request: Request,
 ) -> Result<Response, StatusCode> {
-    // Check body size limit
-    if body.len() > MAX_BODY_SIZE {
-        info!(
-            "Request to endpoint {} rejected: body size {} exceeds limit {}",
-            endpoint_id,
-            body.len(),
-            MAX_BODY_SIZE
-        );
-        return Err(StatusCode::PAYLOAD_TOO_LARGE);
-    }
-
-    // Fetch endpoint from database
-    let endpoint = match sqlx::query_as::<_, Endpoint>(
-        "SELECT id, created_at, custom_response_enabled, response_status, response_headers, response_body, request_count FROM endpoints WHERE id = ? LIMIT 1"
-    )
-    .bind(&endpoint_id)
-    .fetch_optional(&pool)
-    .await
-    {
+    let started = Instant::now();
+    let (
+        Parts {
+            method,
+            uri,
+            version,
+            headers,
+            ..
+        },
+        body,
+    ) = request.into_parts();
+
+    // Fetch endpoint from the cache, falling back to the database
+    let endpoint = match endpoints.get(&pool, &endpoint_id).await {
         Ok(Some(ep)) => ep,
         Ok(None) => {
             info!("Request to non-existent endpoint: {}", endpoint_id);
@@ -59,8 +85,10 @@ pub async fn webhook_handler(
     let path = uri.path();
     let query_string = uri.query().map(|q| q.to_string());
 
-    // Convert headers to JSON
+    // Convert headers to JSON, and keep them as sent for the lossless view
     let headers_json = headers_to_json(&headers);
+    let head = connection.request_head(&method, &uri, version, &headers);
+    let raw_headers_json = serde_json::to_string(&head.headers).unwrap_or_else(|_| "[]".into());
 
     // Extract Content-Type header
     let content_type = headers
@@ -68,119 +96,491 @@ pub async fn webhook_handler(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    // Get client IP address
-    let ip_address = addr.ip().to_string();
-
-    // Get current timestamp with millisecond precision
-    let received_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-
-    // Store body as bytes (can be empty)
-    let body_bytes = if body.is_empty() {
-        None
-    } else {
-        Some(body.to_vec())
-    };
-
-    // Insert request into database asynchronously
-    let pool_clone = pool.clone();
-    let endpoint_id_clone = endpoint_id.clone();
-    let method_str = http_method.to_string();
-    let path_str = path.to_string();
-    let ws_manager_clone = ws_manager.clone();
-
-    tokio::spawn(async move {
-        // Insert the request record
-        let insert_result = sqlx::query(
-            r#"
-            INSERT INTO requests (endpoint_id, method, path, query_string, headers, body, content_type, received_at, ip_address)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#
-        )
-        .bind(&endpoint_id_clone)
-        .bind(&method_str)
-        .bind(&path_str)
-        .bind(&query_string)
-        .bind(&headers_json)
-        .bind(&body_bytes)
-        .bind(&content_type)
-        .bind(&received_at)
-        .bind(&ip_address)
-        .execute(&pool_clone)
-        .await;
-
-        let request_id = match insert_result {
-            Ok(result) => result.last_insert_rowid(),
-            Err(e) => {
-                error!(
-                    "Failed to insert request for endpoint {}: {}",
-                    endpoint_id_clone, e
-                );
-                return;
-            }
-        };
+    // Resolve the client IP, following forwarding headers set by trusted proxies
+    let client_ip = client_ip::resolve_client_ip(
+        connection.remote_addr.ip(),
+        &headers,
+        &config.trusted_proxies,
+    );
+    let ip_address = client_ip.to_string();
+    let tls = client_ip::resolve_tls(
+        connection.remote_addr.ip(),
+        &headers,
+        &config.trusted_proxies,
+        connection.tls,
+    );
+
+    // Refuse clients outside the endpoint's IP filter before reading the body
+    if endpoint
+        .ip_filter()
+        .is_some_and(|filter| !filter.permits(client_ip))
+    {
+        let status = endpoint
+            .ip_block_status
+            .and_then(|status| StatusCode::from_u16(status as u16).ok())
+            .unwrap_or(ip_filter::DEFAULT_BLOCK_STATUS);
+        info!(
+            "Request to endpoint {} from {} blocked by IP filter",
+            endpoint_id, ip_address
+        );
 
-        // Increment request count for the endpoint
-        if let Err(e) =
-            sqlx::query("UPDATE endpoints SET request_count = request_count + 1 WHERE id = ?")
-                .bind(&endpoint_id_clone)
-                .execute(&pool_clone)
+        let counted = if endpoint.ip_block_record {
+            // Kept for audit without its body, which is never read
+            let data = StoreRequestData {
+                endpoint_id: endpoint_id.clone(),
+                method: http_method.to_string(),
+                path: path.to_string(),
+                query_string,
+                headers: headers_json,
+                content_type,
+                received_at: chrono::Utc::now()
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                ip_address,
+                raw_headers: raw_headers_json,
+                http_version: format!("{:?}", version),
+                peer_address: connection.peer_addr.ip().to_string(),
+                remote_port: connection.remote_addr.port() as i64,
+                local_address: connection.local_addr.map(|addr| addr.ip().to_string()),
+                local_port: connection.local_addr.map(|addr| addr.port() as i64),
+                tls,
+                request_size: head.size as i64,
+                processing_time_us: started.elapsed().as_micros() as i64,
+                blocked: true,
+                ..Default::default()
+            };
+            ingest.submit(data).await.map_err(|e| e.to_string())
+        } else {
+            request_service::add_blocked_count(&pool, &endpoint_id, 1)
                 .await
-        {
+                .map_err(|e| e.to_string())
+        };
+        if let Err(e) = counted {
             error!(
-                "Failed to increment request count for endpoint {}: {}",
-                endpoint_id_clone, e
+                "Failed to count blocked request for endpoint {}: {}",
+                endpoint_id, e
             );
         }
 
+        return Ok((
+            status,
+            [("Access-Control-Allow-Origin", "*")],
+            status.canonical_reason().unwrap_or_default(),
+        )
+            .into_response());
+    }
+
+    // Apply rate limits in memory; endpoint settings take precedence over server defaults
+    let (endpoint_limit, client_limit) = endpoint.rate_limits();
+    let endpoint_limit = endpoint_limit.or(config.endpoint_rate_limit);
+    let client_limit = client_limit.or(config.client_rate_limit);
+    if let Err(wait) = rate_limiter.check(&endpoint_id, client_ip, endpoint_limit, client_limit) {
         info!(
-            "Captured {} request to endpoint {} from {}",
-            method_str, endpoint_id_clone, ip_address
+            "Request to endpoint {} from {} throttled, retry in {:?}",
+            endpoint_id, ip_address, wait
         );
+        // Retry-After is in whole seconds, so round up rather than invite an early retry
+        let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+        return Ok((
+            StatusCode::TOO_MANY_REQUESTS,
+            [
+                ("Access-Control-Allow-Origin", "*".to_string()),
+                ("Retry-After", retry_after.to_string()),
+            ],
+            "Too Many Requests",
+        )
+            .into_response());
+    }
+
+    // Resolve body limits, endpoint settings take precedence over server defaults
+    let limits = BodyLimits {
+        max_body_size: endpoint
+            .max_body_size
+            .map(|size| size.max(0) as u64)
+            .unwrap_or(config.max_body_size),
+        overflow: endpoint
+            .body_overflow
+            .as_deref()
+            .and_then(|mode| mode.parse().ok())
+            .unwrap_or(config.body_overflow),
+        spill_dir: config.spill_dir.clone(),
+        max_spill_size: config.max_spill_size,
+    };
+
+    // Reject early when the sender announces a body over the limit
+    let content_length = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if limits.overflow == BodyOverflow::Reject
+        && content_length.is_some_and(|len| len > limits.max_body_size)
+    {
+        info!(
+            "Request to endpoint {} rejected: content length {} exceeds limit {}",
+            endpoint_id,
+            content_length.unwrap_or_default(),
+            limits.max_body_size
+        );
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
 
-        // Parse headers JSON string into serde_json::Value
-        let headers_value =
-            serde_json::from_str(&headers_json).unwrap_or_else(|_| serde_json::json!({}));
-
-        // Parse query string into object
-        let query_params_value = if let Some(qs) = &query_string {
-            let mut params = serde_json::Map::new();
-            for (key, value) in form_urlencoded::parse(qs.as_bytes()) {
-                params.insert(
-                    key.into_owned(),
-                    serde_json::Value::String(value.into_owned()),
-                );
+    let captured = match body::read_body(body, &endpoint_id, &limits).await {
+        Ok(captured) => captured,
+        Err(BodyError::TooLarge) => {
+            info!(
+                "Request to endpoint {} rejected: body exceeds limit {}",
+                endpoint_id, limits.max_body_size
+            );
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        Err(BodyError::Stream(e)) => {
+            info!("Failed to read body for endpoint {}: {}", endpoint_id, e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Err(e) => {
+            error!("Failed to capture body for endpoint {}: {}", endpoint_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // Signature and schema checks, response rules, sequences and templates work on the
+    // body in memory. A spilled body is never read back whole: its signature is checked
+    // by streaming the file, and the other checks are skipped.
+    let verifier = endpoint.signature_verifier();
+    let schema_validator = endpoint.schema_validator();
+    let rules = endpoint.rule_set();
+    let sequence = endpoint.sequence();
+    let templated = endpoint.custom_response_enabled && endpoint.response_template;
+    let inspects_request = templated || !rules.is_empty() || sequence.is_some();
+    let spilled = captured.file.is_some();
+
+    // Check the signature over the body as sent
+    let now = chrono::Utc::now().timestamp();
+    let verification = match (verifier, &captured.file) {
+        (None, _) => None,
+        (Some(Err(_)), _) => Some(signature::Verification::config_error()),
+        (Some(Ok(verifier)), None) => Some(verifier.verify(&headers, &captured.bytes, now)),
+        (Some(Ok(verifier)), Some(file)) => {
+            let (verifier, headers, path) =
+                (verifier.clone(), headers.clone(), file.path().to_path_buf());
+            let verified = tokio::task::spawn_blocking(move || {
+                verifier
+                    .verify_file(&headers, &path, now)
+                    .map_err(|e| format!("failed to read spilled body {}: {}", path.display(), e))
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|verified| verified);
+            match verified {
+                Ok(verification) => Some(verification),
+                Err(e) => {
+                    error!(
+                        "Failed to verify signature for endpoint {}: {}",
+                        endpoint_id, e
+                    );
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
             }
-            serde_json::Value::Object(params)
-        } else {
-            serde_json::json!({})
+        }
+    };
+
+    // Provider detection, schemas, rules, sequences and templates see the body with any
+    // Content-Encoding removed. Decoding runs on a blocking thread, and a body that fails
+    // to decode is seen as sent.
+    let mut decode_error = None;
+    let content_encoding = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let content = match content_encoding {
+        _ if spilled => None,
+        Some(coding)
+            if schema_validator.is_some() || inspects_request || provider::reads_body(&headers) =>
+        {
+            let body = captured.bytes.clone();
+            match tokio::task::spawn_blocking(move || decode::decode_content(&coding, body)).await {
+                Ok(Ok(decoded)) => Some(Cow::Owned(decoded)),
+                Ok(Err((_, e))) => {
+                    info!("Body for endpoint {} was not decoded: {}", endpoint_id, e);
+                    decode_error = Some(e);
+                    Some(Cow::Borrowed(&captured.bytes[..]))
+                }
+                Err(e) => {
+                    error!("Failed to decode body for endpoint {}: {}", endpoint_id, e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+        }
+        _ => Some(Cow::Borrowed(&captured.bytes[..])),
+    };
+
+    // Recognize well-known webhook senders and the event they report. A spilled body
+    // is not read back, so only what the headers carry is detected for it.
+    let detection = provider::detect(&headers, content.as_deref().unwrap_or_default());
+
+    // Validate JSON bodies against the endpoint's schema
+    let schema = schema_validator.and_then(|validator| {
+        let media_type = content_type
+            .as_deref()
+            .map(form::media_type)
+            .unwrap_or_default();
+        // Still-encoded bytes would only fail as "not valid JSON"
+        let skipped = match (&content, &decode_error) {
+            (None, _) => "body was spilled to disk, so it was not validated".to_string(),
+            (Some(_), Some(e)) => {
+                format!("body could not be decoded, so it was not validated: {}", e)
+            }
+            (Some(content), None) => return validator.validate(&media_type, content),
         };
+        validator
+            .applies_to(&media_type)
+            .then(|| schema::Validation::skipped(skipped))
+    });
+
+    // Check the credentials the endpoint requires; failures are recorded, then refused
+    let inbound_auth = endpoint.inbound_auth();
+    let auth = inbound_auth.map(|auth| auth.check(&headers, uri.query()));
+
+    // Verify the bearer token when the endpoint has a JWT key
+    let jwt = endpoint.jwt_verifier().map(|verifier| match verifier {
+        Ok(verifier) => verifier.verify(&headers),
+        Err(e) => jwt::Verification::config_error(e),
+    });
+
+    // Retries are linked by the provider's delivery ID, or by what was sent where
+    let fingerprint = fingerprint::fingerprint(
+        &fingerprint::Delivery {
+            method: http_method,
+            sub_path: &webhook_sub_path(path, &endpoint_id),
+            body_size: captured.size,
+            body_sha256: &captured.sha256,
+        },
+        &headers,
+        &endpoint.fingerprint_header_names(),
+    );
 
-        // Convert body bytes to UTF-8 string if present
-        let body_string = body_bytes
+    // Get current timestamp with millisecond precision
+    let received_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+    // Pick and render the response now so the matched rule, the sequence position and any
+    // render failure are recorded with the request. Refused requests are answered with their refusal instead.
+    let refused = matches!(auth, Some(AuthResult::Unauthorized(_)))
+        || (endpoint.signature_reject
+            && verification
+                .as_ref()
+                .is_some_and(|v| v.status != signature::Status::Valid))
+        || (endpoint.schema_reject
+            && schema
+                .as_ref()
+                .is_some_and(|v| v.status == schema::Status::Invalid));
+    let request_view = (inspects_request && !refused).then(|| TemplateRequest {
+        endpoint_id: endpoint_id.clone(),
+        method: http_method.to_string(),
+        path: path.to_string(),
+        sub_path: webhook_sub_path(path, &endpoint_id),
+        query: params::structure(&params::parse_pairs(
+            uri.query().unwrap_or_default().as_bytes(),
+        )),
+        headers: serde_json::from_str(&headers_json).unwrap_or_default(),
+        // A spilled body is left out; templates render without it
+        body: content
+            .as_deref()
+            .map(|content| String::from_utf8_lossy(content).into_owned())
+            .unwrap_or_default(),
+        json: content
+            .as_deref()
+            .and_then(|content| serde_json::from_slice(content).ok()),
+        ip: ip_address.clone(),
+        received_at: received_at.clone(),
+    });
+    let rule = request_view
+        .as_ref()
+        .filter(|_| !spilled)
+        .and_then(|request| rules.find(request));
+    // Requests no rule answers take the next step of the endpoint's sequence
+    let step = match (rule, sequence, &request_view) {
+        (None, Some(sequence), Some(_)) if !spilled => {
+            let sender = sequence.per_sender.then_some(ip_address.as_str());
+            Some(sequence.step(sequences.advance(&endpoint_id, sender)))
+        }
+        _ => None,
+    };
+    let rendered = request_view
+        .as_ref()
+        .and_then(|request| match (rule, step) {
+            (Some(rule), _) => Some(rule.render(request)),
+            (None, Some((_, step))) => Some(step.render(request)),
+            (None, None) if templated => Some(match endpoint.compiled_response() {
+                Some(template) => template.render(request).map_err(|e| e.to_string()),
+                None => Err("response template does not compile".to_string()),
+            }),
+            (None, None) => None,
+        });
+
+    // Pick the delay now so the one applied is recorded. It is only waited out once the
+    // request is queued, so slow responses never hold up capture.
+    let latency = endpoint.latency().cloned().unwrap_or_default();
+    let delay = match (rule, step) {
+        _ if refused => Duration::ZERO,
+        (Some(rule), _) => rule.delay,
+        (None, Some((_, step))) => step.delay,
+        (None, None) => latency
+            .delay
+            .map(|delay| delay.sample())
+            .unwrap_or_default(),
+    };
+    // Rules and sequence steps answer whole; only the endpoint's own response drips
+    let drip = latency
+        .drip
+        .filter(|_| !refused && rule.is_none() && step.is_none());
+
+    // Store body as bytes (can be empty). From here the ingest queue owns any spilled
+    // file and removes it if the request is not stored.
+    let body_file = captured
+        .file
+        .map(|file| file.persist().to_string_lossy().into_owned());
+    let body_bytes = if captured.bytes.is_empty() {
+        None
+    } else {
+        Some(captured.bytes)
+    };
+
+    let data = StoreRequestData {
+        endpoint_id: endpoint_id.clone(),
+        method: http_method.to_string(),
+        path: path.to_string(),
+        query_string,
+        headers: headers_json,
+        body: body_bytes,
+        content_type,
+        received_at,
+        ip_address,
+        body_size: captured.size as i64,
+        body_truncated: captured.truncated,
+        body_file,
+        raw_headers: raw_headers_json,
+        http_version: format!("{:?}", version),
+        peer_address: connection.peer_addr.ip().to_string(),
+        remote_port: connection.remote_addr.port() as i64,
+        local_address: connection.local_addr.map(|addr| addr.ip().to_string()),
+        local_port: connection.local_addr.map(|addr| addr.port() as i64),
+        tls,
+        request_size: (head.size as u64 + captured.size) as i64,
+        processing_time_us: started.elapsed().as_micros() as i64,
+        provider: detection.as_ref().map(|d| d.provider.to_string()),
+        delivery_id: detection.as_ref().and_then(|d| d.delivery_id.clone()),
+        event_type: detection.and_then(|d| d.event_type),
+        signature_status: verification.as_ref().map(|v| v.status.as_str().to_string()),
+        signature_computed: verification.as_ref().and_then(|v| v.computed.clone()),
+        signature_provided: verification.as_ref().and_then(|v| v.provided.clone()),
+        jwt_status: jwt.as_ref().map(|v| v.status.as_str().to_string()),
+        jwt_error: jwt.and_then(|v| v.error),
+        auth_status: auth.as_ref().map(|a| a.as_str().to_string()),
+        auth_error: auth.as_ref().and_then(|a| a.error()).map(str::to_string),
+        blocked: false,
+        schema_status: schema.as_ref().map(|v| v.status.as_str().to_string()),
+        schema_errors: schema
             .as_ref()
-            .and_then(|bytes| String::from_utf8(bytes.clone()).ok());
-
-        // Broadcast to WebSocket clients
-        let ws_message = WebSocketMessage::NewRequest {
-            data: Box::new(RequestData {
-                id: request_id,
-                endpoint_id: endpoint_id_clone.clone(),
-                method: method_str,
-                path: path_str,
-                query_string: query_string.clone(),
-                query_params: query_params_value,
-                headers: headers_value,
-                body: body_string,
-                content_type,
-                received_at,
-                ip_address: Some(ip_address),
+            .filter(|v| !v.errors.is_empty())
+            .and_then(|v| serde_json::to_string(&v.errors).ok()),
+        fingerprint,
+        response_error: rendered
+            .as_ref()
+            .and_then(|r| r.as_ref().err())
+            .cloned()
+            .or_else(|| {
+                (spilled && request_view.is_some()).then(|| {
+                    "body was spilled to disk, so response rules and the sequence were \
+                     skipped and the template rendered without it"
+                        .to_string()
+                })
             }),
-        };
+        rule_id: rule.map(|rule| rule.id),
+        sequence_position: step.map(|(position, _)| position as i64),
+        response_delay_ms: (!delay.is_zero()).then_some(delay.as_millis() as i64),
+        protobuf: endpoint.protobuf_decoder().cloned(),
+    };
 
-        ws_manager_clone
-            .broadcast(&endpoint_id_clone, ws_message)
-            .await;
-    });
+    // Durable endpoints wait for the request to be committed, others only for it to be queued
+    let durable = endpoint.durable_capture.unwrap_or(config.durable_capture);
+    let queued = if durable {
+        ingest.submit_and_wait(data).await.map(|_| ())
+    } else {
+        ingest.submit(data).await
+    };
+    if let Err(e) = queued {
+        error!(
+            "Failed to capture request for endpoint {}, responding 503: {}",
+            endpoint_id, e
+        );
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    // Unauthorized attempts are kept too, so misconfigured senders show up
+    if let (Some(inbound_auth), Some(AuthResult::Unauthorized(reason))) = (inbound_auth, &auth) {
+        info!(
+            "Request to endpoint {} rejected: unauthorized ({})",
+            endpoint_id, reason
+        );
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert("Access-Control-Allow-Origin", HeaderValue::from_static("*"));
+        if let Some(challenge) = inbound_auth.challenge() {
+            response_headers.insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(challenge),
+            );
+        }
+        return Ok((StatusCode::UNAUTHORIZED, response_headers, "Unauthorized").into_response());
+    }
+
+    // The request is kept either way so failed deliveries can be inspected
+    if let Some(verification) = verification.filter(|v| v.status != signature::Status::Valid) {
+        if endpoint.signature_reject {
+            info!(
+                "Request to endpoint {} rejected: signature {}",
+                endpoint_id,
+                verification.status.as_str()
+            );
+            return Ok((
+                StatusCode::UNAUTHORIZED,
+                [("Access-Control-Allow-Origin", "*")],
+                format!("Signature {}", verification.status.as_str()),
+            )
+                .into_response());
+        }
+    }
+
+    // Contract-testing endpoints answer invalid bodies with the errors found
+    if let Some(schema) = schema.filter(|v| v.status == schema::Status::Invalid) {
+        if endpoint.schema_reject {
+            info!(
+                "Request to endpoint {} rejected: {} schema errors",
+                endpoint_id,
+                schema.errors.len()
+            );
+            return Ok((
+                StatusCode::BAD_REQUEST,
+                [("Access-Control-Allow-Origin", "*")],
+                Json(serde_json::json!({ "errors": schema.errors })),
+            )
+                .into_response());
+        }
+    }
+
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+
+    // A matching rule or sequence step answers in place of the custom response
+    if let Some(rendered) = rendered {
+        let status = match (rule, step) {
+            (Some(rule), _) => rule.status,
+            (None, Some((_, step))) => step.status,
+            (None, None) => {
+                StatusCode::from_u16(endpoint.response_status as u16).unwrap_or(StatusCode::OK)
+            }
+        };
+        return Ok(drip_response(rendered_response(&endpoint_id, status, rendered), drip).await);
+    }
 
     // Build response based on custom configuration
     if endpoint.custom_response_enabled {
@@ -192,9 +592,9 @@ pub async fn webhook_handler(
         let mut response_headers = HeaderMap::new();
         response_headers.insert("Access-Control-Allow-Origin", HeaderValue::from_static("*"));
 
-        if let Some(headers_json) = endpoint.response_headers {
+        if let Some(headers_json) = &endpoint.response_headers {
             if let Ok(headers_map) =
-                serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&headers_json)
+                serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(headers_json)
             {
                 for (key, value) in headers_map {
                     if let Ok(header_name) = HeaderName::from_str(&key) {
@@ -209,12 +609,55 @@ pub async fn webhook_handler(
         }
 
         // Build response body
-        let response_body = endpoint.response_body.unwrap_or_default();
+        let response_body = endpoint.response_body.clone().unwrap_or_default();
 
-        Ok((status, response_headers, response_body).into_response())
+        let response = (status, response_headers, response_body).into_response();
+        Ok(drip_response(response, drip).await)
     } else {
         // Return default 200 OK response
-        Ok((StatusCode::OK, [("Access-Control-Allow-Origin", "*")], "").into_response())
+        let response = (StatusCode::OK, [("Access-Control-Allow-Origin", "*")], "").into_response();
+        Ok(drip_response(response, drip).await)
+    }
+}
+
+/// Response with its body streamed in chunks when the endpoint drips
+async fn drip_response(response: Response, drip: Option<Drip>) -> Response {
+    let Some(drip) = drip else {
+        return response;
+    };
+    let (parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .unwrap_or_default();
+    Response::from_parts(parts, drip.body(bytes))
+}
+
+/// Response from a rule, a sequence step or a templated custom response; a failed render
+/// answers 500
+fn rendered_response(
+    endpoint_id: &str,
+    status: StatusCode,
+    rendered: Result<Rendered, String>,
+) -> Response {
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert("Access-Control-Allow-Origin", HeaderValue::from_static("*"));
+    match rendered {
+        Ok(rendered) => {
+            response_headers.extend(rendered.headers);
+            (status, response_headers, rendered.body).into_response()
+        }
+        Err(e) => {
+            error!(
+                "Failed to render response template for endpoint {}: {}",
+                endpoint_id, e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                response_headers,
+                format!("Response template error: {}", e),
+            )
+                .into_response()
+        }
     }
 }
 
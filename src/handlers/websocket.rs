@@ -6,28 +6,29 @@ use axum::{
     response::IntoResponse,
 };
 use futures_util::{sink::SinkExt, stream::StreamExt};
-use sqlx::SqlitePool;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
-use crate::models::Endpoint;
+use crate::state::AppState;
 use crate::websocket::{WebSocketManager, WebSocketMessage};
 
 /// WebSocket handler for endpoint subscriptions
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     Path(endpoint_id): Path<String>,
-    State((pool, ws_manager)): State<(SqlitePool, Arc<WebSocketManager>)>,
+    State(AppState {
+        pool,
+        ws_manager,
+        endpoints,
+        ..
+    }): State<AppState>,
 ) -> impl IntoResponse {
     // Verify endpoint exists before upgrading connection
-    let endpoint_exists = sqlx::query_as::<_, Endpoint>(
-        "SELECT id, created_at, custom_response_enabled, response_status, response_headers, response_body, request_count FROM endpoints WHERE id = ? LIMIT 1"
-    )
-    .bind(&endpoint_id)
-    .fetch_optional(&pool)
-    .await
-    .is_ok_and(|e| e.is_some());
+    let endpoint_exists = endpoints
+        .get(&pool, &endpoint_id)
+        .await
+        .is_ok_and(|e| e.is_some());
 
     if !endpoint_exists {
         return axum::http::StatusCode::NOT_FOUND.into_response();
@@ -1,6 +1,13 @@
+pub mod access;
+pub mod analysis;
+pub mod config;
 pub mod db;
+pub mod decode;
 pub mod handlers;
 pub mod models;
+pub mod net;
+pub mod response;
 pub mod services;
+pub mod state;
 pub mod static_files;
 pub mod websocket;
@@ -1,16 +1,28 @@
+mod access;
+mod analysis;
+mod config;
 mod db;
+mod decode;
 mod handlers;
 mod models;
+mod net;
+mod response;
 mod services;
+mod state;
 mod static_files;
 mod websocket;
 
+use access::rate_limit::RateLimit;
 use axum::{
-    routing::{any, delete, get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use clap::Parser;
+use config::{Backpressure, BodyOverflow, Config};
+use ipnet::IpNet;
+use state::AppState;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tower_http::{
     compression::CompressionLayer,
@@ -35,6 +47,62 @@ struct Cli {
     /// Database URL (SQLite)
     #[arg(short, long, default_value = "sqlite:./hookshot.db")]
     database_url: String,
+
+    /// Maximum request body size in bytes (endpoints can override)
+    #[arg(long, default_value_t = config::DEFAULT_MAX_BODY_SIZE)]
+    max_body_size: u64,
+
+    /// How to handle bodies over the limit: reject, truncate or spill
+    #[arg(long, default_value_t = BodyOverflow::Reject)]
+    body_overflow: BodyOverflow,
+
+    /// Directory where oversized bodies are spilled
+    #[arg(long, default_value = "./hookshot-bodies")]
+    spill_dir: PathBuf,
+
+    /// Maximum size in bytes of a body spilled to disk
+    #[arg(long, default_value_t = config::DEFAULT_MAX_SPILL_SIZE)]
+    max_spill_size: u64,
+
+    /// Store every request before responding, answering 503 if storage fails
+    #[arg(long)]
+    durable: bool,
+
+    /// Maximum number of captured requests waiting to be written
+    #[arg(long, default_value_t = config::DEFAULT_INGEST_QUEUE_SIZE)]
+    ingest_queue_size: usize,
+
+    /// Maximum number of requests written per database transaction
+    #[arg(long, default_value_t = config::DEFAULT_INGEST_BATCH_SIZE)]
+    ingest_batch_size: usize,
+
+    /// When the ingest queue is full: block until there is room, or reject with 503
+    #[arg(long, default_value_t = Backpressure::Block)]
+    backpressure: Backpressure,
+
+    /// Maximum number of endpoint lookups cached in memory (0 disables the cache)
+    #[arg(long, default_value_t = config::DEFAULT_ENDPOINT_CACHE_SIZE)]
+    endpoint_cache_size: usize,
+
+    /// Proxy address or CIDR whose forwarding headers are trusted (repeatable or comma-separated)
+    #[arg(long = "trusted-proxy", value_delimiter = ',', value_parser = config::parse_ip_net)]
+    trusted_proxies: Vec<IpNet>,
+
+    /// Limit on webhook requests across all endpoints, e.g. 100/s or 6000/m:500
+    #[arg(long)]
+    rate_limit: Option<RateLimit>,
+
+    /// Default limit on requests to each endpoint (endpoints can override)
+    #[arg(long)]
+    endpoint_rate_limit: Option<RateLimit>,
+
+    /// Default limit on requests from each client IP to an endpoint (endpoints can override)
+    #[arg(long)]
+    client_rate_limit: Option<RateLimit>,
+
+    /// Expect a PROXY protocol v1/v2 header on every connection
+    #[arg(long)]
+    proxy_protocol: bool,
 }
 
 #[tokio::main]
@@ -59,6 +127,22 @@ async fn main() {
 
     tracing::info!("Database initialized successfully");
 
+    let config = Config {
+        max_body_size: cli.max_body_size,
+        body_overflow: cli.body_overflow,
+        spill_dir: cli.spill_dir.clone(),
+        max_spill_size: cli.max_spill_size,
+        durable_capture: cli.durable,
+        ingest_queue_size: cli.ingest_queue_size,
+        ingest_batch_size: cli.ingest_batch_size,
+        backpressure: cli.backpressure,
+        endpoint_cache_size: cli.endpoint_cache_size,
+        trusted_proxies: cli.trusted_proxies.clone(),
+        global_rate_limit: cli.rate_limit,
+        endpoint_rate_limit: cli.endpoint_rate_limit,
+        client_rate_limit: cli.client_rate_limit,
+    };
+
     // Initialize WebSocket manager
     let ws_manager = Arc::new(WebSocketManager::new());
 
@@ -101,28 +185,98 @@ async fn main() {
             "/api/endpoints/{id}/response",
             put(handlers::api::update_endpoint_response),
         )
+        .route(
+            "/api/endpoints/{id}/capture",
+            put(handlers::api::update_endpoint_capture),
+        )
+        .route(
+            "/api/endpoints/{id}/protobuf",
+            put(handlers::api::update_endpoint_protobuf),
+        )
+        .route(
+            "/api/endpoints/{id}/signature",
+            put(handlers::api::update_endpoint_signature),
+        )
+        .route(
+            "/api/endpoints/{id}/jwt",
+            put(handlers::api::update_endpoint_jwt),
+        )
+        .route(
+            "/api/endpoints/{id}/auth",
+            put(handlers::api::update_endpoint_auth),
+        )
+        .route(
+            "/api/endpoints/{id}/ip-filter",
+            put(handlers::api::update_endpoint_ip_filter),
+        )
+        .route(
+            "/api/endpoints/{id}/schema",
+            put(handlers::api::update_endpoint_schema),
+        )
+        .route(
+            "/api/endpoints/{id}/rate-limit",
+            put(handlers::api::update_endpoint_rate_limit),
+        )
+        .route(
+            "/api/endpoints/{id}/fingerprint",
+            put(handlers::api::update_endpoint_fingerprint),
+        )
+        .route(
+            "/api/endpoints/{id}/latency",
+            put(handlers::api::update_endpoint_latency),
+        )
+        .route(
+            "/api/endpoints/{id}/sequence",
+            put(handlers::api::update_endpoint_sequence),
+        )
+        .route(
+            "/api/endpoints/{id}/sequence/reset",
+            post(handlers::api::reset_endpoint_sequence),
+        )
+        // API routes for response rules
+        .route(
+            "/api/endpoints/{id}/rules",
+            get(handlers::rule::list_rules).post(handlers::rule::create_rule),
+        )
+        .route(
+            "/api/endpoints/{id}/rules/{rule_id}",
+            put(handlers::rule::update_rule).delete(handlers::rule::delete_rule),
+        )
         // API routes for request retrieval
         .route(
             "/api/endpoints/{id}/requests",
             get(handlers::api::get_endpoint_requests),
         )
         .route("/api/requests/{id}", get(handlers::api::get_request_by_id))
+        .route(
+            "/api/requests/{id}/body",
+            get(handlers::api::get_request_body),
+        )
+        .route(
+            "/api/requests/{id}/parts/{n}",
+            get(handlers::api::get_request_part),
+        )
         // WebSocket endpoint for real-time updates
         .route(
             "/ws/endpoints/{id}",
             get(handlers::websocket::websocket_handler),
-        )
-        // Webhook capture route - accepts all HTTP methods
-        .route("/webhook/{id}", any(handlers::webhook::webhook_handler));
+        );
+
+    let state = AppState::new(pool, ws_manager, config);
+    let ingest = state.ingest.clone();
+    let rate_limiter = state.rate_limiter.clone();
+    let pool = state.pool.clone();
 
     // Combine API routes with static file serving
     let app = api_routes
         // Fallback to static file serving for all other routes (SPA support)
         .fallback(static_files::serve_static_file)
         .layer(CompressionLayer::new())
+        // Webhook responses are sent as configured, so they sit outside compression
+        .merge(handlers::webhook::routes())
         .layer(cors)
         .layer(TraceLayer::new_for_http())
-        .with_state((pool, ws_manager));
+        .with_state(state);
 
     // Start server
     let addr: SocketAddr = format!("{}:{}", cli.host, cli.port)
@@ -133,11 +287,32 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(addr)
         .await
         .expect("Failed to bind to address");
+    let listener = if cli.proxy_protocol {
+        tracing::info!("Expecting PROXY protocol headers");
+        net::WireListener::with_proxy_protocol(listener)
+    } else {
+        net::WireListener::new(listener)
+    }
+    .expect("Failed to read listener address");
 
     axum::serve(
         listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
+        app.into_make_service_with_connect_info::<net::ConnectionInfo>(),
     )
+    .with_graceful_shutdown(shutdown_signal())
     .await
     .expect("Server failed");
+
+    // Flush requests still waiting in the ingest queue, and throttled counts, before exiting
+    tracing::info!("Flushing {} queued requests", ingest.depth());
+    ingest.shutdown().await;
+    rate_limiter.flush_throttled(&pool).await;
+}
+
+/// Resolve when the process receives Ctrl+C
+async fn shutdown_signal() {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Failed to install Ctrl+C handler");
+    tracing::info!("Shutdown signal received");
 }
@@ -1,5 +1,18 @@
+use crate::access::auth::{AuthType, InboundAuth, KeyLocation};
+use crate::access::ip_filter::IpFilter;
+use crate::access::rate_limit::RateLimit;
+use crate::analysis::jwt::{JwtVerifier, KeyType};
+use crate::analysis::schema::{SchemaError, SchemaValidator};
+use crate::analysis::signature::{Scheme, Verifier};
+use crate::config::BodyOverflow;
+use crate::decode::protobuf::ProtobufDecoder;
+use crate::response::latency::ResponseLatency;
+use crate::response::rules::{Matcher, RuleSet};
+use crate::response::sequence::{CompiledSequence, ResponseSequence};
+use crate::response::template::ResponseTemplate;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::sync::OnceLock;
 
 /// Endpoint model
 #[allow(dead_code)]
@@ -11,7 +24,319 @@ pub struct Endpoint {
     pub response_status: i32,
     pub response_headers: Option<String>,
     pub response_body: Option<String>,
+    pub response_template: bool, // Render the body and header values as templates
     pub request_count: i32,
+    pub max_body_size: Option<i64>, // NULL uses the server-wide limit
+    pub body_overflow: Option<String>, // "reject", "truncate" or "spill"; NULL uses the server default
+    pub durable_capture: Option<bool>, // Commit before responding; NULL uses the server default
+    #[serde(skip)]
+    pub protobuf_descriptor: Option<Vec<u8>>, // Compiled FileDescriptorSet for protobuf bodies
+    pub protobuf_message_type: Option<String>, // e.g. "acme.Event"
+    pub protobuf_type_header: Option<String>, // Header naming the message type per request
+    pub signature_scheme: Option<String>, // e.g. "github"; NULL disables verification
+    #[serde(skip)]
+    pub signature_secrets: Option<String>, // JSON list of signing secrets
+    pub signature_header: Option<String>, // Header carrying an hmac-sha256 signature
+    pub signature_tolerance_secs: Option<i64>, // Allowed timestamp skew; NULL uses the default
+    pub signature_reject: bool,        // Respond 401 unless the signature is valid
+    pub jwt_key_type: Option<String>,  // "jwks", "pem" or "secret"; NULL disables verification
+    #[serde(skip)]
+    pub jwt_key: Option<String>, // JWKS JSON, PEM public key or shared secret
+    pub jwt_header: Option<String>,    // Header carrying the token; NULL uses Authorization
+    pub jwt_audience: Option<String>,
+    pub jwt_issuer: Option<String>,
+    pub jwt_leeway_secs: Option<i64>, // Clock skew allowed for exp and nbf
+    pub auth_type: Option<String>,    // "basic", "bearer" or "api_key"; NULL accepts any sender
+    pub auth_username: Option<String>,
+    #[serde(skip)]
+    pub auth_secret: Option<String>, // Basic password, bearer token or API key
+    pub auth_key_name: Option<String>, // Header or query parameter carrying the API key
+    pub auth_key_location: Option<String>, // "header" or "query"
+    pub ip_allow: Option<String>,      // JSON list of CIDR blocks; NULL admits every address
+    pub ip_deny: Option<String>,       // JSON list of CIDR blocks refused even when allowed
+    pub ip_block_status: Option<i32>,  // Status sent to blocked clients; NULL sends 403
+    pub ip_block_record: bool,         // Store blocked requests, flagged as blocked
+    pub blocked_count: i32,
+    pub rate_limit: Option<String>, // e.g. "600/m"; NULL uses the server default
+    pub client_rate_limit: Option<String>, // Limit for each client IP; NULL uses the server default
+    pub throttled_count: i32,
+    pub json_schema: Option<String>, // JSON Schema for JSON bodies; NULL skips validation
+    pub schema_reject: bool,         // Respond 400 with the errors when a body is invalid
+    pub fingerprint_headers: Option<String>, // JSON list of headers hashed with the body; NULL hashes the body only
+    pub response_sequence: Option<String>, // JSON `ResponseSequence`; NULL answers with the custom response
+    pub response_latency: Option<String>,  // JSON `ResponseLatency`; NULL answers at once
+    #[serde(skip)]
+    #[sqlx(skip)]
+    protobuf_decoder: OnceLock<Option<ProtobufDecoder>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    schema_validator: OnceLock<Option<SchemaValidator>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    compiled_response: OnceLock<Option<ResponseTemplate>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    pub response_rules: Vec<ResponseRule>, // Loaded by `get_endpoint`, in evaluation order
+    #[serde(skip)]
+    #[sqlx(skip)]
+    rule_set: OnceLock<RuleSet>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    sequence: OnceLock<Option<CompiledSequence>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    latency: OnceLock<Option<ResponseLatency>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    signature_verifier: OnceLock<Option<Result<Verifier, String>>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    jwt_verifier: OnceLock<Option<Result<JwtVerifier, String>>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    inbound_auth: OnceLock<Option<InboundAuth>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    ip_filter: OnceLock<Option<IpFilter>>,
+    #[serde(skip)]
+    #[sqlx(skip)]
+    rate_limits: OnceLock<(Option<RateLimit>, Option<RateLimit>)>,
+}
+
+impl Endpoint {
+    /// Decoder for the endpoint's protobuf descriptor set, parsed on first use and kept
+    /// for as long as the endpoint stays cached
+    pub fn protobuf_decoder(&self) -> Option<&ProtobufDecoder> {
+        self.protobuf_decoder
+            .get_or_init(|| {
+                let descriptor = self.protobuf_descriptor.as_deref()?;
+                ProtobufDecoder::new(
+                    descriptor,
+                    self.protobuf_message_type.as_deref(),
+                    self.protobuf_type_header.as_deref(),
+                )
+                .map_err(|e| tracing::warn!("Endpoint {} protobuf config: {}", self.id, e))
+                .ok()
+            })
+            .as_ref()
+    }
+
+    /// Validator compiled from the endpoint's JSON Schema on first use and kept for as
+    /// long as the endpoint stays cached
+    pub fn schema_validator(&self) -> Option<&SchemaValidator> {
+        self.schema_validator
+            .get_or_init(|| {
+                SchemaValidator::new(self.json_schema.as_deref()?)
+                    .map_err(|e| tracing::warn!("Endpoint {} schema config: {}", self.id, e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Custom response compiled as templates on first use, when templating is on
+    pub fn compiled_response(&self) -> Option<&ResponseTemplate> {
+        self.compiled_response
+            .get_or_init(|| {
+                if !self.response_template {
+                    return None;
+                }
+                ResponseTemplate::new(
+                    self.response_headers.as_deref(),
+                    self.response_body.as_deref(),
+                )
+                .map_err(|e| tracing::warn!("Endpoint {} response template: {}", self.id, e))
+                .ok()
+            })
+            .as_ref()
+    }
+
+    /// Response rules compiled on first use and kept for as long as the endpoint stays cached
+    pub fn rule_set(&self) -> &RuleSet {
+        self.rule_set
+            .get_or_init(|| RuleSet::new(&self.id, &self.response_rules))
+    }
+
+    /// Delay and drip applied to the endpoint's own response, parsed on first use
+    pub fn latency(&self) -> Option<&ResponseLatency> {
+        self.latency
+            .get_or_init(|| {
+                let json = self.response_latency.as_deref()?;
+                serde_json::from_str(json)
+                    .map_err(|e| tracing::warn!("Endpoint {} response latency: {}", self.id, e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Response sequence compiled on first use, if the endpoint has one
+    pub fn sequence(&self) -> Option<&CompiledSequence> {
+        self.sequence
+            .get_or_init(|| {
+                let json = self.response_sequence.as_deref()?;
+                serde_json::from_str::<ResponseSequence>(json)
+                    .map_err(|e| e.to_string())
+                    .and_then(|sequence| CompiledSequence::new(&sequence))
+                    .map_err(|e| tracing::warn!("Endpoint {} response sequence: {}", self.id, e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Verifier for the endpoint's signature scheme and secrets, built on first use, if
+    /// one is configured. A stored config that does not parse is an error, to be reported
+    /// on each request.
+    pub fn signature_verifier(&self) -> Option<Result<&Verifier, &str>> {
+        self.signature_verifier
+            .get_or_init(|| {
+                let scheme = self.signature_scheme.as_deref()?;
+                let verifier = self
+                    .signature_secrets
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()
+                    .map_err(|e| format!("invalid secrets: {}", e))
+                    .and_then(|secrets| {
+                        Verifier::new(
+                            scheme.parse()?,
+                            secrets.unwrap_or_default(),
+                            self.signature_header.as_deref(),
+                            self.signature_tolerance_secs,
+                        )
+                    })
+                    .inspect_err(|e| {
+                        tracing::warn!("Endpoint {} signature config: {}", self.id, e)
+                    });
+                Some(verifier)
+            })
+            .as_ref()
+            .map(|verifier| verifier.as_ref().map_err(String::as_str))
+    }
+
+    /// Verifier for bearer tokens sent to the endpoint, if one is configured. Its keys
+    /// are parsed on first use and kept for as long as the endpoint stays cached; a
+    /// config that does not parse is kept as its error.
+    pub fn jwt_verifier(&self) -> Option<Result<&JwtVerifier, &str>> {
+        self.jwt_verifier
+            .get_or_init(|| {
+                let key_type = self.jwt_key_type.as_deref()?;
+                let verifier = key_type
+                    .parse()
+                    .and_then(|key_type| {
+                        JwtVerifier::new(
+                            key_type,
+                            self.jwt_key.as_deref().unwrap_or_default(),
+                            self.jwt_header.as_deref(),
+                            self.jwt_audience.as_deref(),
+                            self.jwt_issuer.as_deref(),
+                            self.jwt_leeway_secs,
+                        )
+                    })
+                    .inspect_err(|e| tracing::warn!("Endpoint {} JWT config: {}", self.id, e));
+                Some(verifier)
+            })
+            .as_ref()
+            .map(|verifier| verifier.as_ref().map_err(String::as_str))
+    }
+
+    /// Credentials senders must present, built on first use, if the endpoint requires any.
+    /// A stored config that does not parse refuses every request rather than letting them
+    /// all through.
+    pub fn inbound_auth(&self) -> Option<&InboundAuth> {
+        self.inbound_auth
+            .get_or_init(|| {
+                let auth_type = self.auth_type.as_deref()?;
+                let key_location = self
+                    .auth_key_location
+                    .as_deref()
+                    .map(str::parse)
+                    .transpose();
+                let auth = auth_type
+                    .parse()
+                    .and_then(|auth_type| {
+                        InboundAuth::new(
+                            auth_type,
+                            self.auth_username.as_deref(),
+                            self.auth_secret.as_deref(),
+                            self.auth_key_name.as_deref(),
+                            key_location?,
+                        )
+                    })
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Endpoint {} auth config: {}", self.id, e);
+                        InboundAuth::invalid()
+                    });
+                Some(auth)
+            })
+            .as_ref()
+    }
+
+    /// Client address lists, parsed on first use, if the endpoint restricts who may send
+    /// to it. Stored lists that do not parse block every address rather than admitting
+    /// them all.
+    pub fn ip_filter(&self) -> Option<&IpFilter> {
+        self.ip_filter
+            .get_or_init(|| {
+                let list = |json: Option<&str>| -> Result<Vec<String>, String> {
+                    json.map(serde_json::from_str)
+                        .transpose()
+                        .map(Option::unwrap_or_default)
+                        .map_err(|e| e.to_string())
+                };
+                let filter = list(self.ip_allow.as_deref())
+                    .and_then(|allow| IpFilter::new(&allow, &list(self.ip_deny.as_deref())?))
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Endpoint {} IP filter config: {}", self.id, e);
+                        IpFilter::deny_all()
+                    });
+                Some(filter).filter(|filter| !filter.is_empty())
+            })
+            .as_ref()
+    }
+
+    /// Endpoint-wide and per-client limits, parsed on first use; None uses the server
+    /// default. A stored limit that does not parse applies the strictest limit rather
+    /// than lifting it.
+    pub fn rate_limits(&self) -> (Option<RateLimit>, Option<RateLimit>) {
+        *self.rate_limits.get_or_init(|| {
+            let limit = |name: &str, stored: Option<&str>| {
+                stored.map(|limit| {
+                    limit.parse().unwrap_or_else(|e| {
+                        tracing::warn!("Endpoint {} {}: {}", self.id, name, e);
+                        RateLimit::strictest()
+                    })
+                })
+            };
+            (
+                limit("rate limit", self.rate_limit.as_deref()),
+                limit("client rate limit", self.client_rate_limit.as_deref()),
+            )
+        })
+    }
+
+    /// Headers combined with the body hash when fingerprinting a delivery
+    pub fn fingerprint_header_names(&self) -> Vec<String> {
+        self.fingerprint_headers
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Response rule model
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ResponseRule {
+    pub id: i64,
+    pub endpoint_id: String,
+    pub position: i64, // Rules are tried in ascending position, then creation order
+    pub name: Option<String>,
+    #[sqlx(json)]
+    pub matchers: Vec<Matcher>,
+    pub status: i32,
+    pub headers: Option<String>, // JSON object of header names to values
+    pub body: Option<String>,
+    pub template: bool, // Render the body and header values like a templated custom response
+    pub delay_ms: i64,
+    pub created_at: String,
 }
 
 /// Request model
@@ -28,6 +353,143 @@ pub struct Request {
     pub content_type: Option<String>,
     pub received_at: String,
     pub ip_address: Option<String>,
+    #[sqlx(default)]
+    pub body_size: Option<i64>, // Bytes received from the sender
+    #[sqlx(default)]
+    pub body_truncated: bool,
+    #[sqlx(default)]
+    pub body_file: Option<String>, // Path of the spilled body on disk
+    #[sqlx(default)]
+    pub raw_headers: Option<String>, // JSON list of headers as sent; NULL for older requests
+    #[sqlx(default)]
+    pub http_version: Option<String>, // e.g. "HTTP/1.1"
+    #[sqlx(default)]
+    pub peer_address: Option<String>, // Socket peer, e.g. a reverse proxy; ip_address is the client
+    #[sqlx(default)]
+    pub remote_port: Option<i64>,
+    #[sqlx(default)]
+    pub local_address: Option<String>, // Address the request was received on
+    #[sqlx(default)]
+    pub local_port: Option<i64>,
+    #[sqlx(default)]
+    pub tls: Option<bool>,
+    #[sqlx(default)]
+    pub request_size: Option<i64>, // Head plus body bytes received
+    #[sqlx(default)]
+    pub processing_time_us: Option<i64>, // Time spent handling the request before storage
+    #[sqlx(default)]
+    pub provider: Option<String>, // Detected webhook sender, e.g. "github"
+    #[sqlx(default)]
+    pub event_type: Option<String>, // Event reported by the provider, e.g. "push"
+    #[sqlx(default)]
+    pub signature_status: Option<String>, // "valid", "invalid", "missing", "expired" or "error"
+    #[sqlx(default)]
+    pub signature_computed: Option<String>,
+    #[sqlx(default)]
+    pub signature_provided: Option<String>,
+    #[sqlx(default)]
+    pub jwt_status: Option<String>, // "valid", "invalid", "missing", "expired" or "error"
+    #[sqlx(default)]
+    pub jwt_error: Option<String>,
+    #[sqlx(default)]
+    pub auth_status: Option<String>, // "authorized" or "unauthorized"
+    #[sqlx(default)]
+    pub auth_error: Option<String>,
+    #[sqlx(default)]
+    pub blocked: bool, // Refused by the endpoint's IP filter and kept for audit
+    #[sqlx(default)]
+    pub schema_status: Option<String>, // "valid", "invalid" or "skipped"
+    #[sqlx(default)]
+    pub schema_errors: Option<String>, // JSON list of `SchemaError`
+    #[sqlx(default)]
+    pub fingerprint: Option<String>, // Hex SHA-256 of the body and the endpoint's fingerprint headers
+    #[sqlx(default)]
+    pub delivery_id: Option<String>, // Provider's delivery ID, e.g. X-GitHub-Delivery
+    #[sqlx(default)]
+    pub duplicate_of: Option<i64>, // First request with the same delivery ID or fingerprint
+    #[sqlx(default)]
+    pub retry: i64, // 0 for a first delivery, then 1, 2, ... for each repeat
+    #[sqlx(default)]
+    pub response_error: Option<String>, // Why rendering failed, or that a spilled body was left out
+    #[sqlx(default)]
+    pub rule_id: Option<i64>, // Response rule that answered the request
+    #[sqlx(default)]
+    pub sequence_position: Option<i64>, // Step of the response sequence that answered
+    #[sqlx(default)]
+    pub response_delay_ms: Option<i64>, // Delay applied before answering
+}
+
+/// A header exactly as it was sent: original name casing and raw value bytes.
+/// Serialized as `{"name", "value"}`, with `"encoding": "base64"` when the value is not UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "RawHeaderJson", try_from = "RawHeaderJson")]
+pub struct RawHeader {
+    pub name: String,
+    pub value: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawHeaderJson {
+    name: String,
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+}
+
+impl From<RawHeader> for RawHeaderJson {
+    fn from(header: RawHeader) -> Self {
+        match String::from_utf8(header.value) {
+            Ok(value) => Self {
+                name: header.name,
+                value,
+                encoding: None,
+            },
+            Err(e) => {
+                use base64::{engine::general_purpose, Engine as _};
+                Self {
+                    name: header.name,
+                    value: general_purpose::STANDARD.encode(e.as_bytes()),
+                    encoding: Some("base64".to_string()),
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<RawHeaderJson> for RawHeader {
+    type Error = String;
+
+    fn try_from(json: RawHeaderJson) -> Result<Self, Self::Error> {
+        let value = match json.encoding.as_deref() {
+            None => json.value.into_bytes(),
+            Some("base64") => {
+                use base64::{engine::general_purpose, Engine as _};
+                general_purpose::STANDARD
+                    .decode(&json.value)
+                    .map_err(|e| format!("invalid base64 header value: {}", e))?
+            }
+            Some(other) => return Err(format!("unknown header encoding '{}'", other)),
+        };
+
+        Ok(Self {
+            name: json.name,
+            value,
+        })
+    }
+}
+
+/// A decoded query string or form parameter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryPair {
+    pub name: String,
+    pub value: String,
+}
+
+/// Parse the stored `raw_headers` JSON, returning an empty list for older requests
+pub fn parse_raw_headers(raw_headers: Option<&str>) -> Vec<RawHeader> {
+    raw_headers
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
 }
 
 /// Response for creating a new endpoint
@@ -43,7 +505,47 @@ pub struct RequestQueryParams {
     pub page: u32,
     #[serde(default = "default_limit")]
     pub limit: u32,
-    pub method: Option<String>, // Comma-separated HTTP methods
+    pub method: Option<String>,       // Comma-separated HTTP methods
+    pub path_prefix: Option<String>,  // Sub-path prefix, e.g. "/github"
+    pub http_version: Option<String>, // e.g. "HTTP/1.1"
+    pub tls: Option<bool>,
+    pub local_port: Option<i64>,
+    pub min_size: Option<i64>,      // Minimum total request size in bytes
+    pub max_size: Option<i64>,      // Maximum total request size in bytes
+    pub provider: Option<String>,   // Comma-separated providers, e.g. "github,gitlab"
+    pub event_type: Option<String>, // Comma-separated event types
+    pub signature_status: Option<String>, // Comma-separated, e.g. "invalid,missing"
+    pub jwt_status: Option<String>, // Comma-separated, e.g. "expired"
+    pub auth_status: Option<String>, // "authorized" or "unauthorized"
+    pub blocked: Option<bool>,
+    pub schema_status: Option<String>, // "valid", "invalid" or "skipped"
+    pub hide_duplicates: Option<bool>, // Only list first deliveries
+    pub rule_id: Option<i64>,          // Answered by this response rule
+}
+
+impl Default for RequestQueryParams {
+    fn default() -> Self {
+        Self {
+            page: default_page(),
+            limit: default_limit(),
+            method: None,
+            path_prefix: None,
+            http_version: None,
+            tls: None,
+            local_port: None,
+            min_size: None,
+            max_size: None,
+            provider: None,
+            event_type: None,
+            signature_status: None,
+            jwt_status: None,
+            auth_status: None,
+            blocked: None,
+            schema_status: None,
+            hide_duplicates: None,
+            rule_id: None,
+        }
+    }
 }
 
 fn default_page() -> u32 {
@@ -70,6 +572,131 @@ pub struct UpdateResponseConfig {
     pub status: i32,
     pub headers: Option<String>,
     pub body: Option<String>,
+    #[serde(default)]
+    pub template: bool, // Render the body and header values with the request's data
+}
+
+/// Request body for PUT /api/endpoints/:id/capture
+/// Omitted or null fields fall back to the server-wide defaults.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateCaptureConfig {
+    pub max_body_size: Option<i64>,
+    pub body_overflow: Option<BodyOverflow>,
+    pub durable_capture: Option<bool>,
+}
+
+/// Request body for PUT /api/endpoints/:id/protobuf
+/// A null `descriptor_set` removes the protobuf configuration.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateProtobufConfig {
+    pub descriptor_set: Option<String>, // Base64 encoded FileDescriptorSet
+    pub message_type: Option<String>,   // Default message type, e.g. "acme.Event"
+    pub type_header: Option<String>,    // Header naming the message type of each request
+}
+
+/// Request body for PUT /api/endpoints/:id/signature
+/// A null `scheme` turns verification off and removes the secrets.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateSignatureConfig {
+    pub scheme: Option<Scheme>,
+    #[serde(default)]
+    pub secrets: Vec<String>, // Every secret is tried, so old and new can overlap during rotation
+    pub header: Option<String>, // Signature header for hmac-sha256
+    pub tolerance_secs: Option<i64>,
+    #[serde(default)]
+    pub reject: bool,
+}
+
+/// Request body for PUT /api/endpoints/:id/jwt
+/// A null `key_type` turns verification off and removes the key.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateJwtConfig {
+    pub key_type: Option<KeyType>,
+    pub key: Option<String>,    // JWKS JSON, PEM public key or shared secret
+    pub header: Option<String>, // Defaults to Authorization
+    pub audience: Option<String>, // Required `aud` value
+    pub issuer: Option<String>, // Required `iss` value
+    pub leeway_secs: Option<i64>,
+}
+
+/// Request body for PUT /api/endpoints/:id/auth
+/// A null `auth_type` accepts requests from any sender, so unknown fields are refused
+/// rather than a misspelt `auth_type` turning auth off.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateAuthConfig {
+    pub auth_type: Option<AuthType>,
+    pub username: Option<String>,          // Basic auth username
+    pub secret: Option<String>,            // Basic password, bearer token or API key
+    pub key_name: Option<String>,          // Header or query parameter carrying the API key
+    pub key_location: Option<KeyLocation>, // Defaults to header
+}
+
+/// Request body for PUT /api/endpoints/:id/ip-filter
+/// Empty lists admit every client address, so unknown fields are refused rather than
+/// a misspelt list being dropped.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateIpFilterConfig {
+    #[serde(default)]
+    pub allow: Vec<String>, // CIDR blocks or single addresses
+    #[serde(default)]
+    pub deny: Vec<String>, // Checked first, so a denied address is refused even when allowed
+    pub block_status: Option<u16>, // Defaults to 403
+    #[serde(default)]
+    pub record_blocked: bool,
+}
+
+/// Request body for PUT /api/endpoints/:id/schema
+/// A null `schema` turns validation off.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateSchemaConfig {
+    pub schema: Option<serde_json::Value>,
+    #[serde(default)]
+    pub reject: bool,
+}
+
+/// Request body for PUT /api/endpoints/:id/rate-limit
+/// Limits are written as `<requests>/<s|m|h>[:<burst>]`; null falls back to the server default.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateRateLimitConfig {
+    pub limit: Option<RateLimit>,        // Shared by every sender
+    pub client_limit: Option<RateLimit>, // Applied to each client IP separately
+}
+
+/// Request body for PUT /api/endpoints/:id/fingerprint
+/// An empty list fingerprints the body alone.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateFingerprintConfig {
+    #[serde(default)]
+    pub headers: Vec<String>, // Header names hashed with the body, e.g. ["x-event-type"]
+}
+
+/// Query parameters for POST /api/endpoints/:id/sequence/reset
+#[derive(Debug, Default, Deserialize)]
+pub struct ResetSequenceParams {
+    pub sender: Option<String>, // Client IP to start over for; everyone when absent
+}
+
+/// Request body for POST /api/endpoints/:id/rules and PUT /api/endpoints/:id/rules/:rule_id
+#[derive(Debug, Deserialize)]
+pub struct ResponseRuleConfig {
+    pub name: Option<String>,
+    pub position: Option<i64>, // Defaults to after the endpoint's last rule
+    #[serde(default)]
+    pub matchers: Vec<Matcher>, // All must match; an empty list matches every request
+    #[serde(default = "default_rule_status")]
+    pub status: u16,
+    pub headers: Option<serde_json::Map<String, serde_json::Value>>,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub template: bool,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+fn default_rule_status() -> u16 {
+    200
 }
 
 /// Request with decoded body for API responses
@@ -79,50 +706,327 @@ pub struct RequestResponse {
     pub endpoint_id: String,
     pub method: String,
     pub path: String,
-    pub query_params: serde_json::Value,
+    pub sub_path: String, // Path below /webhook/{id}, always starting with "/"
+    pub query_params: serde_json::Value, // Repeated keys as arrays, bracket keys as nested objects
+    pub query_pairs: Vec<QueryPair>, // Query parameters in the order they were sent
     pub headers: serde_json::Value,
-    pub body: Option<String>, // Base64 encoded or UTF-8 string
+    pub raw_headers: Vec<RawHeader>, // Headers in the order and casing they were sent
+    pub body: Option<String>,        // Base64 encoded or UTF-8 string; None when spilled to disk
+    pub decoded: Option<DecodedBody>, // Body with its Content-Encoding removed
+    pub form_params: Option<serde_json::Value>, // Fields of a urlencoded form body
+    pub parts: Option<Vec<FormPart>>, // Parts of a multipart/form-data body
+    pub protobuf: Option<ProtobufBody>, // Decoded with the endpoint's descriptor set
+    pub structured: Option<StructuredBody>, // MessagePack, CBOR or XML rendered as JSON
+    pub body_size: i64,
+    pub body_truncated: bool,
+    pub body_spilled: bool, // Full body is available from GET /api/requests/:id/body
     pub content_type: Option<String>,
     pub received_at: String,
     pub ip_address: Option<String>,
+    pub peer_address: Option<String>,
+    pub remote_port: Option<i64>,
+    pub local_address: Option<String>,
+    pub local_port: Option<i64>,
+    pub http_version: Option<String>,
+    pub tls: Option<bool>,
+    pub request_size: Option<i64>,
+    pub processing_time_us: Option<i64>,
+    pub provider: Option<String>,
+    pub event_type: Option<String>,
+    pub signature: Option<SignatureCheck>, // Set when the endpoint verifies signatures
+    pub jwts: Vec<JwtToken>,               // Tokens found in headers, decoded without verification
+    pub jwt: Option<JwtCheck>,             // Set when the endpoint verifies tokens
+    pub auth: Option<AuthCheck>,           // Set when the endpoint requires credentials
+    pub blocked: bool,                     // Refused by the endpoint's IP filter
+    pub schema: Option<SchemaCheck>,       // Set when the endpoint validates JSON bodies
+    pub fingerprint: Option<String>,
+    pub delivery_id: Option<String>,
+    pub duplicate_of: Option<i64>, // ID of the first delivery when this one repeats it
+    pub retry: i64,                // How many times the delivery had been seen before
+    pub response_error: Option<String>, // Set when rendering failed or left out a spilled body
+    pub rule_id: Option<i64>,      // Response rule that answered, if any
+    pub sequence_position: Option<i64>, // Step of the response sequence that answered, if any
+    pub response_delay_ms: Option<i64>, // Delay applied before answering, if any
 }
 
 impl From<Request> for RequestResponse {
     fn from(req: Request) -> Self {
+        Self::with_views(req, None, crate::decode::encoding::MAX_DECODED_SIZE)
+    }
+}
+
+impl RequestResponse {
+    /// Build the API response, decoding protobuf bodies with the endpoint's decoder and
+    /// at most `max_decoded` bytes of an encoded body
+    pub fn with_views(
+        req: Request,
+        protobuf: Option<&ProtobufDecoder>,
+        max_decoded: usize,
+    ) -> Self {
         // Parse headers JSON string into serde_json::Value
         let headers = serde_json::from_str(&req.headers).unwrap_or_else(|_| serde_json::json!({}));
 
-        // Parse query string into object
-        let query_params = if let Some(qs) = req.query_string {
-            let mut params = serde_json::Map::new();
-            for (key, value) in form_urlencoded::parse(qs.as_bytes()) {
-                params.insert(
-                    key.into_owned(),
-                    serde_json::Value::String(value.into_owned()),
-                );
-            }
-            serde_json::Value::Object(params)
-        } else {
-            serde_json::json!({})
-        };
+        // Query string as ordered pairs and as a structured object
+        let query_pairs = crate::decode::params::parse_pairs(
+            req.query_string.as_deref().unwrap_or_default().as_bytes(),
+        );
+        let query_params = crate::decode::params::structure(&query_pairs);
+
+        let raw_headers = parse_raw_headers(req.raw_headers.as_deref());
+        let jwts = crate::analysis::jwt::find_tokens(&headers);
+        let views = crate::decode::body_views(
+            &headers,
+            req.content_type.as_deref(),
+            req.body.as_deref(),
+            protobuf,
+            max_decoded,
+        );
+        let sub_path = webhook_sub_path(&req.path, &req.endpoint_id);
+        let body_size = req
+            .body_size
+            .unwrap_or_else(|| req.body.as_ref().map_or(0, |b| b.len() as i64));
 
         Self {
             id: req.id,
             endpoint_id: req.endpoint_id,
             method: req.method,
             path: req.path,
+            sub_path,
             query_params,
+            query_pairs,
+            raw_headers,
+            decoded: views.decoded,
+            form_params: views.form_params,
+            parts: views.parts,
+            protobuf: views.protobuf,
+            structured: views.structured,
             headers,
-            body: req.body.map(|b| {
-                // Try to decode as UTF-8, otherwise base64
-                String::from_utf8(b.clone()).unwrap_or_else(|_| {
-                    use base64::{engine::general_purpose, Engine as _};
-                    general_purpose::STANDARD.encode(&b)
-                })
-            }),
+            body: req.body.map(body_to_string),
+            body_size,
+            body_truncated: req.body_truncated,
+            body_spilled: req.body_file.is_some(),
             content_type: req.content_type,
             received_at: req.received_at,
             ip_address: req.ip_address,
+            peer_address: req.peer_address,
+            remote_port: req.remote_port,
+            local_address: req.local_address,
+            local_port: req.local_port,
+            http_version: req.http_version,
+            tls: req.tls,
+            request_size: req.request_size,
+            processing_time_us: req.processing_time_us,
+            provider: req.provider,
+            event_type: req.event_type,
+            signature: SignatureCheck::from_columns(
+                req.signature_status,
+                req.signature_computed,
+                req.signature_provided,
+            ),
+            jwts,
+            jwt: JwtCheck::from_columns(req.jwt_status, req.jwt_error),
+            auth: AuthCheck::from_columns(req.auth_status, req.auth_error),
+            blocked: req.blocked,
+            schema: SchemaCheck::from_columns(req.schema_status, req.schema_errors),
+            fingerprint: req.fingerprint,
+            delivery_id: req.delivery_id,
+            duplicate_of: req.duplicate_of,
+            retry: req.retry,
+            response_error: req.response_error,
+            rule_id: req.rule_id,
+            sequence_position: req.sequence_position,
+            response_delay_ms: req.response_delay_ms,
         }
     }
 }
+
+/// Body bytes as a UTF-8 string, or base64 when they are not valid UTF-8
+pub fn body_to_string(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|e| {
+        use base64::{engine::general_purpose, Engine as _};
+        general_purpose::STANDARD.encode(e.as_bytes())
+    })
+}
+
+/// A captured body with its Content-Encoding removed; the stored body is left as sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedBody {
+    pub encoding: String,     // Codings that were removed, e.g. "gzip" or "gzip, br"
+    pub body: Option<String>, // Base64 encoded or UTF-8 string
+    pub size: i64,
+    pub truncated: bool, // Decoded output hit the size limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // Decoding failed; body holds the output up to the failure
+}
+
+/// A protobuf body rendered as JSON with the endpoint's descriptor set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtobufBody {
+    pub message_type: Option<String>,
+    pub json: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // Why the body could not be decoded
+}
+
+/// A MessagePack, CBOR or XML body rendered as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructuredBody {
+    pub format: String, // "msgpack", "cbor" or "xml"
+    pub json: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // Why the body could not be decoded
+}
+
+/// Signature verification result stored with a captured request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureCheck {
+    pub status: String, // "valid", "invalid", "missing", "expired" or "error"
+    pub computed: Option<String>, // Expected signature, written like the provided one
+    pub provided: Option<String>, // Signature header as sent
+}
+
+impl SignatureCheck {
+    /// Rebuild the result from its stored columns; `None` for unverified requests
+    pub fn from_columns(
+        status: Option<String>,
+        computed: Option<String>,
+        provided: Option<String>,
+    ) -> Option<Self> {
+        Some(Self {
+            status: status?,
+            computed,
+            provided,
+        })
+    }
+}
+
+/// A JWT found in a request header
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtToken {
+    pub header_name: String,       // HTTP header the token was sent in
+    pub header: serde_json::Value, // JOSE header, e.g. {"alg": "RS256", "kid": "..."}
+    pub claims: serde_json::Value,
+}
+
+/// JWT verification result stored with a captured request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtCheck {
+    pub status: String, // "valid", "invalid", "missing", "expired" or "error"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // Why the token was not accepted
+}
+
+impl JwtCheck {
+    /// Rebuild the result from its stored columns; `None` for unverified requests
+    pub fn from_columns(status: Option<String>, error: Option<String>) -> Option<Self> {
+        Some(Self {
+            status: status?,
+            error,
+        })
+    }
+}
+
+/// JSON Schema validation result stored with a captured request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaCheck {
+    pub status: String, // "valid", "invalid" or "skipped"
+    pub errors: Vec<SchemaError>,
+}
+
+impl SchemaCheck {
+    /// Rebuild the result from its stored columns; `None` for unvalidated requests
+    pub fn from_columns(status: Option<String>, errors: Option<String>) -> Option<Self> {
+        Some(Self {
+            status: status?,
+            errors: errors
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Inbound credential check stored with a captured request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthCheck {
+    pub status: String, // "authorized" or "unauthorized"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // e.g. "missing credentials"
+}
+
+impl AuthCheck {
+    /// Rebuild the result from its stored columns; `None` when no credentials were required
+    pub fn from_columns(status: Option<String>, error: Option<String>) -> Option<Self> {
+        Some(Self {
+            status: status?,
+            error,
+        })
+    }
+}
+
+/// One part of a `multipart/form-data` body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormPart {
+    pub name: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub size: i64,
+    pub headers: Vec<RawHeader>,
+    pub value: Option<String>, // Text of non-file fields; files are served from /api/requests/:id/parts/:n
+}
+
+/// Base path under which an endpoint captures requests
+pub fn webhook_base_path(endpoint_id: &str) -> String {
+    format!("/webhook/{}", endpoint_id)
+}
+
+/// Extract the part of a captured path below `/webhook/{id}`.
+/// The bare endpoint URL maps to "/"; paths outside the endpoint are returned unchanged.
+pub fn webhook_sub_path(path: &str, endpoint_id: &str) -> String {
+    match path.strip_prefix(&webhook_base_path(endpoint_id)) {
+        Some("") => "/".to_string(),
+        Some(rest) if rest.starts_with('/') => rest.to_string(),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_header_round_trip() {
+        let headers = vec![
+            RawHeader {
+                name: "X-Hub-Signature".to_string(),
+                value: b"sha256=abc".to_vec(),
+            },
+            RawHeader {
+                name: "x-binary".to_string(),
+                value: vec![0xff, 0x00, 0x80],
+            },
+        ];
+
+        let json = serde_json::to_string(&headers).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"name":"X-Hub-Signature","value":"sha256=abc"},{"name":"x-binary","value":"/wCA","encoding":"base64"}]"#
+        );
+        assert_eq!(parse_raw_headers(Some(&json)), headers);
+        assert!(parse_raw_headers(None).is_empty());
+    }
+
+    #[test]
+    fn test_webhook_sub_path() {
+        assert_eq!(webhook_sub_path("/webhook/abc", "abc"), "/");
+        assert_eq!(webhook_sub_path("/webhook/abc/", "abc"), "/");
+        assert_eq!(
+            webhook_sub_path("/webhook/abc/github/push", "abc"),
+            "/github/push"
+        );
+        assert_eq!(
+            webhook_sub_path("/webhook/abcdef", "abc"),
+            "/webhook/abcdef"
+        );
+        assert_eq!(webhook_sub_path("/test", "abc"), "/test");
+    }
+}
@@ -0,0 +1,268 @@
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+
+/// Resolve the client IP of a request that arrived from `peer`.
+///
+/// Forwarding headers are only honored when the peer is a trusted proxy. The hops
+/// they list are walked from the nearest one back, skipping trusted proxies, and the
+/// first untrusted address is the client. `Forwarded` (RFC 7239) takes precedence
+/// over `X-Forwarded-For`, which takes precedence over `X-Real-IP`.
+pub fn resolve_client_ip(peer: IpAddr, headers: &HeaderMap, trusted: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(&peer) {
+        return peer;
+    }
+
+    let hops = forwarded_hops(headers)
+        .or_else(|| x_forwarded_for_hops(headers))
+        .or_else(|| x_real_ip_hops(headers))
+        .unwrap_or_default();
+
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        match hop {
+            Some(ip) => {
+                client = *ip;
+                if !is_trusted(ip) {
+                    break;
+                }
+            }
+            // An obfuscated or unknown hop; the last proxy we trust is as far as we can see
+            None => break,
+        }
+    }
+
+    client
+}
+
+/// Resolve whether the client of a request that arrived from `peer` used TLS.
+///
+/// From a trusted proxy this is the `proto` of the `Forwarded` element for the hop
+/// [`resolve_client_ip`] picks as the client, falling back to the last
+/// `X-Forwarded-Proto` value, and `None` when the proxy reports neither. From anyone
+/// else it is what the connection itself says.
+pub fn resolve_tls(
+    peer: IpAddr,
+    headers: &HeaderMap,
+    trusted: &[IpNet],
+    connection_tls: Option<bool>,
+) -> Option<bool> {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(&peer) {
+        return connection_tls;
+    }
+
+    let mut proto = None;
+    for element in forwarded_elements(headers).iter().rev() {
+        let Some(Some(ip)) = element.node else {
+            continue;
+        };
+        proto = element.proto;
+        if !is_trusted(&ip) {
+            break;
+        }
+    }
+
+    proto
+        .or_else(|| list_values(headers, "x-forwarded-proto").pop())
+        .map(|proto| proto.eq_ignore_ascii_case("https") || proto.eq_ignore_ascii_case("wss"))
+}
+
+/// One element of a `Forwarded` header
+struct ForwardedElement<'a> {
+    /// The `for` node: absent, or an address that may be unknown or obfuscated
+    node: Option<Option<IpAddr>>,
+    proto: Option<&'a str>,
+}
+
+/// Header values joined across repeated headers, split on commas
+fn list_values<'a>(headers: &'a HeaderMap, name: &str) -> Vec<&'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn forwarded_elements(headers: &HeaderMap) -> Vec<ForwardedElement<'_>> {
+    list_values(headers, "forwarded")
+        .into_iter()
+        .map(|element| {
+            let pair = |name: &str| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.split_once('=')?;
+                    key.trim()
+                        .eq_ignore_ascii_case(name)
+                        .then(|| value.trim().trim_matches('"'))
+                })
+            };
+            ForwardedElement {
+                node: pair("for").map(parse_node),
+                proto: pair("proto"),
+            }
+        })
+        .collect()
+}
+
+fn forwarded_hops(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let hops: Vec<Option<IpAddr>> = forwarded_elements(headers)
+        .into_iter()
+        .filter_map(|element| element.node)
+        .collect();
+    (!hops.is_empty()).then_some(hops)
+}
+
+fn x_forwarded_for_hops(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let hops: Vec<Option<IpAddr>> = list_values(headers, "x-forwarded-for")
+        .into_iter()
+        .map(parse_node)
+        .collect();
+    (!hops.is_empty()).then_some(hops)
+}
+
+fn x_real_ip_hops(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let value = headers.get("x-real-ip")?.to_str().ok()?.trim();
+    Some(vec![parse_node(value)])
+}
+
+/// Parse an address as it appears in forwarding headers: a bare IP, `ip:port`,
+/// or `[ipv6]` with an optional port. Returns `None` for `unknown` and obfuscated names.
+fn parse_node(value: &str) -> Option<IpAddr> {
+    if let Ok(ip) = value.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    let bracketed = value.strip_prefix('[')?;
+    bracketed.split(']').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn trusted() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap(), "::1/128".parse().unwrap()]
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_untrusted_peer_ignores_headers() {
+        let headers = headers(&[("x-forwarded-for", "1.2.3.4")]);
+        assert_eq!(
+            resolve_client_ip(ip("203.0.113.9"), &headers, &trusted()),
+            ip("203.0.113.9")
+        );
+    }
+
+    #[test]
+    fn test_x_forwarded_for_skips_trusted_hops() {
+        let headers = headers(&[
+            ("x-forwarded-for", "6.6.6.6, 1.2.3.4"),
+            ("x-forwarded-for", "10.0.0.2"),
+        ]);
+        // 6.6.6.6 was supplied by the client and is not believed
+        assert_eq!(
+            resolve_client_ip(ip("10.0.0.1"), &headers, &trusted()),
+            ip("1.2.3.4")
+        );
+    }
+
+    #[test]
+    fn test_forwarded_takes_precedence() {
+        let headers = headers(&[
+            (
+                "forwarded",
+                r#"for="[2001:db8::1]:4711";proto=https, for=10.0.0.3"#,
+            ),
+            ("x-forwarded-for", "1.2.3.4"),
+            ("x-real-ip", "5.6.7.8"),
+        ]);
+        assert_eq!(
+            resolve_client_ip(ip("::1"), &headers, &trusted()),
+            ip("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn test_x_real_ip_and_unknown_hops() {
+        let headers_real_ip = headers(&[("x-real-ip", "5.6.7.8")]);
+        assert_eq!(
+            resolve_client_ip(ip("10.0.0.1"), &headers_real_ip, &trusted()),
+            ip("5.6.7.8")
+        );
+
+        let headers_unknown = headers(&[("forwarded", "for=unknown, for=10.0.0.7")]);
+        assert_eq!(
+            resolve_client_ip(ip("10.0.0.1"), &headers_unknown, &trusted()),
+            ip("10.0.0.7")
+        );
+
+        assert_eq!(
+            resolve_client_ip(ip("10.0.0.1"), &HeaderMap::new(), &trusted()),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_resolve_tls() {
+        let untrusted = headers(&[("x-forwarded-proto", "https")]);
+        assert_eq!(
+            resolve_tls(ip("203.0.113.9"), &untrusted, &trusted(), Some(false)),
+            Some(false)
+        );
+        assert_eq!(
+            resolve_tls(ip("10.0.0.1"), &untrusted, &trusted(), Some(false)),
+            Some(true)
+        );
+
+        // The client's own element counts, not the one between the proxies
+        let forwarded = headers(&[
+            (
+                "forwarded",
+                "for=1.2.3.4;proto=https, for=10.0.0.3;proto=http",
+            ),
+            ("x-forwarded-proto", "http"),
+        ]);
+        assert_eq!(
+            resolve_tls(ip("10.0.0.1"), &forwarded, &trusted(), Some(false)),
+            Some(true)
+        );
+
+        let appended = headers(&[("x-forwarded-proto", "https, http")]);
+        assert_eq!(
+            resolve_tls(ip("10.0.0.1"), &appended, &trusted(), None),
+            Some(false)
+        );
+
+        // A trusted proxy that says nothing leaves it unknown
+        assert_eq!(
+            resolve_tls(ip("10.0.0.1"), &HeaderMap::new(), &trusted(), Some(false)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_node() {
+        assert_eq!(parse_node("1.2.3.4:80"), Some(ip("1.2.3.4")));
+        assert_eq!(parse_node("[::1]"), Some(ip("::1")));
+        assert_eq!(parse_node("_hidden"), None);
+    }
+}
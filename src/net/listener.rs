@@ -0,0 +1,188 @@
+use super::proxy_protocol;
+use super::wire::WireTap;
+use super::ConnectionInfo;
+use axum::extract::connect_info::Connected;
+use axum::serve::{IncomingStream, Listener};
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+/// How long a connection has to send its PROXY protocol header
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// TCP listener whose connections feed every byte read to a [`WireTap`]
+pub struct WireListener {
+    incoming: Incoming,
+    local_addr: SocketAddr,
+}
+
+enum Incoming {
+    Direct(TcpListener),
+    /// Connections whose PROXY protocol header has already been read
+    ProxyProtocol(mpsc::Receiver<(TappedStream, SocketAddr)>),
+}
+
+impl WireListener {
+    pub fn new(inner: TcpListener) -> io::Result<Self> {
+        Ok(Self {
+            local_addr: inner.local_addr()?,
+            incoming: Incoming::Direct(inner),
+        })
+    }
+
+    /// Accept connections that start with a PROXY protocol v1 or v2 header, taking
+    /// the client address and TLS state from the header. Connections without one are
+    /// dropped.
+    pub fn with_proxy_protocol(inner: TcpListener) -> io::Result<Self> {
+        let local_addr = inner.local_addr()?;
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(accept_proxied(inner, tx));
+
+        Ok(Self {
+            incoming: Incoming::ProxyProtocol(rx),
+            local_addr,
+        })
+    }
+}
+
+/// Accept loop for PROXY protocol connections. Headers are read in a task per
+/// connection so a slow sender can't hold up the others.
+async fn accept_proxied(mut listener: TcpListener, tx: mpsc::Sender<(TappedStream, SocketAddr)>) {
+    loop {
+        let (mut stream, peer_addr) = Listener::accept(&mut listener).await;
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let header = tokio::time::timeout(
+                PROXY_HEADER_TIMEOUT,
+                proxy_protocol::read_header(&mut stream),
+            )
+            .await;
+            let header = match header {
+                Ok(Ok(header)) => header,
+                Ok(Err(e)) => {
+                    tracing::warn!("Dropping connection from {}: {}", peer_addr, e);
+                    return;
+                }
+                Err(_) => {
+                    tracing::warn!(
+                        "Dropping connection from {}: no PROXY protocol header",
+                        peer_addr
+                    );
+                    return;
+                }
+            };
+
+            let stream = TappedStream::new(stream, peer_addr, header.tls);
+            let _ = tx.send((stream, header.source.unwrap_or(peer_addr))).await;
+        });
+    }
+}
+
+impl Listener for WireListener {
+    type Io = TappedStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match &mut self.incoming {
+            Incoming::Direct(listener) => {
+                let (stream, addr) = Listener::accept(listener).await;
+                // hookshot itself only speaks plain TCP
+                (TappedStream::new(stream, addr, Some(false)), addr)
+            }
+            Incoming::ProxyProtocol(rx) => match rx.recv().await {
+                Some(connection) => connection,
+                // The accept loop only stops if the runtime is shutting down
+                None => std::future::pending().await,
+            },
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+impl Connected<IncomingStream<'_, WireListener>> for ConnectionInfo {
+    fn connect_info(stream: IncomingStream<'_, WireListener>) -> Self {
+        Self {
+            remote_addr: *stream.remote_addr(),
+            peer_addr: stream.io().peer_addr,
+            local_addr: stream.io().local_addr,
+            tls: stream.io().tls,
+            tap: Some(stream.io().tap.clone()),
+        }
+    }
+}
+
+/// A TCP stream that shows the bytes it reads to its connection's tap
+pub struct TappedStream {
+    inner: TcpStream,
+    peer_addr: SocketAddr,
+    local_addr: Option<SocketAddr>,
+    /// Whether the client used TLS, if known
+    tls: Option<bool>,
+    tap: Arc<WireTap>,
+}
+
+impl TappedStream {
+    fn new(inner: TcpStream, peer_addr: SocketAddr, tls: Option<bool>) -> Self {
+        Self {
+            local_addr: inner.local_addr().ok(),
+            inner,
+            peer_addr,
+            tls,
+            tap: Arc::new(WireTap::new()),
+        }
+    }
+}
+
+impl AsyncRead for TappedStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            self.tap.observe(&buf.filled()[filled..]);
+        }
+        result
+    }
+}
+
+impl AsyncWrite for TappedStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,101 @@
+pub mod client_ip;
+pub mod listener;
+pub mod proxy_protocol;
+pub mod wire;
+
+pub use listener::WireListener;
+
+use crate::models::RawHeader;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, HeaderMap, Method, Uri, Version},
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use wire::{RawHead, WireTap};
+
+/// Information about the connection a request arrived on.
+///
+/// Set by [`WireListener`] in the server. Falls back to a plain `ConnectInfo<SocketAddr>`
+/// (e.g. `MockConnectInfo` in tests), in which case no wire data is available.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    /// Client address, taken from the PROXY protocol header when enabled
+    pub remote_addr: SocketAddr,
+    /// Address of the socket peer, e.g. the load balancer sending the PROXY header
+    pub peer_addr: SocketAddr,
+    /// Address the connection was accepted on
+    pub local_addr: Option<SocketAddr>,
+    /// Whether the connection is encrypted, or `None` when that isn't known
+    pub tls: Option<bool>,
+    pub tap: Option<Arc<WireTap>>,
+}
+
+impl ConnectionInfo {
+    /// Head of a request exactly as sent when the wire tap saw it, otherwise rebuilt
+    /// from the parsed request (lowercase header names, raw values, estimated size)
+    pub fn request_head(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        version: Version,
+        headers: &HeaderMap,
+    ) -> RawHead {
+        let target = uri.to_string();
+        if let Some(head) = self
+            .tap
+            .as_ref()
+            .and_then(|tap| tap.take_head(method.as_str(), &target))
+        {
+            return head;
+        }
+
+        let headers: Vec<RawHeader> = headers
+            .iter()
+            .map(|(name, value)| RawHeader {
+                name: name.as_str().to_string(),
+                value: value.as_bytes().to_vec(),
+            })
+            .collect();
+        // "METHOD target HTTP/x.y\r\n", "name: value\r\n" per header, then "\r\n"
+        let size = method.as_str().len()
+            + target.len()
+            + format!("{:?}", version).len()
+            + 4
+            + headers
+                .iter()
+                .map(|h| h.name.len() + h.value.len() + 4)
+                .sum::<usize>()
+            + 2;
+
+        RawHead {
+            method: method.to_string(),
+            target,
+            headers,
+            size,
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for ConnectionInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = <ConnectInfo<SocketAddr> as FromRequestParts<S>>::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(ConnectInfo(info)) = parts.extensions.get::<ConnectInfo<Self>>() {
+            return Ok(info.clone());
+        }
+
+        let ConnectInfo(remote_addr) =
+            ConnectInfo::<SocketAddr>::from_request_parts(parts, state).await?;
+        Ok(Self {
+            remote_addr,
+            peer_addr: remote_addr,
+            local_addr: None,
+            tls: None,
+            tap: None,
+        })
+    }
+}
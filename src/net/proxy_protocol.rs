@@ -0,0 +1,234 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Signature that starts every PROXY protocol v2 header
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Longest possible v1 header, including the trailing CRLF
+const V1_MAX_LENGTH: usize = 107;
+
+/// v2 TLV describing the TLS session between the client and the proxy
+const PP2_TYPE_SSL: u8 = 0x20;
+
+/// Flag in the SSL TLV set when the client connected over TLS
+const PP2_CLIENT_SSL: u8 = 0x01;
+
+/// What a PROXY protocol header says about the original connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProxyHeader {
+    /// Client address, or `None` when the sender reports none (v1 `UNKNOWN`, v2 `LOCAL`
+    /// or an unsupported address family)
+    pub source: Option<SocketAddr>,
+    /// Whether the client used TLS, when a v2 header carries an SSL TLV
+    pub tls: Option<bool>,
+}
+
+/// Read a PROXY protocol v1 or v2 header from the start of a connection.
+///
+/// Exactly the header is consumed, so the HTTP request that follows is left on the stream.
+pub async fn read_header<R>(stream: &mut R) -> io::Result<ProxyHeader>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut prefix = [0u8; 12];
+    stream.read_exact(&mut prefix).await?;
+
+    if prefix == V2_SIGNATURE {
+        read_v2(stream).await
+    } else if prefix.starts_with(b"PROXY ") {
+        read_v1(stream, &prefix).await
+    } else {
+        Err(invalid("missing PROXY protocol header"))
+    }
+}
+
+async fn read_v1<R>(stream: &mut R, prefix: &[u8]) -> io::Result<ProxyHeader>
+where
+    R: AsyncRead + Unpin,
+{
+    // Read one byte at a time so nothing past the header is consumed
+    let mut line = prefix.to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LENGTH {
+            return Err(invalid("PROXY v1 header too long"));
+        }
+        line.push(stream.read_u8().await?);
+    }
+
+    let line = std::str::from_utf8(&line[..line.len() - 2])
+        .map_err(|_| invalid("PROXY v1 header is not ASCII"))?;
+    let fields: Vec<&str> = line.split(' ').collect();
+    let source = match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => None,
+        ["PROXY", "TCP4" | "TCP6", source, _destination, source_port, _destination_port] => {
+            let ip: IpAddr = source
+                .parse()
+                .map_err(|_| invalid("invalid PROXY v1 source address"))?;
+            let port: u16 = source_port
+                .parse()
+                .map_err(|_| invalid("invalid PROXY v1 source port"))?;
+            Some(SocketAddr::new(ip, port))
+        }
+        _ => return Err(invalid("malformed PROXY v1 header")),
+    };
+    // v1 has no way to describe TLS
+    Ok(ProxyHeader { source, tls: None })
+}
+
+async fn read_v2<R>(stream: &mut R) -> io::Result<ProxyHeader>
+where
+    R: AsyncRead + Unpin,
+{
+    let version_command = stream.read_u8().await?;
+    let family = stream.read_u8().await?;
+    let length = stream.read_u16().await? as usize;
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload).await?;
+
+    if version_command >> 4 != 2 {
+        return Err(invalid("unsupported PROXY protocol version"));
+    }
+    match version_command & 0x0f {
+        // LOCAL: health checks from the proxy itself
+        0 => return Ok(ProxyHeader::default()),
+        1 => {}
+        _ => return Err(invalid("unsupported PROXY v2 command")),
+    }
+
+    let (source, address_length) = match family >> 4 {
+        // AF_INET: source and destination addresses, then ports
+        1 if payload.len() >= 12 => {
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(&payload[0..4]).unwrap());
+            let port = u16::from_be_bytes([payload[8], payload[9]]);
+            (Some(SocketAddr::new(ip.into(), port)), 12)
+        }
+        // AF_INET6
+        2 if payload.len() >= 36 => {
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&payload[0..16]).unwrap());
+            let port = u16::from_be_bytes([payload[32], payload[33]]);
+            (Some(SocketAddr::new(ip.into(), port)), 36)
+        }
+        1 | 2 => return Err(invalid("truncated PROXY v2 address block")),
+        // AF_UNIX: two 108 byte paths, which are no use as a client address
+        3 => (None, 216),
+        // AF_UNSPEC carries no address
+        _ => (None, 0),
+    };
+
+    let tls = payload
+        .get(address_length..)
+        .and_then(ssl_tlv)
+        .map(|client| client & PP2_CLIENT_SSL != 0);
+    Ok(ProxyHeader { source, tls })
+}
+
+/// `client` flags of the SSL TLV among a v2 header's TLVs, if there is one
+fn ssl_tlv(mut tlvs: &[u8]) -> Option<u8> {
+    while tlvs.len() >= 3 {
+        let kind = tlvs[0];
+        let length = u16::from_be_bytes([tlvs[1], tlvs[2]]) as usize;
+        let value = tlvs.get(3..3 + length)?;
+        if kind == PP2_TYPE_SSL {
+            return value.first().copied();
+        }
+        tlvs = &tlvs[3 + length..];
+    }
+    None
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn parse(bytes: &[u8]) -> (io::Result<ProxyHeader>, Vec<u8>) {
+        let mut stream = bytes;
+        let result = read_header(&mut stream).await;
+        (result, stream.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_v1_tcp4() {
+        let (result, rest) =
+            parse(b"PROXY TCP4 203.0.113.7 10.0.0.1 51234 443\r\nGET / HTTP/1.1\r\n\r\n").await;
+
+        let header = result.unwrap();
+        assert_eq!(header.source, Some("203.0.113.7:51234".parse().unwrap()));
+        assert_eq!(header.tls, None);
+        assert_eq!(rest, b"GET / HTTP/1.1\r\n\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_v1_unknown() {
+        let (result, rest) = parse(b"PROXY UNKNOWN\r\nGET").await;
+
+        assert_eq!(result.unwrap(), ProxyHeader::default());
+        assert_eq!(rest, b"GET");
+    }
+
+    #[tokio::test]
+    async fn test_v2_tcp6() {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x21, 0x21, 0x00, 36]);
+        header.extend_from_slice(&"2001:db8::7".parse::<Ipv6Addr>().unwrap().octets());
+        header.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        header.extend_from_slice(&4711u16.to_be_bytes());
+        header.extend_from_slice(&443u16.to_be_bytes());
+        header.extend_from_slice(b"GET");
+
+        let (result, rest) = parse(&header).await;
+
+        assert_eq!(
+            result.unwrap().source,
+            Some("[2001:db8::7]:4711".parse().unwrap())
+        );
+        assert_eq!(rest, b"GET");
+    }
+
+    #[tokio::test]
+    async fn test_v2_ssl_tlv() {
+        let header = |tlvs: &[u8]| {
+            let mut header = V2_SIGNATURE.to_vec();
+            header.extend_from_slice(&[0x21, 0x11]);
+            header.extend_from_slice(&(12 + tlvs.len() as u16).to_be_bytes());
+            header.extend_from_slice(&[203, 0, 113, 7, 10, 0, 0, 1]);
+            header.extend_from_slice(&4711u16.to_be_bytes());
+            header.extend_from_slice(&443u16.to_be_bytes());
+            header.extend_from_slice(tlvs);
+            header
+        };
+        // A NOOP TLV, then SSL with PP2_CLIENT_SSL set and a version sub-TLV
+        let tls = [
+            &[0x04, 0x00, 0x01, 0x00][..],
+            &[0x20, 0x00, 0x0b, 0x01, 0, 0, 0, 0, 0x21, 0x00, 0x03],
+            b"1.3",
+        ]
+        .concat();
+        let plain = [0x20, 0x00, 0x05, 0x00, 0, 0, 0, 0];
+
+        assert_eq!(parse(&header(&tls)).await.0.unwrap().tls, Some(true));
+        assert_eq!(parse(&header(&plain)).await.0.unwrap().tls, Some(false));
+        assert_eq!(parse(&header(&[])).await.0.unwrap().tls, None);
+    }
+
+    #[tokio::test]
+    async fn test_v2_local() {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x20, 0x00, 0x00, 0x00]);
+
+        let (result, _) = parse(&header).await;
+
+        assert_eq!(result.unwrap(), ProxyHeader::default());
+    }
+
+    #[tokio::test]
+    async fn test_missing_header() {
+        let (result, _) = parse(b"GET / HTTP/1.1\r\n\r\n").await;
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
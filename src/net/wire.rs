@@ -0,0 +1,384 @@
+use crate::models::RawHeader;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Largest request head the tap will buffer before giving up on a connection
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Largest chunk-size or trailer line the tap will buffer
+const MAX_LINE_SIZE: usize = 8 * 1024;
+
+/// Maximum number of headers parsed per request head
+const MAX_HEADERS: usize = 256;
+
+/// Number of unclaimed heads kept per connection, e.g. for requests to the API
+const MAX_PENDING_HEADS: usize = 16;
+
+/// An HTTP/1 request head as it appeared on the wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawHead {
+    pub method: String,
+    pub target: String,
+    pub headers: Vec<RawHeader>,
+    /// Size of the head in bytes, including the blank line ending it
+    pub size: usize,
+}
+
+/// Where the parser is within the byte stream of a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Reading a request line and headers
+    Head,
+    /// Skipping a fixed-length body
+    Body(u64),
+    /// Reading a chunk-size line
+    ChunkSize,
+    /// Skipping chunk data
+    ChunkData(u64),
+    /// Skipping the line break after chunk data
+    ChunkEnd,
+    /// Reading trailer fields after the last chunk
+    Trailers,
+    /// The stream is no longer HTTP/1 (upgrade, HTTP/2 or malformed); stop parsing
+    Stopped,
+}
+
+/// Incremental parser that extracts request heads from the bytes read on a
+/// connection, following body framing to find where the next request starts.
+#[derive(Debug)]
+pub struct HeadParser {
+    state: State,
+    buf: Vec<u8>,
+}
+
+impl Default for HeadParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeadParser {
+    pub fn new() -> Self {
+        Self {
+            state: State::Head,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Feed bytes read from the connection, returning the heads completed by them
+    pub fn feed(&mut self, data: &[u8]) -> Vec<RawHead> {
+        let mut heads = Vec::new();
+        let mut pos = 0;
+
+        while pos < data.len() {
+            match self.state {
+                State::Head => {
+                    // Empty lines before a request line are ignored (RFC 9112 section 2.2)
+                    if self.buf.is_empty() && matches!(data[pos], b'\r' | b'\n') {
+                        pos += 1;
+                        continue;
+                    }
+
+                    let search_from = self.buf.len().saturating_sub(3);
+                    self.buf.extend_from_slice(&data[pos..]);
+                    pos = data.len();
+
+                    match find_head_end(&self.buf[search_from..]) {
+                        Some(end) => {
+                            let end = search_from + end;
+                            // Hand the bytes past the head back to the loop
+                            pos -= self.buf.len() - end;
+                            self.buf.truncate(end);
+                            let head = std::mem::take(&mut self.buf);
+                            if let Some(head) = self.parse_head(&head) {
+                                heads.push(head);
+                            }
+                        }
+                        None if self.buf.len() > MAX_HEAD_SIZE => self.stop(),
+                        None => {}
+                    }
+                }
+                State::Body(remaining) | State::ChunkData(remaining) => {
+                    let skip = remaining.min((data.len() - pos) as u64);
+                    pos += skip as usize;
+                    let remaining = remaining - skip;
+                    self.state = match (self.state, remaining) {
+                        (State::Body(_), 0) => State::Head,
+                        (State::Body(_), n) => State::Body(n),
+                        (_, 0) => State::ChunkEnd,
+                        (_, n) => State::ChunkData(n),
+                    };
+                }
+                State::ChunkSize | State::ChunkEnd | State::Trailers => {
+                    let Some(line) = self.read_line(data, &mut pos) else {
+                        continue;
+                    };
+                    self.state = match self.state {
+                        State::ChunkSize => match parse_chunk_size(&line) {
+                            Some(0) => State::Trailers,
+                            Some(size) => State::ChunkData(size),
+                            None => State::Stopped,
+                        },
+                        State::ChunkEnd => State::ChunkSize,
+                        _ if trim_line_end(&line).is_empty() => State::Head,
+                        _ => State::Trailers,
+                    };
+                }
+                State::Stopped => break,
+            }
+        }
+
+        heads
+    }
+
+    /// Whether the parser has given up on the connection
+    pub fn is_stopped(&self) -> bool {
+        self.state == State::Stopped
+    }
+
+    /// Accumulate a line terminated by `\n`, returning it once complete
+    fn read_line(&mut self, data: &[u8], pos: &mut usize) -> Option<Vec<u8>> {
+        let rest = &data[*pos..];
+        match rest.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                self.buf.extend_from_slice(&rest[..=i]);
+                *pos += i + 1;
+                Some(std::mem::take(&mut self.buf))
+            }
+            None => {
+                self.buf.extend_from_slice(rest);
+                *pos = data.len();
+                if self.buf.len() > MAX_LINE_SIZE {
+                    self.stop();
+                }
+                None
+            }
+        }
+    }
+
+    /// Parse a complete head and work out how the message body is framed
+    fn parse_head(&mut self, bytes: &[u8]) -> Option<RawHead> {
+        let mut headers = vec![httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut request = httparse::Request::new(&mut headers);
+        if !matches!(request.parse(bytes), Ok(httparse::Status::Complete(_))) {
+            self.stop();
+            return None;
+        }
+
+        let method = request.method.unwrap_or_default().to_string();
+        let target = request.path.unwrap_or_default().to_string();
+        let headers: Vec<RawHeader> = request
+            .headers
+            .iter()
+            .map(|h| RawHeader {
+                name: h.name.to_string(),
+                value: h.value.to_vec(),
+            })
+            .collect();
+
+        let transfer_encoding: Vec<String> = header_values(&headers, "transfer-encoding").collect();
+        let content_length = header_values(&headers, "content-length").next();
+        let upgrade = method.eq_ignore_ascii_case("CONNECT")
+            || header_values(&headers, "upgrade").next().is_some();
+
+        self.state = if upgrade {
+            // Whatever follows is no longer HTTP/1
+            State::Stopped
+        } else if !transfer_encoding.is_empty() {
+            let chunked = transfer_encoding
+                .join(",")
+                .rsplit(',')
+                .next()
+                .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"));
+            if chunked {
+                State::ChunkSize
+            } else {
+                State::Stopped
+            }
+        } else {
+            match content_length.map(|len| len.trim().parse::<u64>()) {
+                None | Some(Ok(0)) => State::Head,
+                Some(Ok(len)) => State::Body(len),
+                Some(Err(_)) => State::Stopped,
+            }
+        };
+
+        Some(RawHead {
+            method,
+            target,
+            headers,
+            size: bytes.len(),
+        })
+    }
+
+    fn stop(&mut self) {
+        self.state = State::Stopped;
+        self.buf = Vec::new();
+    }
+}
+
+/// Values of a header, matched case-insensitively
+fn header_values<'a>(headers: &'a [RawHeader], name: &'a str) -> impl Iterator<Item = String> + 'a {
+    headers
+        .iter()
+        .filter(move |h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| String::from_utf8_lossy(&h.value).into_owned())
+}
+
+/// Position just past the blank line ending a head, if present
+fn find_head_end(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4)
+}
+
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Parse a chunk-size line, ignoring chunk extensions
+fn parse_chunk_size(line: &[u8]) -> Option<u64> {
+    let line = std::str::from_utf8(trim_line_end(line)).ok()?;
+    let size = line.split(';').next()?.trim();
+    u64::from_str_radix(size, 16).ok()
+}
+
+/// Per-connection tap fed with every byte read from the socket. Handlers claim
+/// the head of the request they are serving with [`WireTap::take_head`].
+#[derive(Debug, Default)]
+pub struct WireTap {
+    inner: Mutex<TapState>,
+}
+
+#[derive(Debug, Default)]
+struct TapState {
+    parser: HeadParser,
+    heads: VecDeque<RawHead>,
+}
+
+impl WireTap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record bytes read from the connection
+    pub fn observe(&self, data: &[u8]) {
+        let mut state = self.inner.lock().unwrap();
+        if state.parser.is_stopped() {
+            return;
+        }
+
+        for head in state.parser.feed(data) {
+            if state.heads.len() == MAX_PENDING_HEADS {
+                state.heads.pop_front();
+            }
+            state.heads.push_back(head);
+        }
+    }
+
+    /// Claim the head of the request being served. Requests on a connection are
+    /// handled in order, so unclaimed heads before the match belong to earlier requests.
+    pub fn take_head(&self, method: &str, target: &str) -> Option<RawHead> {
+        let mut state = self.inner.lock().unwrap();
+        let index = state
+            .heads
+            .iter()
+            .position(|head| head.method == method && head.target == target)?;
+        state.heads.drain(..index);
+        state.heads.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(head: &RawHead) -> Vec<&str> {
+        head.headers.iter().map(|h| h.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_head_preserves_order_and_case() {
+        let mut parser = HeadParser::new();
+        let heads = parser.feed(
+            b"POST /webhook/abc?x=1 HTTP/1.1\r\nHost: localhost\r\nX-Hub-Signature-256: sha256=ab\r\nhost-Alias: b\r\nContent-Length: 2\r\n\r\n{}",
+        );
+
+        assert_eq!(heads.len(), 1);
+        assert_eq!(heads[0].method, "POST");
+        assert_eq!(heads[0].target, "/webhook/abc?x=1");
+        assert_eq!(
+            names(&heads[0]),
+            [
+                "Host",
+                "X-Hub-Signature-256",
+                "host-Alias",
+                "Content-Length"
+            ]
+        );
+        assert_eq!(heads[0].headers[1].value, b"sha256=ab");
+        assert_eq!(heads[0].size, 117);
+    }
+
+    #[test]
+    fn test_parse_non_utf8_value() {
+        let mut parser = HeadParser::new();
+        let heads = parser.feed(b"GET / HTTP/1.1\r\nX-Bin: \xff\xfe\r\n\r\n");
+
+        assert_eq!(heads[0].headers[0].value, vec![0xff, 0xfe]);
+    }
+
+    #[test]
+    fn test_keep_alive_with_bodies() {
+        let mut parser = HeadParser::new();
+        let heads = parser.feed(
+            b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello\
+              POST /b HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+              5;ext=1\r\nhello\r\n0\r\nX-Trailer: t\r\n\r\n\
+              GET /c HTTP/1.1\r\n\r\n",
+        );
+
+        let targets: Vec<&str> = heads.iter().map(|h| h.target.as_str()).collect();
+        assert_eq!(targets, ["/a", "/b", "/c"]);
+    }
+
+    #[test]
+    fn test_split_across_reads() {
+        let bytes = b"POST /a HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\nGET /b HTTP/1.1\r\nAccept: */*\r\n\r\n";
+        let mut parser = HeadParser::new();
+        let mut heads = Vec::new();
+        for byte in bytes.chunks(1) {
+            heads.extend(parser.feed(byte));
+        }
+
+        let targets: Vec<&str> = heads.iter().map(|h| h.target.as_str()).collect();
+        assert_eq!(targets, ["/a", "/b"]);
+        assert_eq!(names(&heads[1]), ["Accept"]);
+    }
+
+    #[test]
+    fn test_stops_on_http2_and_upgrade() {
+        let mut parser = HeadParser::new();
+        parser.feed(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n");
+        assert!(parser.is_stopped());
+
+        let mut parser = HeadParser::new();
+        let heads = parser.feed(
+            b"GET /ws HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n\x81\x05hello",
+        );
+        assert_eq!(heads.len(), 1);
+        assert!(parser.is_stopped());
+    }
+
+    #[test]
+    fn test_tap_matches_request_in_order() {
+        let tap = WireTap::new();
+        tap.observe(b"GET /api HTTP/1.1\r\n\r\nPOST /webhook/a HTTP/1.1\r\nX-A: 1\r\n\r\n");
+
+        let head = tap.take_head("POST", "/webhook/a").unwrap();
+        assert_eq!(names(&head), ["X-A"]);
+        assert!(tap.take_head("GET", "/api").is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use super::rules::MAX_DELAY_MS;
+use axum::body::{Body, Bytes};
+use futures_util::StreamExt;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::time::Duration;
+
+/// Most chunks a dripped body is split into
+pub const MAX_DRIP_CHUNKS: u32 = 1_000;
+
+/// How long to wait before answering
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Delay {
+    Fixed {
+        ms: u64,
+    },
+    /// Any delay between the bounds, equally likely
+    Uniform {
+        min_ms: u64,
+        max_ms: u64,
+    },
+    /// Normally distributed around the mean, clamped to 0-60 seconds
+    Normal {
+        mean_ms: u64,
+        std_dev_ms: u64,
+    },
+}
+
+impl Delay {
+    fn validate(&self) -> Result<(), String> {
+        let longest = match *self {
+            Delay::Fixed { ms } => ms,
+            Delay::Uniform { min_ms, max_ms } => {
+                if min_ms > max_ms {
+                    return Err(format!(
+                        "min_ms {} is greater than max_ms {}",
+                        min_ms, max_ms
+                    ));
+                }
+                max_ms
+            }
+            Delay::Normal { mean_ms, .. } => mean_ms,
+        };
+        if longest > MAX_DELAY_MS {
+            return Err(format!("delays must be at most {} ms", MAX_DELAY_MS));
+        }
+        Ok(())
+    }
+
+    /// Pick the delay for one response
+    pub fn sample(&self) -> Duration {
+        let ms = match *self {
+            Delay::Fixed { ms } => ms,
+            Delay::Uniform { min_ms, max_ms } => rand::thread_rng().gen_range(min_ms..=max_ms),
+            Delay::Normal {
+                mean_ms,
+                std_dev_ms,
+            } => Normal::new(mean_ms as f64, std_dev_ms as f64)
+                .map(|normal| normal.sample(&mut rand::thread_rng()))
+                .unwrap_or(mean_ms as f64)
+                .clamp(0.0, MAX_DELAY_MS as f64)
+                .round() as u64,
+        };
+        Duration::from_millis(ms.min(MAX_DELAY_MS))
+    }
+}
+
+/// Send the body a chunk at a time, spread evenly over `duration_ms`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Drip {
+    pub duration_ms: u64,
+    #[serde(default = "default_drip_chunks")]
+    pub chunks: u32,
+}
+
+fn default_drip_chunks() -> u32 {
+    10
+}
+
+impl Drip {
+    fn validate(&self) -> Result<(), String> {
+        if self.duration_ms > MAX_DELAY_MS {
+            return Err(format!("drip duration must be at most {} ms", MAX_DELAY_MS));
+        }
+        if !(1..=MAX_DRIP_CHUNKS).contains(&self.chunks) {
+            return Err(format!("drip chunks must be 1-{}", MAX_DRIP_CHUNKS));
+        }
+        Ok(())
+    }
+
+    /// Streaming body that waits before each chunk, so the last one ends the duration.
+    /// An empty body still holds the response open for the whole duration.
+    pub fn body(&self, bytes: Bytes) -> Body {
+        let size = bytes.len().div_ceil(self.chunks.max(1) as usize).max(1);
+        let chunks: Vec<Bytes> = if bytes.is_empty() {
+            vec![bytes]
+        } else {
+            (0..bytes.len())
+                .step_by(size)
+                .map(|start| bytes.slice(start..(start + size).min(bytes.len())))
+                .collect()
+        };
+        let interval = Duration::from_millis(self.duration_ms) / chunks.len() as u32;
+        Body::from_stream(
+            futures_util::stream::iter(chunks).then(move |chunk| async move {
+                tokio::time::sleep(interval).await;
+                Ok::<_, Infallible>(chunk)
+            }),
+        )
+    }
+}
+
+/// Latency injected into an endpoint's own response, stored as JSON on the endpoint.
+/// Also the request body for PUT /api/endpoints/:id/latency, where both null turns it off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseLatency {
+    pub delay: Option<Delay>, // Before the status and headers are sent
+    pub drip: Option<Drip>,   // While the body is sent
+}
+
+impl ResponseLatency {
+    pub fn is_empty(&self) -> bool {
+        self.delay.is_none() && self.drip.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(delay) = &self.delay {
+            delay.validate()?;
+        }
+        if let Some(drip) = &self.drip {
+            drip.validate()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delay(json: serde_json::Value) -> Delay {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_sample_delays() {
+        let fixed = delay(serde_json::json!({"type": "fixed", "ms": 250}));
+        assert_eq!(fixed.sample(), Duration::from_millis(250));
+
+        let uniform = delay(serde_json::json!({"type": "uniform", "min_ms": 100, "max_ms": 200}));
+        let normal = delay(serde_json::json!({"type": "normal", "mean_ms": 50, "std_dev_ms": 100}));
+        for _ in 0..100 {
+            let sample = uniform.sample().as_millis();
+            assert!((100..=200).contains(&sample), "{}", sample);
+            // Samples below zero are clamped
+            assert!(normal.sample() <= Duration::from_millis(MAX_DELAY_MS));
+        }
+        let exact = delay(serde_json::json!({"type": "normal", "mean_ms": 80, "std_dev_ms": 0}));
+        assert_eq!(exact.sample(), Duration::from_millis(80));
+    }
+
+    #[test]
+    fn test_invalid_latency() {
+        for json in [
+            serde_json::json!({"delay": {"type": "fixed", "ms": 60001}}),
+            serde_json::json!({"delay": {"type": "uniform", "min_ms": 5, "max_ms": 1}}),
+            serde_json::json!({"delay": {"type": "normal", "mean_ms": 90000, "std_dev_ms": 1}}),
+            serde_json::json!({"drip": {"duration_ms": 1000, "chunks": 0}}),
+            serde_json::json!({"drip": {"duration_ms": 120000}}),
+        ] {
+            let latency: ResponseLatency = serde_json::from_value(json.clone()).unwrap();
+            assert!(latency.validate().is_err(), "{}", json);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drip_body() {
+        let drip = Drip {
+            duration_ms: 60,
+            chunks: 3,
+        };
+        let started = std::time::Instant::now();
+        let mut stream = drip.body(Bytes::from_static(b"abcdefg")).into_data_stream();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            chunks.push(chunk.unwrap());
+        }
+        assert_eq!(chunks, vec!["abc", "def", "g"]);
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+}
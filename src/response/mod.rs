@@ -0,0 +1,4 @@
+pub mod latency;
+pub mod rules;
+pub mod sequence;
+pub mod template;
@@ -0,0 +1,420 @@
+use super::template::{Rendered, ResponseTemplate, TemplateRequest};
+use crate::models::ResponseRule;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Longest delay a rule may add before answering
+pub const MAX_DELAY_MS: u64 = 60_000;
+
+/// A condition on the incoming request. A rule matches when all of its matchers do.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Matcher {
+    /// HTTP method, compared case-insensitively
+    Method { method: String },
+    /// Glob over the sub-path below `/webhook/{id}`; `*` stays within a segment, `**` spans them
+    Path { glob: String },
+    Header {
+        name: String,
+        #[serde(flatten)]
+        condition: Condition,
+    },
+    /// Top-level query parameter; repeated parameters match when any value does
+    Query {
+        name: String,
+        #[serde(flatten)]
+        condition: Condition,
+    },
+    /// Value in the JSON body, as a JSON pointer (`/order/id`) or dotted path (`order.id`)
+    Json {
+        path: String,
+        #[serde(flatten)]
+        condition: Condition,
+    },
+}
+
+/// How a header, query parameter or JSON value is compared. With neither `equals` nor
+/// `regex` the value only has to be present.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Test {
+    Present,
+    Equals(serde_json::Value),
+    Regex(Regex),
+}
+
+impl Test {
+    fn new(condition: &Condition) -> Result<Self, String> {
+        match (&condition.equals, &condition.regex) {
+            (Some(_), Some(_)) => Err("a matcher takes either equals or regex, not both".into()),
+            (Some(value), None) => Ok(Test::Equals(value.clone())),
+            (None, Some(pattern)) => Regex::new(pattern)
+                .map(Test::Regex)
+                .map_err(|e| format!("invalid regex '{}': {}", pattern, e)),
+            (None, None) => Ok(Test::Present),
+        }
+    }
+
+    /// Whether a value, or any element of an array of values, passes
+    fn passes(&self, value: &serde_json::Value) -> bool {
+        match (self, value) {
+            (Test::Present, _) => true,
+            (Test::Equals(expected), _) if expected == value => true,
+            // Headers and query parameters are strings, so `"equals": 1` matches "1"
+            (Test::Equals(expected), serde_json::Value::String(s)) => {
+                !expected.is_string()
+                    && serde_json::from_str::<serde_json::Value>(s).is_ok_and(|v| v == *expected)
+            }
+            (Test::Regex(regex), serde_json::Value::String(s)) => regex.is_match(s),
+            (Test::Regex(regex), value) if !value.is_array() => regex.is_match(&value.to_string()),
+            (_, serde_json::Value::Array(values)) => values.iter().any(|v| self.passes(v)),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum CompiledMatcher {
+    Method(String),
+    Path(GlobMatcher),
+    Header(HeaderName, Test),
+    Query(String, Test),
+    Json(String, Test),
+}
+
+impl CompiledMatcher {
+    fn new(matcher: &Matcher) -> Result<Self, String> {
+        Ok(match matcher {
+            Matcher::Method { method } => CompiledMatcher::Method(method.trim().to_string()),
+            Matcher::Path { glob } => CompiledMatcher::Path(
+                GlobBuilder::new(glob)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| format!("invalid path glob '{}': {}", glob, e))?
+                    .compile_matcher(),
+            ),
+            Matcher::Header { name, condition } => CompiledMatcher::Header(
+                HeaderName::from_str(name.trim())
+                    .map_err(|_| format!("invalid header name '{}'", name))?,
+                Test::new(condition)?,
+            ),
+            Matcher::Query { name, condition } => {
+                CompiledMatcher::Query(name.clone(), Test::new(condition)?)
+            }
+            Matcher::Json { path, condition } => {
+                CompiledMatcher::Json(json_pointer(path), Test::new(condition)?)
+            }
+        })
+    }
+
+    fn matches(&self, request: &TemplateRequest) -> bool {
+        match self {
+            CompiledMatcher::Method(method) => request.method.eq_ignore_ascii_case(method),
+            CompiledMatcher::Path(glob) => glob.is_match(&request.sub_path),
+            CompiledMatcher::Header(name, test) => request
+                .headers
+                .get(name.as_str())
+                .is_some_and(|value| test.passes(value)),
+            CompiledMatcher::Query(name, test) => request
+                .query
+                .get(name)
+                .is_some_and(|value| test.passes(value)),
+            CompiledMatcher::Json(pointer, test) => request
+                .json
+                .as_ref()
+                .and_then(|json| json.pointer(pointer))
+                .is_some_and(|value| test.passes(value)),
+        }
+    }
+}
+
+/// Dotted paths such as `order.items.0.id` as a JSON pointer
+fn json_pointer(path: &str) -> String {
+    let path = path.trim();
+    if path.is_empty() || path.starts_with('/') {
+        return path.to_string();
+    }
+    path.split('.')
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// What a rule answers with: fixed headers and body, or templates rendered per request
+#[derive(Debug, Clone)]
+pub enum RuleResponse {
+    Static(Rendered),
+    Template(ResponseTemplate),
+}
+
+impl RuleResponse {
+    /// Build a response from a JSON object of headers and a body. Unlike the endpoint's
+    /// custom response, headers that are not valid are refused rather than skipped.
+    pub fn new(
+        headers_json: Option<&str>,
+        body: Option<&str>,
+        template: bool,
+    ) -> Result<Self, String> {
+        if template {
+            return ResponseTemplate::new(headers_json, body).map(RuleResponse::Template);
+        }
+
+        let mut headers = HeaderMap::new();
+        if let Some(json) = headers_json {
+            let map: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)
+                .map_err(|e| format!("headers must be a JSON object: {}", e))?;
+            for (name, value) in map {
+                let header = HeaderName::from_str(&name)
+                    .map_err(|_| format!("invalid header name '{}'", name))?;
+                let value = value
+                    .as_str()
+                    .and_then(|value| HeaderValue::from_str(value).ok())
+                    .ok_or_else(|| format!("invalid value for header {}", name))?;
+                headers.insert(header, value);
+            }
+        }
+        Ok(RuleResponse::Static(Rendered {
+            headers,
+            body: body.unwrap_or_default().to_string(),
+        }))
+    }
+
+    /// Headers and body for a request
+    pub fn render(&self, request: &TemplateRequest) -> Result<Rendered, String> {
+        match self {
+            RuleResponse::Static(rendered) => Ok(rendered.clone()),
+            RuleResponse::Template(template) => template.render(request).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// A rule compiled for matching
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    pub id: i64,
+    pub status: StatusCode,
+    pub delay: Duration,
+    matchers: Vec<CompiledMatcher>,
+    response: RuleResponse,
+}
+
+impl CompiledRule {
+    pub fn new(rule: &ResponseRule) -> Result<Self, String> {
+        Ok(Self {
+            id: rule.id,
+            status: StatusCode::from_u16(rule.status as u16)
+                .map_err(|_| format!("invalid status {}", rule.status))?,
+            delay: Duration::from_millis(rule.delay_ms.clamp(0, MAX_DELAY_MS as i64) as u64),
+            matchers: rule
+                .matchers
+                .iter()
+                .map(CompiledMatcher::new)
+                .collect::<Result<_, _>>()?,
+            response: RuleResponse::new(
+                rule.headers.as_deref(),
+                rule.body.as_deref(),
+                rule.template,
+            )?,
+        })
+    }
+
+    pub fn matches(&self, request: &TemplateRequest) -> bool {
+        self.matchers.iter().all(|matcher| matcher.matches(request))
+    }
+
+    /// Headers and body for a request that matched
+    pub fn render(&self, request: &TemplateRequest) -> Result<Rendered, String> {
+        self.response.render(request)
+    }
+}
+
+/// Check that matchers compile, e.g. before a rule is saved
+pub fn validate_matchers(matchers: &[Matcher]) -> Result<(), String> {
+    matchers
+        .iter()
+        .try_for_each(|matcher| CompiledMatcher::new(matcher).map(|_| ()))
+}
+
+/// An endpoint's rules in evaluation order
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleSet {
+    /// Compile rules already sorted by position; rules that no longer compile are skipped
+    pub fn new(endpoint_id: &str, rules: &[ResponseRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| {
+                CompiledRule::new(rule)
+                    .map_err(|e| {
+                        tracing::warn!("Endpoint {} response rule {}: {}", endpoint_id, rule.id, e)
+                    })
+                    .ok()
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// First rule matching the request
+    pub fn find(&self, request: &TemplateRequest) -> Option<&CompiledRule> {
+        self.rules.iter().find(|rule| rule.matches(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(json: serde_json::Value) -> CompiledMatcher {
+        CompiledMatcher::new(&serde_json::from_value(json).unwrap()).unwrap()
+    }
+
+    fn request() -> TemplateRequest {
+        TemplateRequest {
+            method: "POST".to_string(),
+            sub_path: "/orders/42/items".to_string(),
+            query: serde_json::json!({"page": "2", "tag": ["a", "b"]}),
+            headers: serde_json::json!({"x-event": "order.paid"}),
+            json: Some(serde_json::json!({"order": {"id": 42, "status": "paid"}})),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matchers() {
+        let request = request();
+        for (json, expected) in [
+            (
+                serde_json::json!({"type": "method", "method": "post"}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "method", "method": "GET"}),
+                false,
+            ),
+            (
+                serde_json::json!({"type": "path", "glob": "/orders/*/items"}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "path", "glob": "/orders/*"}),
+                false,
+            ),
+            (
+                serde_json::json!({"type": "path", "glob": "/orders/**"}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "header", "name": "X-Event", "equals": "order.paid"}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "header", "name": "x-event", "regex": "^order\\."}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "header", "name": "x-missing"}),
+                false,
+            ),
+            (
+                serde_json::json!({"type": "query", "name": "page", "equals": 2}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "query", "name": "tag", "equals": "b"}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "json", "path": "order.id", "equals": 42}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "json", "path": "/order/status", "regex": "^pa"}),
+                true,
+            ),
+            (
+                serde_json::json!({"type": "json", "path": "order.id", "equals": "42"}),
+                false,
+            ),
+            (
+                serde_json::json!({"type": "json", "path": "order.refund"}),
+                false,
+            ),
+        ] {
+            assert_eq!(
+                matcher(json.clone()).matches(&request),
+                expected,
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_matchers() {
+        for json in [
+            serde_json::json!({"type": "path", "glob": "/orders/[a"}),
+            serde_json::json!({"type": "header", "name": "x-a", "regex": "("}),
+            serde_json::json!({"type": "header", "name": "bad name"}),
+            serde_json::json!({"type": "query", "name": "a", "equals": "1", "regex": "1"}),
+        ] {
+            let matcher: Matcher = serde_json::from_value(json.clone()).unwrap();
+            assert!(CompiledMatcher::new(&matcher).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rule = |id: i64, matchers: serde_json::Value, body: &str| ResponseRule {
+            id,
+            endpoint_id: "e".to_string(),
+            position: id,
+            name: None,
+            matchers: serde_json::from_value(matchers).unwrap(),
+            status: 200 + id as i32,
+            headers: Some(r#"{"X-Rule": "yes"}"#.to_string()),
+            body: Some(body.to_string()),
+            template: false,
+            delay_ms: 0,
+            created_at: String::new(),
+        };
+        let rules = RuleSet::new(
+            "e",
+            &[
+                rule(
+                    1,
+                    serde_json::json!([{"type": "method", "method": "GET"}]),
+                    "get",
+                ),
+                rule(
+                    2,
+                    serde_json::json!([{"type": "path", "glob": "/orders/**"}]),
+                    "orders",
+                ),
+                rule(3, serde_json::json!([]), "fallback"),
+            ],
+        );
+
+        let matched = rules.find(&request()).unwrap();
+        assert_eq!((matched.id, matched.status), (2, StatusCode::ACCEPTED));
+        let rendered = matched.render(&request()).unwrap();
+        assert_eq!(rendered.body, "orders");
+        assert_eq!(rendered.headers["x-rule"], "yes");
+    }
+}
@@ -0,0 +1,287 @@
+use super::rules::{RuleResponse, MAX_DELAY_MS};
+use super::template::{Rendered, TemplateRequest};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Number of positions kept; past it the least recently answered one is dropped
+const MAX_POSITIONS: usize = 10_000;
+
+/// What happens once every step has been answered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceMode {
+    /// Keep answering with the last step, e.g. 500, 500, 503, then 200 forever
+    #[default]
+    Hold,
+    /// Start again from the first step, round-robin
+    Cycle,
+}
+
+/// One scripted response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceStep {
+    #[serde(default = "default_step_status")]
+    pub status: u16,
+    pub headers: Option<serde_json::Map<String, serde_json::Value>>,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub template: bool,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+fn default_step_status() -> u16 {
+    200
+}
+
+/// Responses an endpoint answers with in turn, stored as JSON on the endpoint.
+/// Also the request body for PUT /api/endpoints/:id/sequence, where no steps turns it off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseSequence {
+    #[serde(default)]
+    pub steps: Vec<SequenceStep>,
+    #[serde(default)]
+    pub mode: SequenceMode,
+    #[serde(default)]
+    pub per_sender: bool, // Track a separate position for each client IP
+}
+
+/// A step compiled for answering
+#[derive(Debug, Clone)]
+pub struct CompiledStep {
+    pub status: StatusCode,
+    pub delay: Duration,
+    response: RuleResponse,
+}
+
+impl CompiledStep {
+    fn new(step: &SequenceStep) -> Result<Self, String> {
+        if step.delay_ms > MAX_DELAY_MS {
+            return Err(format!("delay_ms must be at most {}", MAX_DELAY_MS));
+        }
+        let headers = step
+            .headers
+            .as_ref()
+            .map(|headers| serde_json::Value::Object(headers.clone()).to_string());
+        Ok(Self {
+            status: StatusCode::from_u16(step.status)
+                .ok()
+                .filter(|_| (100..=599).contains(&step.status))
+                .ok_or_else(|| format!("invalid status {}", step.status))?,
+            delay: Duration::from_millis(step.delay_ms),
+            response: RuleResponse::new(headers.as_deref(), step.body.as_deref(), step.template)?,
+        })
+    }
+
+    pub fn render(&self, request: &TemplateRequest) -> Result<Rendered, String> {
+        self.response.render(request)
+    }
+}
+
+/// An endpoint's sequence compiled for answering
+#[derive(Debug, Clone)]
+pub struct CompiledSequence {
+    steps: Vec<CompiledStep>,
+    mode: SequenceMode,
+    pub per_sender: bool,
+}
+
+impl CompiledSequence {
+    /// Compile a sequence, naming the first step that is not valid
+    pub fn new(sequence: &ResponseSequence) -> Result<Self, String> {
+        if sequence.steps.is_empty() {
+            return Err("a sequence needs at least one step".to_string());
+        }
+        let steps = sequence
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| CompiledStep::new(step).map_err(|e| format!("step {}: {}", i, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            steps,
+            mode: sequence.mode,
+            per_sender: sequence.per_sender,
+        })
+    }
+
+    /// Step answering the request that follows `answered` earlier ones, with its position
+    pub fn step(&self, answered: u64) -> (usize, &CompiledStep) {
+        let last = self.steps.len() - 1;
+        let position = match self.mode {
+            SequenceMode::Hold => answered.min(last as u64) as usize,
+            SequenceMode::Cycle => (answered % self.steps.len() as u64) as usize,
+        };
+        (position, &self.steps[position])
+    }
+}
+
+/// Endpoint ID and, for per-sender sequences, the sender's IP
+type PositionKey = (String, Option<String>);
+
+#[derive(Debug)]
+struct Position {
+    answered: u64,
+    /// When the position last moved, as a key of `Positions::recent`
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Positions {
+    positions: HashMap<PositionKey, Position>,
+    /// Keys by last use, oldest first
+    recent: BTreeMap<u64, PositionKey>,
+    next_use: u64,
+}
+
+/// Number of sequence responses given per endpoint, or per endpoint and sender.
+///
+/// Positions are kept in memory, so they start over when the server restarts. Only the
+/// `MAX_POSITIONS` most recently answered are kept, so a sequence a sender hasn't
+/// reached in a long while may start over for them.
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    positions: Mutex<Positions>,
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a response and return how many were given before it
+    pub fn advance(&self, endpoint_id: &str, sender: Option<&str>) -> u64 {
+        let mut positions = self.positions.lock().unwrap();
+        let positions = &mut *positions;
+        let key = (endpoint_id.to_string(), sender.map(str::to_string));
+        let used = positions.next_use;
+        positions.next_use += 1;
+
+        if let Some(position) = positions.positions.get(&key) {
+            positions.recent.remove(&position.used);
+        } else if positions.positions.len() >= MAX_POSITIONS {
+            if let Some((_, oldest)) = positions.recent.pop_first() {
+                positions.positions.remove(&oldest);
+            }
+        }
+        positions.recent.insert(used, key.clone());
+
+        let position = positions
+            .positions
+            .entry(key)
+            .or_insert(Position { answered: 0, used });
+        position.used = used;
+        let before = position.answered;
+        position.answered = before.saturating_add(1);
+        before
+    }
+
+    /// Start an endpoint's sequence over, for one sender or for everyone
+    pub fn reset(&self, endpoint_id: &str, sender: Option<&str>) {
+        let mut positions = self.positions.lock().unwrap();
+        let Positions {
+            positions, recent, ..
+        } = &mut *positions;
+        positions.retain(|(id, key_sender), position| {
+            let keep = id != endpoint_id
+                || sender.is_some_and(|sender| key_sender.as_deref() != Some(sender));
+            if !keep {
+                recent.remove(&position.used);
+            }
+            keep
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(json: serde_json::Value) -> Result<CompiledSequence, String> {
+        CompiledSequence::new(&serde_json::from_value(json).unwrap())
+    }
+
+    fn statuses(sequence: &CompiledSequence, count: u64) -> Vec<u16> {
+        (0..count)
+            .map(|answered| sequence.step(answered).1.status.as_u16())
+            .collect()
+    }
+
+    #[test]
+    fn test_modes() {
+        let steps = serde_json::json!([{"status": 500}, {"status": 503}, {}]);
+        let hold = sequence(serde_json::json!({ "steps": steps })).unwrap();
+        assert_eq!(statuses(&hold, 5), vec![500, 503, 200, 200, 200]);
+        assert_eq!(hold.step(9).0, 2);
+
+        let cycle = sequence(serde_json::json!({ "steps": steps, "mode": "cycle" })).unwrap();
+        assert_eq!(statuses(&cycle, 5), vec![500, 503, 200, 500, 503]);
+        assert_eq!(cycle.step(4).0, 1);
+    }
+
+    #[test]
+    fn test_invalid_sequences() {
+        for (json, error) in [
+            (serde_json::json!({"steps": []}), "at least one step"),
+            (serde_json::json!({"steps": [{}, {"status": 42}]}), "step 1"),
+            (
+                serde_json::json!({"steps": [{"delay_ms": 600000}]}),
+                "delay_ms",
+            ),
+            (
+                serde_json::json!({"steps": [{"body": "{{ nope() }}", "template": true}]}),
+                "step 0",
+            ),
+        ] {
+            let message = sequence(json.clone()).unwrap_err();
+            assert!(message.contains(error), "{}: {}", json, message);
+        }
+    }
+
+    #[test]
+    fn test_tracker_positions() {
+        let tracker = SequenceTracker::new();
+        assert_eq!(tracker.advance("a", None), 0);
+        assert_eq!(tracker.advance("a", None), 1);
+        assert_eq!(tracker.advance("a", Some("10.0.0.1")), 0);
+        assert_eq!(tracker.advance("a", Some("10.0.0.2")), 0);
+        assert_eq!(tracker.advance("b", None), 0);
+
+        // Resetting one sender leaves the others in place
+        tracker.reset("a", Some("10.0.0.1"));
+        assert_eq!(tracker.advance("a", Some("10.0.0.1")), 0);
+        assert_eq!(tracker.advance("a", Some("10.0.0.2")), 1);
+
+        tracker.reset("a", None);
+        assert_eq!(tracker.advance("a", None), 0);
+        assert_eq!(tracker.advance("a", Some("10.0.0.2")), 0);
+        assert_eq!(tracker.advance("b", None), 1);
+
+        let positions = tracker.positions.lock().unwrap();
+        assert_eq!(positions.positions.len(), positions.recent.len());
+    }
+
+    #[test]
+    fn test_least_recently_answered_position_is_dropped() {
+        let tracker = SequenceTracker::new();
+        let sender = |i: usize| format!("sender-{}", i);
+
+        assert_eq!(tracker.advance("a", Some(&sender(0))), 0);
+        assert_eq!(tracker.advance("a", Some(&sender(1))), 0);
+        // Answering the first sender again makes the second one the oldest
+        assert_eq!(tracker.advance("a", Some(&sender(0))), 1);
+        for i in 2..=MAX_POSITIONS {
+            assert_eq!(tracker.advance("a", Some(&sender(i))), 0);
+        }
+
+        assert_eq!(
+            tracker.positions.lock().unwrap().positions.len(),
+            MAX_POSITIONS
+        );
+        assert_eq!(tracker.advance("a", Some(&sender(0))), 2);
+        assert_eq!(tracker.advance("a", Some(&sender(1))), 0);
+    }
+}
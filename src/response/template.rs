@@ -0,0 +1,242 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use minijinja::{Environment, Error, ErrorKind};
+use rand::Rng;
+use serde::Serialize;
+use sha2::Sha256;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const BODY: &str = "body";
+
+/// The incoming request as seen by response rules, and by templates as the `request` variable
+#[derive(Debug, Default, Serialize)]
+pub struct TemplateRequest {
+    pub endpoint_id: String,
+    pub method: String,
+    pub path: String,
+    pub sub_path: String,
+    pub query: serde_json::Value,   // Structured like `query_params`
+    pub headers: serde_json::Value, // Lowercase names; repeated headers as arrays
+    pub body: String,               // Lossy UTF-8 with any Content-Encoding removed
+    pub json: Option<serde_json::Value>, // Parsed body when it is valid JSON
+    pub ip: String,
+    pub received_at: String,
+}
+
+/// Response headers and body rendered for one request
+#[derive(Debug, Clone)]
+pub struct Rendered {
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+/// An endpoint's custom response body and header values compiled as templates.
+///
+/// Besides `request`, templates can call `now(format)`, `uuid()` and `random(min, max)`,
+/// and use the `b64encode`, `b64decode` and `hmac_sha256(secret)` filters.
+#[derive(Debug, Clone)]
+pub struct ResponseTemplate {
+    env: Arc<Environment<'static>>,
+    headers: Vec<HeaderName>,
+}
+
+impl ResponseTemplate {
+    /// Compile the body and the values of a JSON object of headers. Syntax errors and
+    /// unknown functions, filters or tests are reported here rather than on capture.
+    pub fn new(headers_json: Option<&str>, body: Option<&str>) -> Result<Self, String> {
+        let mut env = environment();
+        env.add_template_owned(BODY, body.unwrap_or_default().to_string())
+            .map_err(|e| format!("invalid body template: {}", e))?;
+
+        let headers_map: serde_json::Map<String, serde_json::Value> = match headers_json {
+            Some(json) => serde_json::from_str(json)
+                .map_err(|e| format!("headers must be a JSON object: {}", e))?,
+            None => serde_json::Map::new(),
+        };
+        let mut headers = Vec::with_capacity(headers_map.len());
+        for (name, value) in headers_map {
+            let Some(value) = value.as_str() else {
+                continue;
+            };
+            let header = HeaderName::from_str(&name)
+                .map_err(|_| format!("invalid header name '{}'", name))?;
+            env.add_template_owned(format!("header:{}", header), value.to_string())
+                .map_err(|e| format!("invalid template for header {}: {}", name, e))?;
+            headers.push(header);
+        }
+
+        let template = Self {
+            env: Arc::new(env),
+            headers,
+        };
+        // Names are only resolved while rendering, so try a render against an empty request
+        if let Err(e) = template.render(&TemplateRequest::default()) {
+            if matches!(
+                e.kind(),
+                ErrorKind::UnknownFunction | ErrorKind::UnknownFilter | ErrorKind::UnknownTest
+            ) {
+                return Err(e.to_string());
+            }
+        }
+        Ok(template)
+    }
+
+    /// Render the headers and body for a request
+    pub fn render(&self, request: &TemplateRequest) -> Result<Rendered, Error> {
+        let ctx = minijinja::context! { request };
+        let mut headers = HeaderMap::new();
+        for name in &self.headers {
+            let value = self
+                .env
+                .get_template(&format!("header:{}", name))?
+                .render(&ctx)?;
+            let value = HeaderValue::from_str(&value).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidOperation,
+                    format!("header {} rendered to an invalid value", name),
+                )
+            })?;
+            headers.insert(name.clone(), value);
+        }
+        let body = self.env.get_template(BODY)?.render(&ctx)?;
+        Ok(Rendered { headers, body })
+    }
+}
+
+/// Environment with the helpers available to response templates
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.add_function("now", now);
+    env.add_function("uuid", || uuid::Uuid::new_v4().to_string());
+    env.add_function("random", random);
+    env.add_filter("b64encode", |value: String| {
+        general_purpose::STANDARD.encode(value)
+    });
+    env.add_filter("b64decode", b64decode);
+    env.add_filter("hmac_sha256", hmac_sha256);
+    env
+}
+
+/// Current UTC time, as RFC 3339 or with a strftime format such as `"%s"`
+fn now(format: Option<String>) -> Result<String, Error> {
+    let now = chrono::Utc::now();
+    let Some(format) = format else {
+        return Ok(now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+    };
+    // `to_string` panics on an invalid format, while `write!` reports it
+    let mut formatted = String::new();
+    write!(formatted, "{}", now.format(&format)).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("now() format '{}' is not valid", format),
+        )
+    })?;
+    Ok(formatted)
+}
+
+/// Random integer between `min` and `max` inclusive, 0-100 by default
+fn random(min: Option<i64>, max: Option<i64>) -> Result<i64, Error> {
+    let (min, max) = (min.unwrap_or(0), max.unwrap_or(100));
+    if min > max {
+        return Err(Error::new(
+            ErrorKind::InvalidOperation,
+            format!("random() min {} is greater than max {}", min, max),
+        ));
+    }
+    Ok(rand::thread_rng().gen_range(min..=max))
+}
+
+fn b64decode(value: String) -> Result<String, Error> {
+    let bytes = general_purpose::STANDARD
+        .decode(value.trim())
+        .map_err(|e| Error::new(ErrorKind::InvalidOperation, format!("b64decode: {}", e)))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Hex HMAC-SHA256 of the value, e.g. `{{ request.body | hmac_sha256("secret") }}`
+fn hmac_sha256(value: String, secret: String) -> Result<String, Error> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| Error::new(ErrorKind::InvalidOperation, e.to_string()))?;
+    mac.update(value.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> TemplateRequest {
+        TemplateRequest {
+            method: "POST".to_string(),
+            path: "/webhook/abc/orders".to_string(),
+            query: serde_json::json!({"page": "2"}),
+            headers: serde_json::json!({"x-request-id": "r-1"}),
+            body: r#"{"id": 7}"#.to_string(),
+            json: Some(serde_json::json!({"id": 7})),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_request_data() {
+        let template = ResponseTemplate::new(
+            Some(r#"{"X-Echo": "{{ request.headers['x-request-id'] }}", "X-Count": 1}"#),
+            Some(r#"{{ request.method }} {{ request.path }} page={{ request.query.page }} id={{ request.json.id }}"#),
+        )
+        .unwrap();
+        let rendered = template.render(&request()).unwrap();
+        assert_eq!(rendered.body, "POST /webhook/abc/orders page=2 id=7");
+        assert_eq!(rendered.headers["x-echo"], "r-1");
+        // Only string values are templates
+        assert!(!rendered.headers.contains_key("x-count"));
+    }
+
+    #[test]
+    fn test_helpers() {
+        let template = ResponseTemplate::new(
+            None,
+            Some(concat!(
+                "{{ 'hi' | b64encode }} {{ 'aGk=' | b64decode }} ",
+                "{{ request.body | hmac_sha256('secret') }} ",
+                "{{ uuid() | length }} {{ random(5, 5) }} {{ now('%Y') | length }}"
+            )),
+        )
+        .unwrap();
+        let rendered = template.render(&request()).unwrap();
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(br#"{"id": 7}"#);
+        assert_eq!(
+            rendered.body,
+            format!(
+                "aGk= hi {} 36 5 4",
+                hex::encode(mac.finalize().into_bytes())
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(ResponseTemplate::new(None, Some("{{ request.method")).is_err());
+        assert!(ResponseTemplate::new(None, Some("{{ request.body | shout }}")).is_err());
+        assert!(ResponseTemplate::new(None, Some("{{ missing() }}")).is_err());
+        assert!(ResponseTemplate::new(Some(r#"{"X-A": "{% if %}"}"#), None).is_err());
+        assert!(ResponseTemplate::new(Some("[]"), None).is_err());
+    }
+
+    #[test]
+    fn test_render_error() {
+        let template = ResponseTemplate::new(None, Some("{{ random(9, 1) }}")).unwrap();
+        let error = template.render(&request()).unwrap_err();
+        assert!(error.to_string().contains("greater than max"));
+    }
+
+    #[test]
+    fn test_invalid_now_format() {
+        let template = ResponseTemplate::new(None, Some("{{ now('%Q') }}")).unwrap();
+        let error = template.render(&request()).unwrap_err();
+        assert!(error.to_string().contains("is not valid"));
+    }
+}
@@ -0,0 +1,318 @@
+use crate::config::BodyOverflow;
+use axum::body::Body;
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Limits applied while reading a request body
+#[derive(Debug, Clone)]
+pub struct BodyLimits {
+    /// Maximum number of bytes kept in memory
+    pub max_body_size: u64,
+    /// What to do once `max_body_size` is exceeded
+    pub overflow: BodyOverflow,
+    /// Directory for spilled bodies, one sub-directory per endpoint
+    pub spill_dir: PathBuf,
+    /// Hard cap on bytes read from the sender in any mode
+    pub max_spill_size: u64,
+}
+
+/// A request body read from the wire
+#[derive(Debug, Default)]
+pub struct CapturedBody {
+    /// Body bytes held in memory (empty when the body was spilled to disk)
+    pub bytes: Vec<u8>,
+    /// Number of bytes received from the sender
+    pub size: u64,
+    /// Whether part of the body was discarded
+    pub truncated: bool,
+    /// The body on disk when it was spilled
+    pub file: Option<SpillFile>,
+    /// SHA-256 of every byte read, including any the overflow mode discarded
+    pub sha256: [u8; 32],
+}
+
+/// A body spilled to disk. The file is deleted when this is dropped, so a request
+/// that is never stored leaves nothing behind, unless it is handed over with `persist`.
+#[derive(Debug)]
+pub struct SpillFile {
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl SpillFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keep the file and return its path, once storage has taken ownership of it
+    pub fn persist(mut self) -> PathBuf {
+        self.persisted = true;
+        std::mem::take(&mut self.path)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Errors that can occur while reading a body
+#[derive(Debug)]
+pub enum BodyError {
+    /// Body exceeds the limit and the overflow mode is `reject`
+    TooLarge,
+    /// The client stream failed
+    Stream(axum::Error),
+    /// Writing the spill file failed
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for BodyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyError::TooLarge => write!(f, "body exceeds size limit"),
+            BodyError::Stream(e) => write!(f, "failed to read body: {}", e),
+            BodyError::Io(e) => write!(f, "failed to spill body: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BodyError {}
+
+/// Stream a request body, keeping it in memory up to the limit and applying the
+/// overflow mode beyond it. Reading stops once `max_spill_size` bytes have been seen.
+pub async fn read_body(
+    body: Body,
+    endpoint_id: &str,
+    limits: &BodyLimits,
+) -> Result<CapturedBody, BodyError> {
+    let mut stream = body.into_data_stream();
+    let mut captured = CapturedBody::default();
+    let mut spill: Option<tokio::fs::File> = None;
+    let mut hasher = Sha256::new();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(BodyError::Stream)?;
+        let received = captured.size;
+        captured.size += chunk.len() as u64;
+
+        // Stop reading past the hard cap, keeping only what fits
+        let chunk = if captured.size > limits.max_spill_size {
+            captured.truncated = true;
+            let keep = limits.max_spill_size.saturating_sub(received) as usize;
+            &chunk[..keep.min(chunk.len())]
+        } else {
+            &chunk[..]
+        };
+        hasher.update(chunk);
+
+        if let Some(file) = spill.as_mut() {
+            file.write_all(chunk).await.map_err(BodyError::Io)?;
+        } else if (captured.bytes.len() + chunk.len()) as u64 <= limits.max_body_size {
+            captured.bytes.extend_from_slice(chunk);
+        } else {
+            match limits.overflow {
+                BodyOverflow::Reject => return Err(BodyError::TooLarge),
+                BodyOverflow::Truncate => {
+                    let keep = limits.max_body_size as usize - captured.bytes.len();
+                    captured.bytes.extend_from_slice(&chunk[..keep]);
+                    captured.truncated = true;
+                }
+                BodyOverflow::Spill => {
+                    // Guard the file first so a failure from here on removes it
+                    let path = captured.file.insert(SpillFile {
+                        path: spill_path(&limits.spill_dir, endpoint_id),
+                        persisted: false,
+                    });
+                    let mut file = create_spill_file(path.path())
+                        .await
+                        .map_err(BodyError::Io)?;
+                    file.write_all(&captured.bytes)
+                        .await
+                        .map_err(BodyError::Io)?;
+                    file.write_all(chunk).await.map_err(BodyError::Io)?;
+                    captured.bytes = Vec::new();
+                    spill = Some(file);
+                }
+            }
+        }
+
+        if captured.size > limits.max_spill_size {
+            break;
+        }
+    }
+
+    if let Some(mut file) = spill {
+        file.flush().await.map_err(BodyError::Io)?;
+    }
+
+    captured.sha256 = hasher.finalize().into();
+    Ok(captured)
+}
+
+/// Directory holding the spilled bodies of one endpoint
+pub fn endpoint_spill_dir(spill_dir: &Path, endpoint_id: &str) -> PathBuf {
+    spill_dir.join(endpoint_id)
+}
+
+fn spill_path(spill_dir: &Path, endpoint_id: &str) -> PathBuf {
+    endpoint_spill_dir(spill_dir, endpoint_id).join(format!("{}.bin", uuid::Uuid::new_v4()))
+}
+
+async fn create_spill_file(path: &Path) -> std::io::Result<tokio::fs::File> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::File::create(path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(overflow: BodyOverflow, spill_dir: PathBuf) -> BodyLimits {
+        BodyLimits {
+            max_body_size: 8,
+            overflow,
+            spill_dir,
+            max_spill_size: 32,
+        }
+    }
+
+    fn chunked_body(chunks: Vec<&'static [u8]>) -> Body {
+        let stream = futures_util::stream::iter(
+            chunks
+                .into_iter()
+                .map(|c| Ok::<_, std::io::Error>(axum::body::Bytes::from_static(c))),
+        );
+        Body::from_stream(stream)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hookshot_{}_{}", name, std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn test_read_body_within_limit() {
+        let body = chunked_body(vec![b"abc", b"def"]);
+        let captured = read_body(
+            body,
+            "ep",
+            &limits(BodyOverflow::Reject, temp_dir("within")),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(captured.bytes, b"abcdef");
+        assert_eq!(captured.size, 6);
+        assert!(!captured.truncated);
+        assert!(captured.file.is_none());
+        assert_eq!(captured.sha256, <[u8; 32]>::from(Sha256::digest(b"abcdef")));
+    }
+
+    #[tokio::test]
+    async fn test_read_body_reject() {
+        let body = chunked_body(vec![b"abcdef", b"ghijkl"]);
+        let result = read_body(
+            body,
+            "ep",
+            &limits(BodyOverflow::Reject, temp_dir("reject")),
+        )
+        .await;
+
+        assert!(matches!(result, Err(BodyError::TooLarge)));
+    }
+
+    #[tokio::test]
+    async fn test_read_body_truncate() {
+        let body = chunked_body(vec![b"abcdef", b"ghijkl"]);
+        let captured = read_body(
+            body,
+            "ep",
+            &limits(BodyOverflow::Truncate, temp_dir("truncate")),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(captured.bytes, b"abcdefgh");
+        assert_eq!(captured.size, 12);
+        assert!(captured.truncated);
+        assert!(captured.file.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_body_spill() {
+        let dir = temp_dir("spill");
+        let body = chunked_body(vec![b"abcdef", b"ghijkl", b"mnop"]);
+        let captured = read_body(body, "ep", &limits(BodyOverflow::Spill, dir.clone()))
+            .await
+            .unwrap();
+
+        assert!(captured.bytes.is_empty());
+        assert_eq!(captured.size, 16);
+        assert!(!captured.truncated);
+
+        let file = captured.file.expect("body should be spilled");
+        assert!(file.path().starts_with(dir.join("ep")));
+        assert_eq!(std::fs::read(file.path()).unwrap(), b"abcdefghijklmnop");
+
+        // Persisted files stay, others are removed once dropped
+        let path = file.persist();
+        assert!(path.exists());
+        let body = chunked_body(vec![b"abcdef", b"ghijkl"]);
+        let captured = read_body(body, "ep", &limits(BodyOverflow::Spill, dir.clone()))
+            .await
+            .unwrap();
+        let dropped = captured.file.unwrap().path().to_path_buf();
+        assert!(!dropped.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_read_body_spill_hard_cap() {
+        let dir = temp_dir("spill_cap");
+        let body = chunked_body(vec![
+            b"0123456789",
+            b"0123456789",
+            b"0123456789",
+            b"0123456789",
+        ]);
+        let captured = read_body(body, "ep", &limits(BodyOverflow::Spill, dir.clone()))
+            .await
+            .unwrap();
+
+        assert!(captured.truncated);
+        let file = captured.file.expect("body should be spilled");
+        assert_eq!(std::fs::read(file.path()).unwrap().len(), 32);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_read_body_stream_error_removes_spill() {
+        let dir = temp_dir("spill_error");
+        let stream = futures_util::stream::iter(vec![
+            Ok(axum::body::Bytes::from_static(b"abcdef")),
+            Ok(axum::body::Bytes::from_static(b"ghijkl")),
+            Err(std::io::Error::other("connection reset")),
+        ]);
+        let result = read_body(
+            Body::from_stream(stream),
+            "ep",
+            &limits(BodyOverflow::Spill, dir.clone()),
+        )
+        .await;
+
+        assert!(matches!(result, Err(BodyError::Stream(_))));
+        let left = std::fs::read_dir(dir.join("ep")).unwrap().count();
+        assert_eq!(left, 0);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,7 +1,25 @@
-use crate::models::{CreateEndpointResponse, Endpoint};
+use crate::analysis::signature::Scheme;
+use crate::config::BodyOverflow;
+use crate::models::{
+    CreateEndpointResponse, Endpoint, UpdateAuthConfig, UpdateFingerprintConfig,
+    UpdateIpFilterConfig, UpdateJwtConfig, UpdateRateLimitConfig, UpdateSchemaConfig,
+};
+use crate::response::latency::ResponseLatency;
+use crate::response::sequence::ResponseSequence;
+use crate::services::rule;
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
+/// Columns selected into the `Endpoint` model
+const ENDPOINT_COLUMNS: &str = "id, created_at, custom_response_enabled, response_status,
+    response_headers, response_body, response_template, request_count, max_body_size, body_overflow, durable_capture,
+    protobuf_descriptor, protobuf_message_type, protobuf_type_header, signature_scheme,
+    signature_secrets, signature_header, signature_tolerance_secs, signature_reject, jwt_key_type,
+    jwt_key, jwt_header, jwt_audience, jwt_issuer, jwt_leeway_secs, auth_type, auth_username,
+    auth_secret, auth_key_name, auth_key_location, ip_allow, ip_deny, ip_block_status,
+    ip_block_record, blocked_count, rate_limit, client_rate_limit, throttled_count,
+    json_schema, schema_reject, fingerprint_headers, response_sequence, response_latency";
+
 /// Create a new endpoint with a generated UUID
 pub async fn create_endpoint(pool: &SqlitePool) -> Result<CreateEndpointResponse, sqlx::Error> {
     let id = Uuid::new_v4().to_string();
@@ -23,35 +41,32 @@ pub async fn create_endpoint(pool: &SqlitePool) -> Result<CreateEndpointResponse
 
 /// List all endpoints with full configuration
 pub async fn list_endpoints(pool: &SqlitePool) -> Result<Vec<Endpoint>, sqlx::Error> {
-    let endpoints = sqlx::query_as::<_, Endpoint>(
-        r#"
-        SELECT id, created_at, custom_response_enabled, response_status,
-               response_headers, response_body, request_count
-        FROM endpoints
-        ORDER BY created_at DESC
-        "#,
-    )
+    let endpoints = sqlx::query_as::<_, Endpoint>(&format!(
+        "SELECT {} FROM endpoints ORDER BY created_at DESC",
+        ENDPOINT_COLUMNS
+    ))
     .fetch_all(pool)
     .await?;
 
     Ok(endpoints)
 }
 
-/// Get a single endpoint by ID
+/// Get a single endpoint by ID, with its response rules
 pub async fn get_endpoint(pool: &SqlitePool, id: &str) -> Result<Option<Endpoint>, sqlx::Error> {
-    let endpoint = sqlx::query_as::<_, Endpoint>(
-        r#"
-        SELECT id, created_at, custom_response_enabled, response_status,
-               response_headers, response_body, request_count
-        FROM endpoints
-        WHERE id = ?
-        "#,
-    )
+    let endpoint = sqlx::query_as::<_, Endpoint>(&format!(
+        "SELECT {} FROM endpoints WHERE id = ?",
+        ENDPOINT_COLUMNS
+    ))
     .bind(id)
     .fetch_optional(pool)
     .await?;
 
-    Ok(endpoint)
+    let Some(mut endpoint) = endpoint else {
+        return Ok(None);
+    };
+    endpoint.response_rules = rule::list_rules(pool, id).await?;
+
+    Ok(Some(endpoint))
 }
 
 /// Update custom response configuration for an endpoint
@@ -62,6 +77,7 @@ pub async fn update_response_config(
     status: i32,
     headers: Option<String>,
     body: Option<String>,
+    template: bool,
 ) -> Result<bool, sqlx::Error> {
     let result = sqlx::query(
         r#"
@@ -69,7 +85,8 @@ pub async fn update_response_config(
         SET custom_response_enabled = ?,
             response_status = ?,
             response_headers = ?,
-            response_body = ?
+            response_body = ?,
+            response_template = ?
         WHERE id = ?
         "#,
     )
@@ -77,6 +94,298 @@ pub async fn update_response_config(
     .bind(status)
     .bind(&headers)
     .bind(&body)
+    .bind(template)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update capture settings for an endpoint (None falls back to server defaults)
+pub async fn update_capture_config(
+    pool: &SqlitePool,
+    id: &str,
+    max_body_size: Option<i64>,
+    body_overflow: Option<BodyOverflow>,
+    durable_capture: Option<bool>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET max_body_size = ?,
+            body_overflow = ?,
+            durable_capture = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(max_body_size)
+    .bind(body_overflow.map(|mode| mode.as_str()))
+    .bind(durable_capture)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Set or clear the protobuf descriptor set of an endpoint
+pub async fn update_protobuf_config(
+    pool: &SqlitePool,
+    id: &str,
+    descriptor: Option<Vec<u8>>,
+    message_type: Option<String>,
+    type_header: Option<String>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET protobuf_descriptor = ?,
+            protobuf_message_type = ?,
+            protobuf_type_header = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(descriptor)
+    .bind(message_type)
+    .bind(type_header)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update signature verification for an endpoint; `secrets` is a JSON list
+pub async fn update_signature_config(
+    pool: &SqlitePool,
+    id: &str,
+    scheme: Option<Scheme>,
+    secrets: Option<String>,
+    header: Option<String>,
+    tolerance_secs: Option<i64>,
+    reject: bool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET signature_scheme = ?,
+            signature_secrets = ?,
+            signature_header = ?,
+            signature_tolerance_secs = ?,
+            signature_reject = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(scheme.map(|scheme| scheme.as_str()))
+    .bind(secrets)
+    .bind(header)
+    .bind(tolerance_secs)
+    .bind(reject)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update JWT verification for an endpoint
+pub async fn update_jwt_config(
+    pool: &SqlitePool,
+    id: &str,
+    config: &UpdateJwtConfig,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET jwt_key_type = ?,
+            jwt_key = ?,
+            jwt_header = ?,
+            jwt_audience = ?,
+            jwt_issuer = ?,
+            jwt_leeway_secs = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(config.key_type.map(|key_type| key_type.as_str()))
+    .bind(&config.key)
+    .bind(&config.header)
+    .bind(&config.audience)
+    .bind(&config.issuer)
+    .bind(config.leeway_secs)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update the credentials senders must present to an endpoint
+pub async fn update_auth_config(
+    pool: &SqlitePool,
+    id: &str,
+    config: &UpdateAuthConfig,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET auth_type = ?,
+            auth_username = ?,
+            auth_secret = ?,
+            auth_key_name = ?,
+            auth_key_location = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(config.auth_type.map(|auth_type| auth_type.as_str()))
+    .bind(&config.username)
+    .bind(&config.secret)
+    .bind(&config.key_name)
+    .bind(config.key_location.map(|location| location.as_str()))
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update the client address lists for an endpoint; `allow` and `deny` are stored as JSON lists
+pub async fn update_ip_filter_config(
+    pool: &SqlitePool,
+    id: &str,
+    config: &UpdateIpFilterConfig,
+) -> Result<bool, sqlx::Error> {
+    let list = |entries: &[String]| {
+        (!entries.is_empty()).then(|| serde_json::to_string(entries).unwrap_or_default())
+    };
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET ip_allow = ?,
+            ip_deny = ?,
+            ip_block_status = ?,
+            ip_block_record = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(list(&config.allow))
+    .bind(list(&config.deny))
+    .bind(config.block_status)
+    .bind(config.record_blocked)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update the request rate limits for an endpoint
+pub async fn update_rate_limit_config(
+    pool: &SqlitePool,
+    id: &str,
+    config: &UpdateRateLimitConfig,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET rate_limit = ?,
+            client_rate_limit = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(config.limit.map(|limit| limit.to_string()))
+    .bind(config.client_limit.map(|limit| limit.to_string()))
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update JSON Schema validation for an endpoint
+pub async fn update_schema_config(
+    pool: &SqlitePool,
+    id: &str,
+    config: &UpdateSchemaConfig,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET json_schema = ?,
+            schema_reject = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(config.schema.as_ref().map(|schema| schema.to_string()))
+    .bind(config.reject)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update the headers hashed with the body when fingerprinting deliveries
+pub async fn update_fingerprint_config(
+    pool: &SqlitePool,
+    id: &str,
+    config: &UpdateFingerprintConfig,
+) -> Result<bool, sqlx::Error> {
+    let headers = (!config.headers.is_empty())
+        .then(|| serde_json::to_string(&config.headers).unwrap_or_default());
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET fingerprint_headers = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(headers)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Replace an endpoint's response sequence; one without steps is cleared
+pub async fn update_sequence_config(
+    pool: &SqlitePool,
+    id: &str,
+    sequence: &ResponseSequence,
+) -> Result<bool, sqlx::Error> {
+    let json =
+        (!sequence.steps.is_empty()).then(|| serde_json::to_string(sequence).unwrap_or_default());
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET response_sequence = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(json)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Replace the latency injected into an endpoint's response; an empty one is cleared
+pub async fn update_latency_config(
+    pool: &SqlitePool,
+    id: &str,
+    latency: &ResponseLatency,
+) -> Result<bool, sqlx::Error> {
+    let json = (!latency.is_empty()).then(|| serde_json::to_string(latency).unwrap_or_default());
+    let result = sqlx::query(
+        r#"
+        UPDATE endpoints
+        SET response_latency = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(json)
     .bind(id)
     .execute(pool)
     .await?;
@@ -152,6 +461,7 @@ mod tests {
             404,
             Some(r#"{"x-custom":"value"}"#.to_string()),
             Some(r#"{"error":"not found"}"#.to_string()),
+            false,
         )
         .await
         .unwrap();
@@ -172,11 +482,42 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_update_capture_config() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        let created = create_endpoint(&pool).await.unwrap();
+
+        let updated = update_capture_config(
+            &pool,
+            &created.id,
+            Some(1024),
+            Some(BodyOverflow::Spill),
+            Some(true),
+        )
+        .await
+        .unwrap();
+        assert!(updated, "Update should succeed");
+
+        let endpoint = get_endpoint(&pool, &created.id).await.unwrap().unwrap();
+        assert_eq!(endpoint.max_body_size, Some(1024));
+        assert_eq!(endpoint.body_overflow, Some("spill".to_string()));
+        assert_eq!(endpoint.durable_capture, Some(true));
+
+        // Clearing falls back to server defaults
+        update_capture_config(&pool, &created.id, None, None, None)
+            .await
+            .unwrap();
+        let endpoint = get_endpoint(&pool, &created.id).await.unwrap().unwrap();
+        assert_eq!(endpoint.max_body_size, None);
+        assert_eq!(endpoint.body_overflow, None);
+        assert_eq!(endpoint.durable_capture, None);
+    }
+
     #[tokio::test]
     async fn test_update_response_config_nonexistent() {
         let pool = init_pool("sqlite::memory:").await.unwrap();
 
-        let updated = update_response_config(&pool, "nonexistent-id", true, 404, None, None)
+        let updated = update_response_config(&pool, "nonexistent-id", true, 404, None, None, false)
             .await
             .unwrap();
 
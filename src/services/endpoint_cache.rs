@@ -0,0 +1,203 @@
+use crate::models::Endpoint;
+use crate::services::endpoint;
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// In-memory cache of endpoint configuration keyed by endpoint ID.
+///
+/// Lookups for unknown IDs are cached too, so repeated requests to a missing endpoint
+/// are answered without touching the database. Once full, the least recently used
+/// lookup is dropped, so probing random IDs cannot flush endpoints in use. Handlers
+/// that create, update or delete an endpoint must call [`EndpointCache::invalidate`].
+/// A size of 0 disables caching.
+pub struct EndpointCache {
+    entries: Mutex<Entries>,
+    /// Bumped on every invalidation so a lookup racing with an update is not cached
+    generation: AtomicU64,
+    max_entries: usize,
+    /// Lookups answered from memory
+    hits: AtomicU64,
+    /// Lookups that went to the database
+    misses: AtomicU64,
+}
+
+impl EndpointCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            generation: AtomicU64::new(0),
+            max_entries,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Get an endpoint, loading it from the database on a cache miss
+    pub async fn get(
+        &self,
+        pool: &SqlitePool,
+        id: &str,
+    ) -> Result<Option<Arc<Endpoint>>, sqlx::Error> {
+        if self.max_entries == 0 {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return Ok(endpoint::get_endpoint(pool, id).await?.map(Arc::new));
+        }
+
+        if let Some(endpoint) = self.entries.lock().unwrap().touch(id) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(endpoint);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let endpoint = endpoint::get_endpoint(pool, id).await?.map(Arc::new);
+
+        let mut entries = self.entries.lock().unwrap();
+        if self.generation.load(Ordering::Acquire) == generation {
+            entries.insert(id, endpoint.clone(), self.max_entries);
+        }
+
+        Ok(endpoint)
+    }
+
+    /// Drop the cached entry for an endpoint after it was created, changed or deleted
+    pub fn invalidate(&self, id: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.remove(id);
+    }
+
+    /// Number of cached lookups
+    pub fn size(&self) -> usize {
+        self.entries.lock().unwrap().entries.len()
+    }
+
+    /// Lookups answered from memory and lookups that went to the database
+    pub fn hits_and_misses(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Cached lookups, dropping the least recently used once the cache is full
+#[derive(Default)]
+struct Entries {
+    entries: HashMap<String, Entry>,
+    /// IDs by last use, oldest first
+    recent: BTreeMap<u64, String>,
+    next_use: u64,
+}
+
+struct Entry {
+    endpoint: Option<Arc<Endpoint>>,
+    used: u64,
+}
+
+impl Entries {
+    /// Cached lookup for an ID, marked as just used
+    fn touch(&mut self, id: &str) -> Option<Option<Arc<Endpoint>>> {
+        let entry = self.entries.get_mut(id)?;
+        self.recent.remove(&entry.used);
+        entry.used = self.next_use;
+        self.next_use += 1;
+        self.recent.insert(entry.used, id.to_string());
+        Some(entry.endpoint.clone())
+    }
+
+    fn insert(&mut self, id: &str, endpoint: Option<Arc<Endpoint>>, max_entries: usize) {
+        self.remove(id);
+        if self.entries.len() >= max_entries {
+            if let Some((_, oldest)) = self.recent.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        let used = self.next_use;
+        self.next_use += 1;
+        self.recent.insert(used, id.to_string());
+        self.entries
+            .insert(id.to_string(), Entry { endpoint, used });
+    }
+
+    fn remove(&mut self, id: &str) {
+        if let Some(entry) = self.entries.remove(id) {
+            self.recent.remove(&entry.used);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_ENDPOINT_CACHE_SIZE;
+    use crate::db::init_pool;
+
+    #[tokio::test]
+    async fn test_cache_serves_without_database() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        let created = endpoint::create_endpoint(&pool).await.unwrap();
+        let cache = EndpointCache::new(DEFAULT_ENDPOINT_CACHE_SIZE);
+
+        assert!(cache.get(&pool, &created.id).await.unwrap().is_some());
+
+        // Remove the row behind the cache's back; the cached entry is still served
+        sqlx::query("DELETE FROM endpoints WHERE id = ?")
+            .bind(&created.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(cache.get(&pool, &created.id).await.unwrap().is_some());
+
+        cache.invalidate(&created.id);
+        assert!(cache.get(&pool, &created.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cache_missing_endpoint_until_invalidated() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        let cache = EndpointCache::new(DEFAULT_ENDPOINT_CACHE_SIZE);
+
+        assert!(cache.get(&pool, "later").await.unwrap().is_none());
+        assert_eq!(cache.size(), 1);
+
+        sqlx::query("INSERT INTO endpoints (id) VALUES ('later')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(cache.get(&pool, "later").await.unwrap().is_none());
+
+        cache.invalidate("later");
+        assert!(cache.get(&pool, "later").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_cache_is_bounded() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        let cache = EndpointCache::new(2);
+
+        // "a" is used again after "b", so "b" is the one dropped for "c"
+        for id in ["a", "b", "a", "c"] {
+            cache.get(&pool, id).await.unwrap();
+        }
+        assert_eq!(cache.size(), 2);
+        assert_eq!(cache.hits_and_misses(), (1, 3));
+
+        cache.get(&pool, "a").await.unwrap();
+        cache.get(&pool, "c").await.unwrap();
+        assert_eq!(cache.hits_and_misses(), (3, 3));
+        cache.get(&pool, "b").await.unwrap();
+        assert_eq!(cache.hits_and_misses(), (3, 4));
+    }
+
+    #[tokio::test]
+    async fn test_cache_disabled() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        let cache = EndpointCache::new(0);
+
+        assert!(cache.get(&pool, "a").await.unwrap().is_none());
+        assert_eq!(cache.size(), 0);
+    }
+}
@@ -0,0 +1,514 @@
+use crate::config::Backpressure;
+use crate::models::{
+    parse_raw_headers, webhook_sub_path, AuthCheck, JwtCheck, SchemaCheck, SignatureCheck,
+};
+use crate::services::request::{self as request_service, StoreRequestData, StoredRequest};
+use crate::websocket::{RequestData, WebSocketManager, WebSocketMessage};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// A captured request waiting to be written
+struct IngestJob {
+    data: StoreRequestData,
+    /// Notified once the request is committed (durable capture only)
+    ack: Option<oneshot::Sender<Result<i64, IngestError>>>,
+}
+
+/// Errors returned when submitting a request to the ingest queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IngestError {
+    /// The queue is full and backpressure is set to reject
+    QueueFull,
+    /// The writer has shut down
+    Closed,
+    /// The request could not be stored
+    Storage(String),
+}
+
+impl std::fmt::Display for IngestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IngestError::QueueFull => write!(f, "ingest queue is full"),
+            IngestError::Closed => write!(f, "ingest queue is closed"),
+            IngestError::Storage(e) => write!(f, "failed to store request: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {}
+
+/// Bounded queue feeding a single writer task that stores captured requests in
+/// batched transactions and broadcasts them to WebSocket clients once committed.
+pub struct IngestQueue {
+    tx: mpsc::Sender<IngestJob>,
+    capacity: usize,
+    backpressure: Backpressure,
+    shutdown: Arc<Notify>,
+    writer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl IngestQueue {
+    /// Create the queue and spawn its writer task
+    pub fn start(
+        pool: SqlitePool,
+        ws_manager: Arc<WebSocketManager>,
+        capacity: usize,
+        batch_size: usize,
+        backpressure: Backpressure,
+    ) -> Self {
+        let capacity = capacity.max(1);
+        let (tx, rx) = mpsc::channel(capacity);
+        let shutdown = Arc::new(Notify::new());
+        let writer = tokio::spawn(run_writer(
+            pool,
+            ws_manager,
+            rx,
+            batch_size.max(1),
+            shutdown.clone(),
+        ));
+
+        Self {
+            tx,
+            capacity,
+            backpressure,
+            shutdown,
+            writer: Mutex::new(Some(writer)),
+        }
+    }
+
+    /// Queue a request for storage without waiting for it to be written
+    pub async fn submit(&self, data: StoreRequestData) -> Result<(), IngestError> {
+        self.enqueue(IngestJob { data, ack: None }).await
+    }
+
+    /// Queue a request and wait until it is committed, returning its ID
+    pub async fn submit_and_wait(&self, data: StoreRequestData) -> Result<i64, IngestError> {
+        let (ack_tx, ack_rx) = oneshot::channel();
+        self.enqueue(IngestJob {
+            data,
+            ack: Some(ack_tx),
+        })
+        .await?;
+
+        ack_rx.await.unwrap_or(Err(IngestError::Closed))
+    }
+
+    /// Number of requests waiting to be written
+    pub fn depth(&self) -> usize {
+        self.capacity - self.tx.capacity()
+    }
+
+    /// Maximum number of requests the queue can hold
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Stop accepting requests and wait for the writer to drain the queue
+    pub async fn shutdown(&self) {
+        self.shutdown.notify_one();
+        let writer = self.writer.lock().unwrap().take();
+        if let Some(writer) = writer {
+            if let Err(e) = writer.await {
+                error!("Ingest writer task failed: {}", e);
+            }
+        }
+    }
+
+    async fn enqueue(&self, job: IngestJob) -> Result<(), IngestError> {
+        let (job, error) = match self.backpressure {
+            Backpressure::Block => match self.tx.send(job).await {
+                Ok(()) => return Ok(()),
+                Err(mpsc::error::SendError(job)) => (job, IngestError::Closed),
+            },
+            Backpressure::Reject => match self.tx.try_send(job) {
+                Ok(()) => return Ok(()),
+                Err(mpsc::error::TrySendError::Full(job)) => {
+                    warn!(
+                        "Ingest queue full ({} requests), rejecting request for endpoint {}",
+                        self.capacity, job.data.endpoint_id
+                    );
+                    (job, IngestError::QueueFull)
+                }
+                Err(mpsc::error::TrySendError::Closed(job)) => (job, IngestError::Closed),
+            },
+        };
+
+        // The request will never be stored, so nothing else will clean up its spilled body
+        remove_body_file(&job.data).await;
+        Err(error)
+    }
+}
+
+async fn remove_body_file(data: &StoreRequestData) {
+    if let Some(body_file) = &data.body_file {
+        let _ = tokio::fs::remove_file(body_file).await;
+    }
+}
+
+/// Writer loop: pull up to `batch_size` jobs at a time and store them together
+async fn run_writer(
+    pool: SqlitePool,
+    ws_manager: Arc<WebSocketManager>,
+    mut rx: mpsc::Receiver<IngestJob>,
+    batch_size: usize,
+    shutdown: Arc<Notify>,
+) {
+    let mut batch = Vec::with_capacity(batch_size);
+
+    loop {
+        tokio::select! {
+            received = rx.recv_many(&mut batch, batch_size) => {
+                if received == 0 {
+                    break;
+                }
+            }
+            _ = shutdown.notified() => {
+                // Refuse new requests but keep draining what is already queued
+                rx.close();
+                continue;
+            }
+        }
+
+        let jobs = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+        debug!(
+            "Writing batch of {} requests ({} still queued)",
+            jobs.len(),
+            rx.len()
+        );
+        write_batch(&pool, &ws_manager, jobs).await;
+    }
+
+    debug!("Ingest writer stopped");
+}
+
+/// Store a batch in one transaction, falling back to one transaction per request
+/// so a single bad row doesn't fail the whole batch
+async fn write_batch(pool: &SqlitePool, ws_manager: &Arc<WebSocketManager>, jobs: Vec<IngestJob>) {
+    match store_batch(pool, &jobs).await {
+        Ok(stored) => {
+            for (job, stored) in jobs.into_iter().zip(stored) {
+                finish_job(ws_manager, job, Ok(stored)).await;
+            }
+        }
+        Err(e) => {
+            warn!(
+                "Batch insert of {} requests failed, retrying individually: {}",
+                jobs.len(),
+                e
+            );
+            for job in jobs {
+                let result = request_service::store_captured_request(pool, &job.data).await;
+                finish_job(ws_manager, job, result).await;
+            }
+        }
+    }
+}
+
+async fn store_batch(
+    pool: &SqlitePool,
+    jobs: &[IngestJob],
+) -> Result<Vec<StoredRequest>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut stored = Vec::with_capacity(jobs.len());
+    let mut counts: HashMap<&str, i64> = HashMap::new();
+    let mut blocked: HashMap<&str, i64> = HashMap::new();
+
+    for job in jobs {
+        stored.push(request_service::store_request(&mut *tx, &job.data).await?);
+        let counter = if job.data.blocked {
+            &mut blocked
+        } else {
+            &mut counts
+        };
+        *counter.entry(job.data.endpoint_id.as_str()).or_default() += 1;
+    }
+
+    for (endpoint_id, count) in counts {
+        request_service::add_request_count(&mut *tx, endpoint_id, count).await?;
+    }
+    for (endpoint_id, count) in blocked {
+        request_service::add_blocked_count(&mut *tx, endpoint_id, count).await?;
+    }
+
+    tx.commit().await?;
+    Ok(stored)
+}
+
+/// Acknowledge a job and broadcast it when stored; clean up its spilled body otherwise
+async fn finish_job(
+    ws_manager: &Arc<WebSocketManager>,
+    job: IngestJob,
+    result: Result<StoredRequest, sqlx::Error>,
+) {
+    let result = match result {
+        Ok(stored) => {
+            info!(
+                "Captured {} request to endpoint {} from {}",
+                job.data.method, job.data.endpoint_id, job.data.ip_address
+            );
+            Ok(stored)
+        }
+        Err(e) => {
+            error!(
+                "Failed to store request for endpoint {}: {}",
+                job.data.endpoint_id, e
+            );
+            remove_body_file(&job.data).await;
+            Err(IngestError::Storage(e.to_string()))
+        }
+    };
+
+    if let Some(ack) = job.ack {
+        let _ = ack.send(result.clone().map(|stored| stored.id));
+    }
+
+    // Broadcast to WebSocket clients. Decoding the body can be slow, so it happens
+    // on a blocking thread rather than holding up the next batch.
+    if let Ok(stored) = result {
+        let ws_manager = ws_manager.clone();
+        tokio::spawn(async move {
+            match tokio::task::spawn_blocking(move || request_data(stored, job.data)).await {
+                Ok(data) => {
+                    let endpoint_id = data.endpoint_id.clone();
+                    ws_manager
+                        .broadcast(
+                            &endpoint_id,
+                            WebSocketMessage::NewRequest {
+                                data: Box::new(data),
+                            },
+                        )
+                        .await;
+                }
+                Err(e) => error!("Failed to build broadcast for stored request: {}", e),
+            }
+        });
+    }
+}
+
+/// Build the WebSocket payload for a stored request
+fn request_data(stored: StoredRequest, data: StoreRequestData) -> RequestData {
+    // Parse headers JSON string into serde_json::Value
+    let headers_value =
+        serde_json::from_str(&data.headers).unwrap_or_else(|_| serde_json::json!({}));
+
+    // Query string as ordered pairs and as a structured object
+    let query_pairs = crate::decode::params::parse_pairs(
+        data.query_string.as_deref().unwrap_or_default().as_bytes(),
+    );
+    let query_params_value = crate::decode::params::structure(&query_pairs);
+
+    let raw_headers = parse_raw_headers(Some(&data.raw_headers));
+    let jwts = crate::analysis::jwt::find_tokens(&headers_value);
+
+    let views = crate::decode::body_views(
+        &headers_value,
+        data.content_type.as_deref(),
+        data.body.as_deref(),
+        data.protobuf.as_ref(),
+        crate::decode::encoding::MAX_DECODED_SIZE,
+    );
+
+    // Convert body bytes to UTF-8 string if present
+    let body_string = data.body.and_then(|bytes| String::from_utf8(bytes).ok());
+
+    RequestData {
+        id: stored.id,
+        sub_path: webhook_sub_path(&data.path, &data.endpoint_id),
+        endpoint_id: data.endpoint_id,
+        method: data.method,
+        path: data.path,
+        query_string: data.query_string,
+        query_params: query_params_value,
+        query_pairs,
+        headers: headers_value,
+        raw_headers,
+        body: body_string,
+        decoded: views.decoded,
+        form_params: views.form_params,
+        parts: views.parts,
+        protobuf: views.protobuf,
+        structured: views.structured,
+        body_size: data.body_size,
+        body_truncated: data.body_truncated,
+        body_spilled: data.body_file.is_some(),
+        content_type: data.content_type,
+        received_at: data.received_at,
+        ip_address: Some(data.ip_address),
+        peer_address: Some(data.peer_address),
+        remote_port: Some(data.remote_port),
+        local_address: data.local_address,
+        local_port: data.local_port,
+        http_version: Some(data.http_version),
+        tls: data.tls,
+        request_size: Some(data.request_size),
+        processing_time_us: Some(data.processing_time_us),
+        provider: data.provider,
+        event_type: data.event_type,
+        signature: SignatureCheck::from_columns(
+            data.signature_status,
+            data.signature_computed,
+            data.signature_provided,
+        ),
+        jwts,
+        jwt: JwtCheck::from_columns(data.jwt_status, data.jwt_error),
+        auth: AuthCheck::from_columns(data.auth_status, data.auth_error),
+        blocked: data.blocked,
+        schema: SchemaCheck::from_columns(data.schema_status, data.schema_errors),
+        fingerprint: data.fingerprint,
+        delivery_id: data.delivery_id,
+        duplicate_of: stored.duplicate_of,
+        retry: stored.retry,
+        response_error: data.response_error,
+        rule_id: data.rule_id,
+        sequence_position: data.sequence_position,
+        response_delay_ms: data.response_delay_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_pool;
+
+    async fn create_test_endpoint(pool: &SqlitePool, id: &str) {
+        sqlx::query("INSERT INTO endpoints (id) VALUES (?)")
+            .bind(id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    fn test_data(endpoint_id: &str) -> StoreRequestData {
+        StoreRequestData {
+            endpoint_id: endpoint_id.to_string(),
+            method: "POST".to_string(),
+            path: format!("/webhook/{}", endpoint_id),
+            query_string: None,
+            headers: "{}".to_string(),
+            body: Some(b"{}".to_vec()),
+            content_type: Some("application/json".to_string()),
+            received_at: chrono::Utc::now().to_rfc3339(),
+            ip_address: "127.0.0.1".to_string(),
+            body_size: 2,
+            body_truncated: false,
+            body_file: None,
+            ..Default::default()
+        }
+    }
+
+    async fn count_for(pool: &SqlitePool, endpoint_id: &str) -> (i64, i64) {
+        sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM requests WHERE endpoint_id = ?1), request_count
+             FROM endpoints WHERE id = ?1",
+        )
+        .bind(endpoint_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_batched_writes() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        create_test_endpoint(&pool, "a").await;
+        create_test_endpoint(&pool, "b").await;
+
+        let queue = IngestQueue::start(
+            pool.clone(),
+            Arc::new(WebSocketManager::new()),
+            1000,
+            64,
+            Backpressure::Block,
+        );
+
+        for i in 0..200 {
+            let endpoint_id = if i % 2 == 0 { "a" } else { "b" };
+            queue.submit(test_data(endpoint_id)).await.unwrap();
+        }
+        queue.shutdown().await;
+
+        assert_eq!(count_for(&pool, "a").await, (100, 100));
+        assert_eq!(count_for(&pool, "b").await, (100, 100));
+        assert_eq!(queue.depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_wait_returns_committed_id() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        create_test_endpoint(&pool, "a").await;
+
+        let queue = IngestQueue::start(
+            pool.clone(),
+            Arc::new(WebSocketManager::new()),
+            10,
+            10,
+            Backpressure::Reject,
+        );
+
+        let request_id = queue.submit_and_wait(test_data("a")).await.unwrap();
+        let stored: Option<(i64,)> = sqlx::query_as("SELECT id FROM requests WHERE id = ?")
+            .bind(request_id)
+            .fetch_optional(&pool)
+            .await
+            .unwrap();
+        assert!(stored.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_row_does_not_fail_batch() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        create_test_endpoint(&pool, "a").await;
+
+        let queue = IngestQueue::start(
+            pool.clone(),
+            Arc::new(WebSocketManager::new()),
+            10,
+            10,
+            Backpressure::Block,
+        );
+
+        // The missing endpoint violates the foreign key
+        let missing = queue.submit_and_wait(test_data("missing"));
+        let ok = queue.submit_and_wait(test_data("a"));
+        let (missing, ok) = tokio::join!(missing, ok);
+
+        assert!(matches!(missing, Err(IngestError::Storage(_))));
+        assert!(ok.is_ok());
+        assert_eq!(count_for(&pool, "a").await, (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_reject_when_full() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        let queue = IngestQueue::start(
+            pool,
+            Arc::new(WebSocketManager::new()),
+            1,
+            1,
+            Backpressure::Reject,
+        );
+
+        // Stop the writer so nothing is drained
+        queue.shutdown().await;
+        let (tx, _rx) = mpsc::channel(1);
+        let queue = IngestQueue { tx, ..queue };
+
+        assert!(queue.submit(test_data("a")).await.is_ok());
+        assert_eq!(queue.depth(), 1);
+
+        // A rejected request's spilled body is removed
+        let body_file =
+            std::env::temp_dir().join(format!("hookshot_rejected_{}.bin", std::process::id()));
+        std::fs::write(&body_file, b"spilled").unwrap();
+        let data = StoreRequestData {
+            body_file: Some(body_file.to_string_lossy().into_owned()),
+            ..test_data("a")
+        };
+        assert_eq!(queue.submit(data).await, Err(IngestError::QueueFull));
+        assert!(!body_file.exists());
+    }
+}
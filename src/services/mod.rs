@@ -1,2 +1,6 @@
+pub mod body;
 pub mod endpoint;
+pub mod endpoint_cache;
+pub mod ingest;
 pub mod request;
+pub mod rule;
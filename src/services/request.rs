@@ -1,6 +1,8 @@
-use sqlx::SqlitePool;
+use crate::decode::protobuf::ProtobufDecoder;
+use sqlx::{Executor, Sqlite, SqlitePool};
 
 /// Request data for storing in database
+#[derive(Debug, Default)]
 pub struct StoreRequestData {
     pub endpoint_id: String,
     pub method: String,
@@ -11,46 +13,229 @@ pub struct StoreRequestData {
     pub content_type: Option<String>,
     pub received_at: String,
     pub ip_address: String,
+    pub body_size: i64,
+    pub body_truncated: bool,
+    pub body_file: Option<String>,
+    pub raw_headers: String, // JSON list of `RawHeader`
+    pub http_version: String,
+    pub peer_address: String, // Socket peer; `ip_address` is the resolved client
+    pub remote_port: i64,
+    pub local_address: Option<String>,
+    pub local_port: Option<i64>,
+    pub tls: Option<bool>,
+    pub request_size: i64,
+    pub processing_time_us: i64,
+    pub provider: Option<String>, // e.g. "github"
+    pub event_type: Option<String>,
+    pub signature_status: Option<String>, // "valid", "invalid", "missing", "expired" or "error"
+    pub signature_computed: Option<String>,
+    pub signature_provided: Option<String>,
+    pub jwt_status: Option<String>, // "valid", "invalid", "missing", "expired" or "error"
+    pub jwt_error: Option<String>,
+    pub auth_status: Option<String>, // "authorized" or "unauthorized"
+    pub auth_error: Option<String>,
+    pub blocked: bool, // Counted in `blocked_count` rather than `request_count`
+    pub schema_status: Option<String>, // "valid", "invalid" or "skipped"
+    pub schema_errors: Option<String>, // JSON list of `SchemaError`
+    pub fingerprint: Option<String>, // Method, sub-path, body hash and selected headers; None without a body
+    pub delivery_id: Option<String>, // Provider's ID for the delivery, kept across retries
+    pub response_error: Option<String>, // Why rendering failed, or that a spilled body was left out
+    pub rule_id: Option<i64>,
+    pub sequence_position: Option<i64>, // Step of the response sequence that answered
+    pub response_delay_ms: Option<i64>, // Delay applied before answering
+    pub protobuf: Option<ProtobufDecoder>, // Renders the broadcast's protobuf view; not stored
 }
 
-/// Store a captured request in the database
-#[allow(dead_code)]
-pub async fn store_request(pool: &SqlitePool, data: StoreRequestData) -> Result<i64, sqlx::Error> {
-    let result = sqlx::query(
+/// A stored request and how it relates to earlier deliveries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoredRequest {
+    pub id: i64,
+    /// First request to the endpoint with the same delivery ID, or the same fingerprint
+    /// when the request has no delivery ID
+    pub duplicate_of: Option<i64>,
+    /// Number of earlier matching requests; 0 for a first delivery
+    pub retry: i64,
+}
+
+/// Earlier requests with the same delivery ID, answered by `idx_requests_endpoint_delivery`
+const SAME_DELIVERY: &str = "endpoint_id = ? AND delivery_id = ?";
+
+/// Earlier requests with the same fingerprint, answered by `idx_requests_endpoint_fingerprint`.
+/// Keep the two filters apart: an `OR` of them only uses `endpoint_id` and scans every
+/// request the endpoint holds.
+const SAME_FINGERPRINT: &str = "endpoint_id = ? AND fingerprint = ?";
+
+/// Store a captured request in the database, linking it to the first delivery it repeats
+pub async fn store_request<'e, E>(
+    executor: E,
+    data: &StoreRequestData,
+) -> Result<StoredRequest, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    // The delivery ID takes precedence; a request with neither matches nothing
+    let (repeats, key) = match &data.delivery_id {
+        Some(delivery_id) => (SAME_DELIVERY, Some(delivery_id)),
+        None => (SAME_FINGERPRINT, data.fingerprint.as_ref()),
+    };
+    let sql = format!(
         r#"
-        INSERT INTO requests (endpoint_id, method, path, query_string, headers, body, content_type, received_at, ip_address)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO requests (endpoint_id, method, path, query_string, headers, body, content_type, received_at, ip_address,
+                              body_size, body_truncated, body_file, raw_headers, http_version, peer_address,
+                              remote_port, local_address, local_port, tls, request_size, processing_time_us,
+                              provider, event_type, signature_status, signature_computed, signature_provided,
+                              jwt_status, jwt_error, auth_status, auth_error, blocked,
+                              schema_status, schema_errors, fingerprint, delivery_id, response_error,
+                              rule_id, sequence_position, response_delay_ms, duplicate_of, retry)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                (SELECT MIN(id) FROM requests WHERE {repeats}),
+                (SELECT COUNT(*) FROM requests WHERE {repeats}))
+        RETURNING id, duplicate_of, retry
         "#
-    )
-    .bind(&data.endpoint_id)
-    .bind(&data.method)
-    .bind(&data.path)
-    .bind(&data.query_string)
-    .bind(&data.headers)
-    .bind(&data.body)
-    .bind(&data.content_type)
-    .bind(&data.received_at)
-    .bind(&data.ip_address)
-    .execute(pool)
-    .await?;
-
-    Ok(result.last_insert_rowid())
+    );
+    let (id, duplicate_of, retry) = sqlx::query_as(&sql)
+        .bind(&data.endpoint_id)
+        .bind(&data.method)
+        .bind(&data.path)
+        .bind(&data.query_string)
+        .bind(&data.headers)
+        .bind(&data.body)
+        .bind(&data.content_type)
+        .bind(&data.received_at)
+        .bind(&data.ip_address)
+        .bind(data.body_size)
+        .bind(data.body_truncated)
+        .bind(&data.body_file)
+        .bind(&data.raw_headers)
+        .bind(&data.http_version)
+        .bind(&data.peer_address)
+        .bind(data.remote_port)
+        .bind(&data.local_address)
+        .bind(data.local_port)
+        .bind(data.tls)
+        .bind(data.request_size)
+        .bind(data.processing_time_us)
+        .bind(&data.provider)
+        .bind(&data.event_type)
+        .bind(&data.signature_status)
+        .bind(&data.signature_computed)
+        .bind(&data.signature_provided)
+        .bind(&data.jwt_status)
+        .bind(&data.jwt_error)
+        .bind(&data.auth_status)
+        .bind(&data.auth_error)
+        .bind(data.blocked)
+        .bind(&data.schema_status)
+        .bind(&data.schema_errors)
+        .bind(&data.fingerprint)
+        .bind(&data.delivery_id)
+        .bind(&data.response_error)
+        .bind(data.rule_id)
+        .bind(data.sequence_position)
+        .bind(data.response_delay_ms)
+        // Once for the first occurrence, once for the retry ordinal
+        .bind(&data.endpoint_id)
+        .bind(key)
+        .bind(&data.endpoint_id)
+        .bind(key)
+        .fetch_one(executor)
+        .await?;
+
+    Ok(StoredRequest {
+        id,
+        duplicate_of,
+        retry,
+    })
 }
 
 /// Increment request count for an endpoint
-#[allow(dead_code)]
-pub async fn increment_request_count(
-    pool: &SqlitePool,
+pub async fn increment_request_count<'e, E>(
+    executor: E,
     endpoint_id: &str,
-) -> Result<(), sqlx::Error> {
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
     sqlx::query("UPDATE endpoints SET request_count = request_count + 1 WHERE id = ?")
         .bind(endpoint_id)
-        .execute(pool)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Add a batch of captured requests to an endpoint's request count
+pub async fn add_request_count<'e, E>(
+    executor: E,
+    endpoint_id: &str,
+    count: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query("UPDATE endpoints SET request_count = request_count + ? WHERE id = ?")
+        .bind(count)
+        .bind(endpoint_id)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Add requests refused by an endpoint's IP filter to its blocked count
+pub async fn add_blocked_count<'e, E>(
+    executor: E,
+    endpoint_id: &str,
+    count: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query("UPDATE endpoints SET blocked_count = blocked_count + ? WHERE id = ?")
+        .bind(count)
+        .bind(endpoint_id)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Add requests refused by rate limiting to an endpoint's throttled count
+pub async fn add_throttled_count<'e, E>(
+    executor: E,
+    endpoint_id: &str,
+    count: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query("UPDATE endpoints SET throttled_count = throttled_count + ? WHERE id = ?")
+        .bind(count)
+        .bind(endpoint_id)
+        .execute(executor)
         .await?;
 
     Ok(())
 }
 
+/// Store a captured request and increment the endpoint's request count atomically.
+/// Blocked requests are added to the blocked count instead.
+pub async fn store_captured_request(
+    pool: &SqlitePool,
+    data: &StoreRequestData,
+) -> Result<StoredRequest, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let stored = store_request(&mut *tx, data).await?;
+    if data.blocked {
+        add_blocked_count(&mut *tx, &data.endpoint_id, 1).await?;
+    } else {
+        increment_request_count(&mut *tx, &data.endpoint_id).await?;
+    }
+    tx.commit().await?;
+
+    Ok(stored)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,7 +264,7 @@ mod tests {
 
         let request_id = store_request(
             &pool,
-            StoreRequestData {
+            &StoreRequestData {
                 endpoint_id: endpoint_id.to_string(),
                 method: "POST".to_string(),
                 path: "/webhook/test".to_string(),
@@ -89,10 +274,15 @@ mod tests {
                 content_type: Some("application/json".to_string()),
                 received_at: received_at.clone(),
                 ip_address: "127.0.0.1".to_string(),
+                body_size: 9,
+                body_truncated: false,
+                body_file: None,
+                ..Default::default()
             },
         )
         .await
-        .unwrap();
+        .unwrap()
+        .id;
 
         assert!(request_id > 0);
 
@@ -113,6 +303,97 @@ mod tests {
         assert_eq!(stored.body, body);
         assert_eq!(stored.content_type, Some("application/json".to_string()));
         assert_eq!(stored.ip_address, Some("127.0.0.1".to_string()));
+
+        let body_size: Option<i64> =
+            sqlx::query_scalar("SELECT body_size FROM requests WHERE id = ?")
+                .bind(request_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(body_size, Some(9));
+    }
+
+    #[tokio::test]
+    async fn test_store_request_links_duplicates() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        create_test_endpoint(&pool, "a").await;
+        create_test_endpoint(&pool, "b").await;
+
+        let data =
+            |endpoint_id: &str, fingerprint: &str, delivery_id: Option<&str>| StoreRequestData {
+                endpoint_id: endpoint_id.to_string(),
+                method: "POST".to_string(),
+                fingerprint: Some(fingerprint.to_string()),
+                delivery_id: delivery_id.map(str::to_string),
+                ..Default::default()
+            };
+        let store = |data: StoreRequestData| {
+            let pool = pool.clone();
+            async move { store_request(&pool, &data).await.unwrap() }
+        };
+
+        let first = store(data("a", "f1", None)).await;
+        assert_eq!((first.duplicate_of, first.retry), (None, 0));
+        let second = store(data("a", "f1", None)).await;
+        assert_eq!((second.duplicate_of, second.retry), (Some(first.id), 1));
+        let third = store(data("a", "f1", None)).await;
+        assert_eq!((third.duplicate_of, third.retry), (Some(first.id), 2));
+
+        // Other endpoints and other bodies are not duplicates
+        assert_eq!(store(data("b", "f1", None)).await.duplicate_of, None);
+        assert_eq!(store(data("a", "f2", None)).await.duplicate_of, None);
+
+        // Requests without a fingerprint or delivery ID are never linked
+        let ping = || StoreRequestData {
+            endpoint_id: "a".to_string(),
+            method: "GET".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(store(ping()).await.duplicate_of, None);
+        let second_ping = store(ping()).await;
+        assert_eq!((second_ping.duplicate_of, second_ping.retry), (None, 0));
+
+        // Delivery IDs take precedence over the fingerprint
+        let delivery = store(data("a", "f1", Some("d1"))).await;
+        assert_eq!(delivery.duplicate_of, None);
+        let redelivery = store(data("a", "f3", Some("d1"))).await;
+        assert_eq!(
+            (redelivery.duplicate_of, redelivery.retry),
+            (Some(delivery.id), 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_lookups_use_their_index() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+
+        for (filter, index) in [
+            (
+                SAME_DELIVERY,
+                "idx_requests_endpoint_delivery (endpoint_id=? AND delivery_id=?)",
+            ),
+            (
+                SAME_FINGERPRINT,
+                "idx_requests_endpoint_fingerprint (endpoint_id=? AND fingerprint=?)",
+            ),
+        ] {
+            let plan: Vec<(i64, i64, i64, String)> = sqlx::query_as(&format!(
+                "EXPLAIN QUERY PLAN SELECT MIN(id), COUNT(*) FROM requests WHERE {}",
+                filter
+            ))
+            .bind("a")
+            .bind("key")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            assert!(
+                // Both columns, not just a search on `endpoint_id`
+                plan.iter().any(|(.., detail)| detail.contains(index)),
+                "{}: {:?}",
+                filter,
+                plan
+            );
+        }
     }
 
     #[tokio::test]
@@ -150,4 +431,53 @@ mod tests {
             .unwrap();
         assert_eq!(count, 2);
     }
+
+    #[tokio::test]
+    async fn test_store_captured_request_rolls_back() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        let endpoint_id = "test-endpoint";
+        create_test_endpoint(&pool, endpoint_id).await;
+
+        let data = StoreRequestData {
+            endpoint_id: endpoint_id.to_string(),
+            method: "POST".to_string(),
+            path: "/webhook/test".to_string(),
+            query_string: None,
+            headers: "{}".to_string(),
+            body: None,
+            content_type: None,
+            received_at: chrono::Utc::now().to_rfc3339(),
+            ip_address: "127.0.0.1".to_string(),
+            body_size: 0,
+            body_truncated: false,
+            body_file: None,
+            ..Default::default()
+        };
+
+        store_captured_request(&pool, &data).await.unwrap();
+
+        // Make the counter update fail so the insert has to be rolled back
+        sqlx::query(
+            "CREATE TRIGGER fail_count BEFORE UPDATE ON endpoints
+             BEGIN SELECT RAISE(ABORT, 'count failed'); END",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(store_captured_request(&pool, &data).await.is_err());
+
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM requests")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, 1);
+
+        let count: i32 = sqlx::query_scalar("SELECT request_count FROM endpoints WHERE id = ?")
+            .bind(endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
 }
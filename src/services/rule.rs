@@ -0,0 +1,202 @@
+use crate::models::{ResponseRule, ResponseRuleConfig};
+use sqlx::types::Json;
+use sqlx::SqlitePool;
+
+/// Columns selected into the `ResponseRule` model
+const RULE_COLUMNS: &str =
+    "id, endpoint_id, position, name, matchers, status, headers, body, template, delay_ms, created_at";
+
+/// List an endpoint's response rules in evaluation order
+pub async fn list_rules(
+    pool: &SqlitePool,
+    endpoint_id: &str,
+) -> Result<Vec<ResponseRule>, sqlx::Error> {
+    sqlx::query_as::<_, ResponseRule>(&format!(
+        "SELECT {} FROM response_rules WHERE endpoint_id = ? ORDER BY position, id",
+        RULE_COLUMNS
+    ))
+    .bind(endpoint_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Get one of an endpoint's response rules
+pub async fn get_rule(
+    pool: &SqlitePool,
+    endpoint_id: &str,
+    rule_id: i64,
+) -> Result<Option<ResponseRule>, sqlx::Error> {
+    sqlx::query_as::<_, ResponseRule>(&format!(
+        "SELECT {} FROM response_rules WHERE id = ? AND endpoint_id = ?",
+        RULE_COLUMNS
+    ))
+    .bind(rule_id)
+    .bind(endpoint_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Add a rule to an endpoint, after its last rule unless a position is given
+pub async fn create_rule(
+    pool: &SqlitePool,
+    endpoint_id: &str,
+    config: &ResponseRuleConfig,
+) -> Result<ResponseRule, sqlx::Error> {
+    let id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO response_rules (endpoint_id, position, name, matchers, status, headers, body, template, delay_ms)
+        VALUES (?, COALESCE(?, (SELECT COALESCE(MAX(position), -1) + 1 FROM response_rules WHERE endpoint_id = ?)),
+                ?, ?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(endpoint_id)
+    .bind(config.position)
+    .bind(endpoint_id)
+    .bind(&config.name)
+    .bind(Json(&config.matchers))
+    .bind(config.status)
+    .bind(headers_json(config))
+    .bind(&config.body)
+    .bind(config.template)
+    .bind(config.delay_ms as i64)
+    .fetch_one(pool)
+    .await?;
+
+    get_rule(pool, endpoint_id, id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Replace a rule, keeping its position unless a new one is given
+pub async fn update_rule(
+    pool: &SqlitePool,
+    endpoint_id: &str,
+    rule_id: i64,
+    config: &ResponseRuleConfig,
+) -> Result<Option<ResponseRule>, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE response_rules
+        SET position = COALESCE(?, position),
+            name = ?,
+            matchers = ?,
+            status = ?,
+            headers = ?,
+            body = ?,
+            template = ?,
+            delay_ms = ?
+        WHERE id = ? AND endpoint_id = ?
+        "#,
+    )
+    .bind(config.position)
+    .bind(&config.name)
+    .bind(Json(&config.matchers))
+    .bind(config.status)
+    .bind(headers_json(config))
+    .bind(&config.body)
+    .bind(config.template)
+    .bind(config.delay_ms as i64)
+    .bind(rule_id)
+    .bind(endpoint_id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+    get_rule(pool, endpoint_id, rule_id).await
+}
+
+/// Delete one of an endpoint's rules
+pub async fn delete_rule(
+    pool: &SqlitePool,
+    endpoint_id: &str,
+    rule_id: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM response_rules WHERE id = ? AND endpoint_id = ?")
+        .bind(rule_id)
+        .bind(endpoint_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Headers as stored, a JSON object like the endpoint's `response_headers`
+pub fn headers_json(config: &ResponseRuleConfig) -> Option<String> {
+    config
+        .headers
+        .as_ref()
+        .map(|headers| serde_json::Value::Object(headers.clone()).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_pool;
+    use crate::services::endpoint::{create_endpoint, get_endpoint};
+
+    fn config(json: serde_json::Value) -> ResponseRuleConfig {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rule_crud() {
+        let pool = init_pool("sqlite::memory:").await.unwrap();
+        let endpoint = create_endpoint(&pool).await.unwrap();
+
+        let first = create_rule(
+            &pool,
+            &endpoint.id,
+            &config(serde_json::json!({
+                "name": "paid orders",
+                "matchers": [{"type": "json", "path": "order.status", "equals": "paid"}],
+                "status": 202,
+                "headers": {"X-Rule": "paid"}
+            })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.position, 0);
+        assert_eq!(first.status, 202);
+        assert_eq!(first.headers.as_deref(), Some(r#"{"X-Rule":"paid"}"#));
+        assert_eq!(first.matchers.len(), 1);
+
+        let second = create_rule(&pool, &endpoint.id, &config(serde_json::json!({})))
+            .await
+            .unwrap();
+        assert_eq!((second.position, second.status), (1, 200));
+
+        // Moving the second rule first changes the evaluation order
+        let moved = update_rule(
+            &pool,
+            &endpoint.id,
+            second.id,
+            &config(serde_json::json!({"position": -1, "body": "fallback"})),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(moved.body.as_deref(), Some("fallback"));
+        let ids: Vec<i64> = list_rules(&pool, &endpoint.id)
+            .await
+            .unwrap()
+            .iter()
+            .map(|rule| rule.id)
+            .collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+
+        // Endpoints are loaded with their rules
+        let loaded = get_endpoint(&pool, &endpoint.id).await.unwrap().unwrap();
+        assert_eq!(loaded.response_rules.len(), 2);
+
+        // Rules are scoped to their endpoint
+        assert!(!delete_rule(&pool, "other", first.id).await.unwrap());
+        assert!(delete_rule(&pool, &endpoint.id, first.id).await.unwrap());
+        assert!(get_rule(&pool, &endpoint.id, first.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::access::rate_limit::{RateLimiter, THROTTLED_FLUSH_INTERVAL};
+use crate::config::Config;
+use crate::response::sequence::SequenceTracker;
+use crate::services::endpoint_cache::EndpointCache;
+use crate::services::ingest::IngestQueue;
+use crate::websocket::WebSocketManager;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+/// Shared application state passed to every handler
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: SqlitePool,
+    pub ws_manager: Arc<WebSocketManager>,
+    pub config: Arc<Config>,
+    pub ingest: Arc<IngestQueue>,
+    pub endpoints: Arc<EndpointCache>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub sequences: Arc<SequenceTracker>,
+}
+
+impl AppState {
+    /// Create application state from a database pool, WebSocket manager and settings.
+    /// Spawns the ingest writer and the throttled count flush, so this must be called
+    /// inside a Tokio runtime.
+    pub fn new(pool: SqlitePool, ws_manager: Arc<WebSocketManager>, config: Config) -> Self {
+        let endpoints = EndpointCache::new(config.endpoint_cache_size);
+        let ingest = IngestQueue::start(
+            pool.clone(),
+            ws_manager.clone(),
+            config.ingest_queue_size,
+            config.ingest_batch_size,
+            config.backpressure,
+        );
+
+        let rate_limiter = Arc::new(RateLimiter::new(config.global_rate_limit));
+        let flush_pool = pool.clone();
+        let flush_limiter = rate_limiter.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(THROTTLED_FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                flush_limiter.flush_throttled(&flush_pool).await;
+            }
+        });
+
+        Self {
+            pool,
+            ws_manager,
+            config: Arc::new(config),
+            ingest: Arc::new(ingest),
+            endpoints: Arc::new(endpoints),
+            rate_limiter,
+            sequences: Arc::new(SequenceTracker::new()),
+        }
+    }
+}
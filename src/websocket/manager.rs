@@ -1,3 +1,7 @@
+use crate::models::{
+    AuthCheck, DecodedBody, FormPart, JwtCheck, JwtToken, ProtobufBody, QueryPair, RawHeader,
+    SchemaCheck, SignatureCheck, StructuredBody,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -17,19 +21,54 @@ pub enum WebSocketMessage {
 }
 
 /// Request data for new_request messages
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RequestData {
     pub id: i64,
     pub endpoint_id: String,
     pub method: String,
     pub path: String,
+    pub sub_path: String,
     pub query_string: Option<String>,
     pub query_params: serde_json::Value,
+    pub query_pairs: Vec<QueryPair>,
     pub headers: serde_json::Value,
+    pub raw_headers: Vec<RawHeader>,
     pub body: Option<String>,
+    pub decoded: Option<DecodedBody>,
+    pub form_params: Option<serde_json::Value>,
+    pub parts: Option<Vec<FormPart>>,
+    pub protobuf: Option<ProtobufBody>,
+    pub structured: Option<StructuredBody>,
+    pub body_size: i64,
+    pub body_truncated: bool,
+    pub body_spilled: bool,
     pub content_type: Option<String>,
     pub received_at: String,
     pub ip_address: Option<String>,
+    pub peer_address: Option<String>,
+    pub remote_port: Option<i64>,
+    pub local_address: Option<String>,
+    pub local_port: Option<i64>,
+    pub http_version: Option<String>,
+    pub tls: Option<bool>,
+    pub request_size: Option<i64>,
+    pub processing_time_us: Option<i64>,
+    pub provider: Option<String>,
+    pub event_type: Option<String>,
+    pub signature: Option<SignatureCheck>,
+    pub jwts: Vec<JwtToken>,
+    pub jwt: Option<JwtCheck>,
+    pub auth: Option<AuthCheck>,
+    pub blocked: bool,
+    pub schema: Option<SchemaCheck>,
+    pub fingerprint: Option<String>,
+    pub delivery_id: Option<String>,
+    pub duplicate_of: Option<i64>,
+    pub retry: i64,
+    pub response_error: Option<String>,
+    pub rule_id: Option<i64>,
+    pub sequence_position: Option<i64>,
+    pub response_delay_ms: Option<i64>,
 }
 
 /// Client connection handle
@@ -229,6 +268,7 @@ mod tests {
                 content_type: Some("application/json".to_string()),
                 received_at: "2024-01-01T00:00:00Z".to_string(),
                 ip_address: Some("127.0.0.1".to_string()),
+                ..Default::default()
             }),
         };
 
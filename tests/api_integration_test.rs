@@ -1,5 +1,8 @@
 use axum::extract::{Path, Query, State};
-use hookshot::{db, handlers::api, models::RequestQueryParams, websocket::WebSocketManager};
+use hookshot::{
+    config::Config, db, handlers::api, models::RequestQueryParams, state::AppState,
+    websocket::WebSocketManager,
+};
 use sqlx::SqlitePool;
 use std::{sync::Arc, time::Instant};
 
@@ -7,8 +10,8 @@ async fn setup_test_db() -> SqlitePool {
     db::init_pool("sqlite::memory:").await.unwrap()
 }
 
-fn create_test_state(pool: SqlitePool) -> (SqlitePool, Arc<WebSocketManager>) {
-    (pool, Arc::new(WebSocketManager::new()))
+fn create_test_state(pool: SqlitePool) -> AppState {
+    AppState::new(pool, Arc::new(WebSocketManager::new()), Config::default())
 }
 
 async fn create_test_endpoint(pool: &SqlitePool) -> String {
@@ -61,6 +64,7 @@ async fn test_pagination_with_150_requests() {
         page: 1,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -79,6 +83,7 @@ async fn test_pagination_with_150_requests() {
         page: 2,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -97,6 +102,7 @@ async fn test_pagination_with_150_requests() {
         page: 3,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -115,6 +121,7 @@ async fn test_pagination_with_150_requests() {
         page: 4,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id),
@@ -153,6 +160,7 @@ async fn test_method_filtering_with_mixed_methods() {
         page: 1,
         limit: 50,
         method: Some("POST".to_string()),
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -171,6 +179,7 @@ async fn test_method_filtering_with_mixed_methods() {
         page: 1,
         limit: 50,
         method: Some("POST,PUT,DELETE".to_string()),
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -188,6 +197,7 @@ async fn test_method_filtering_with_mixed_methods() {
         page: 1,
         limit: 50,
         method: Some("PATCH".to_string()),
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id),
@@ -339,6 +349,7 @@ async fn test_performance_query_10k_requests() {
         page: 1,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -367,6 +378,7 @@ async fn test_performance_query_10k_requests() {
         page: 1,
         limit: 50,
         method: Some("POST".to_string()),
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -396,6 +408,7 @@ async fn test_performance_query_10k_requests() {
         page: 50,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id),
@@ -429,6 +442,7 @@ async fn test_invalid_endpoint_returns_404() {
         page: 1,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path("nonexistent-endpoint-id".to_string()),
@@ -456,6 +470,7 @@ async fn test_pagination_limits() {
         page: 1,
         limit: 200,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -472,6 +487,7 @@ async fn test_pagination_limits() {
         page: 0,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id),
@@ -483,3 +499,76 @@ async fn test_pagination_limits() {
     let response = result.unwrap().0;
     assert_eq!(response.page, 1); // Should default to 1
 }
+
+#[tokio::test]
+async fn test_path_prefix_filtering() {
+    let pool = setup_test_db().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    for sub_path in [
+        "",
+        "/github/push",
+        "/github/issues",
+        "/GitHub/push",
+        "/stripe",
+    ] {
+        sqlx::query(
+            "INSERT INTO requests (endpoint_id, method, path, headers) VALUES (?, 'POST', ?, '{}')",
+        )
+        .bind(&endpoint_id)
+        .bind(format!("/webhook/{}{}", endpoint_id, sub_path))
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let params = RequestQueryParams {
+        path_prefix: Some("/github".to_string()),
+        ..Default::default()
+    };
+    let response = api::get_endpoint_requests(
+        Path(endpoint_id.clone()),
+        Query(params),
+        State(create_test_state(pool.clone())),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(response.total, 2);
+    assert!(response
+        .requests
+        .iter()
+        .all(|r| r.sub_path.starts_with("/github/")));
+
+    // Prefix without a leading slash is normalized
+    let params = RequestQueryParams {
+        path_prefix: Some("stripe".to_string()),
+        ..Default::default()
+    };
+    let response = api::get_endpoint_requests(
+        Path(endpoint_id.clone()),
+        Query(params),
+        State(create_test_state(pool.clone())),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(response.total, 1);
+    assert_eq!(response.requests[0].sub_path, "/stripe");
+
+    // "/" matches everything, including the bare endpoint URL
+    let params = RequestQueryParams {
+        path_prefix: Some("/".to_string()),
+        ..Default::default()
+    };
+    let response = api::get_endpoint_requests(
+        Path(endpoint_id),
+        Query(params),
+        State(create_test_state(pool)),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(response.total, 5);
+    assert!(response.requests.iter().any(|r| r.sub_path == "/"));
+}
@@ -3,21 +3,29 @@ use axum::{
     Json,
 };
 use hookshot::{
+    access::auth::{AuthType, KeyLocation},
+    analysis::{jwt::KeyType, signature::Scheme},
+    config::Config,
     db,
     handlers::api,
     handlers::endpoint,
-    models::{RequestQueryParams, UpdateResponseConfig},
+    handlers::rule,
+    models::{
+        RequestQueryParams, ResetSequenceParams, ResponseRuleConfig, UpdateAuthConfig,
+        UpdateFingerprintConfig, UpdateIpFilterConfig, UpdateJwtConfig, UpdateProtobufConfig,
+        UpdateRateLimitConfig, UpdateResponseConfig, UpdateSchemaConfig, UpdateSignatureConfig,
+    },
+    response::{latency::ResponseLatency, sequence::ResponseSequence},
+    state::AppState,
     websocket::WebSocketManager,
 };
 use sqlx::SqlitePool;
 use std::sync::Arc;
 
-type AppState = (SqlitePool, Arc<WebSocketManager>);
-
 async fn setup() -> AppState {
     let pool = db::init_pool("sqlite::memory:").await.unwrap();
     let ws_manager = Arc::new(WebSocketManager::new());
-    (pool, ws_manager)
+    AppState::new(pool, ws_manager, Config::default())
 }
 
 async fn create_endpoint(state: &AppState) -> String {
@@ -76,7 +84,7 @@ async fn test_get_endpoint_requests_pagination() {
 
     // Create 75 requests
     for _ in 0..75 {
-        create_request(&state.0, &endpoint_id, "POST").await;
+        create_request(&state.pool, &endpoint_id, "POST").await;
     }
 
     // Page 1
@@ -84,6 +92,7 @@ async fn test_get_endpoint_requests_pagination() {
         page: 1,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -103,6 +112,7 @@ async fn test_get_endpoint_requests_pagination() {
         page: 2,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(Path(endpoint_id), Query(params), State(state))
         .await
@@ -120,10 +130,10 @@ async fn test_get_endpoint_requests_method_filter() {
 
     // Create mixed requests
     for _ in 0..10 {
-        create_request(&state.0, &endpoint_id, "POST").await;
+        create_request(&state.pool, &endpoint_id, "POST").await;
     }
     for _ in 0..5 {
-        create_request(&state.0, &endpoint_id, "GET").await;
+        create_request(&state.pool, &endpoint_id, "GET").await;
     }
 
     // Filter by POST
@@ -131,6 +141,7 @@ async fn test_get_endpoint_requests_method_filter() {
         page: 1,
         limit: 50,
         method: Some("POST".to_string()),
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -149,6 +160,7 @@ async fn test_get_endpoint_requests_method_filter() {
         page: 1,
         limit: 50,
         method: Some("POST,GET".to_string()),
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(Path(endpoint_id), Query(params), State(state))
         .await
@@ -162,7 +174,7 @@ async fn test_get_endpoint_requests_method_filter() {
 async fn test_get_request_by_id() {
     let state = setup().await;
     let endpoint_id = create_endpoint(&state).await;
-    let request_id = create_request(&state.0, &endpoint_id, "POST").await;
+    let request_id = create_request(&state.pool, &endpoint_id, "POST").await;
 
     let result = api::get_request_by_id(Path(request_id), State(state))
         .await
@@ -187,7 +199,7 @@ async fn test_delete_endpoint() {
 
     // Create requests
     for _ in 0..5 {
-        create_request(&state.0, &endpoint_id, "POST").await;
+        create_request(&state.pool, &endpoint_id, "POST").await;
     }
 
     // Delete endpoint
@@ -197,7 +209,7 @@ async fn test_delete_endpoint() {
     // Verify cascade delete
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM requests WHERE endpoint_id = ?")
         .bind(&endpoint_id)
-        .fetch_one(&state.0)
+        .fetch_one(&state.pool)
         .await
         .unwrap();
     assert_eq!(count, 0);
@@ -220,6 +232,7 @@ async fn test_update_endpoint_response() {
         status: 404,
         headers: Some(r#"{"x-custom":"value"}"#.to_string()),
         body: Some(r#"{"error":"Not found"}"#.to_string()),
+        template: false,
     };
 
     let result = api::update_endpoint_response(
@@ -254,6 +267,7 @@ async fn test_update_endpoint_response_invalid_status() {
             status,
             headers: None,
             body: None,
+            template: false,
         };
         let result = api::update_endpoint_response(
             Path(endpoint_id.clone()),
@@ -272,6 +286,7 @@ async fn test_update_endpoint_response_invalid_status() {
             status,
             headers: None,
             body: None,
+            template: false,
         };
         let result = api::update_endpoint_response(
             Path(endpoint_id.clone()),
@@ -293,6 +308,7 @@ async fn test_update_endpoint_response_invalid_json() {
         status: 200,
         headers: Some("not valid json".to_string()),
         body: None,
+        template: false,
     };
 
     let result = api::update_endpoint_response(Path(endpoint_id), State(state), Json(config)).await;
@@ -302,6 +318,41 @@ async fn test_update_endpoint_response_invalid_json() {
     assert!(msg.contains("valid JSON"));
 }
 
+#[tokio::test]
+async fn test_update_endpoint_response_template() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config = |body: &str| UpdateResponseConfig {
+        enabled: true,
+        status: 200,
+        headers: Some(r#"{"X-Request-Method": "{{ request.method }}"}"#.to_string()),
+        body: Some(body.to_string()),
+        template: true,
+    };
+    let Json(endpoint) = api::update_endpoint_response(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config(r#"{"id": "{{ uuid() }}"}"#)),
+    )
+    .await
+    .unwrap();
+    assert!(endpoint.response_template);
+    assert!(endpoint.compiled_response().is_some());
+
+    for body in ["{{ request.method", "{{ request.body | shout }}"] {
+        let (status, msg) = api::update_endpoint_response(
+            Path(endpoint_id.clone()),
+            State(state.clone()),
+            Json(config(body)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert!(!msg.is_empty());
+    }
+}
+
 #[tokio::test]
 async fn test_update_endpoint_response_not_found() {
     let state = setup().await;
@@ -310,6 +361,7 @@ async fn test_update_endpoint_response_not_found() {
         status: 200,
         headers: None,
         body: None,
+        template: false,
     };
     let result =
         api::update_endpoint_response(Path("nonexistent".to_string()), State(state), Json(config))
@@ -317,13 +369,788 @@ async fn test_update_endpoint_response_not_found() {
     assert_eq!(result.unwrap_err().0, axum::http::StatusCode::NOT_FOUND);
 }
 
+/// Base64 descriptor set for `package acme; message Event { string name = 1; }`
+fn event_descriptor_set() -> String {
+    use base64::{engine::general_purpose, Engine as _};
+    use prost_reflect::prost::Message;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+
+    let set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("acme.proto".to_string()),
+            package: Some("acme".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Event".to_string()),
+                field: vec![FieldDescriptorProto {
+                    name: Some("name".to_string()),
+                    number: Some(1),
+                    label: Some(Label::Optional as i32),
+                    r#type: Some(Type::String as i32),
+                    json_name: Some("name".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+    general_purpose::STANDARD.encode(set.encode_to_vec())
+}
+
+#[tokio::test]
+async fn test_update_endpoint_protobuf() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config = UpdateProtobufConfig {
+        descriptor_set: Some(event_descriptor_set()),
+        message_type: Some("acme.Event".to_string()),
+        type_header: None,
+    };
+    let Json(endpoint) = api::update_endpoint_protobuf(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        endpoint.protobuf_message_type.as_deref(),
+        Some("acme.Event")
+    );
+
+    // acme.Event { name: "deploy" }
+    let request_id = sqlx::query(
+        "INSERT INTO requests (endpoint_id, method, path, headers, body, content_type)
+         VALUES (?, 'POST', '/test', '{}', ?, 'application/x-protobuf')",
+    )
+    .bind(&endpoint_id)
+    .bind(b"\x0a\x06deploy".to_vec())
+    .execute(&state.pool)
+    .await
+    .unwrap()
+    .last_insert_rowid();
+
+    let Json(response) = api::get_request_by_id(Path(request_id), State(state.clone()))
+        .await
+        .unwrap();
+    let protobuf = response.protobuf.expect("body should be decoded");
+    assert_eq!(protobuf.message_type.as_deref(), Some("acme.Event"));
+    assert_eq!(protobuf.json, Some(serde_json::json!({"name": "deploy"})));
+    assert!(protobuf.error.is_none());
+
+    // Removing the descriptor set stops decoding
+    let Json(endpoint) = api::update_endpoint_protobuf(
+        Path(endpoint_id),
+        State(state.clone()),
+        Json(UpdateProtobufConfig::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.protobuf_message_type, None);
+
+    let Json(response) = api::get_request_by_id(Path(request_id), State(state))
+        .await
+        .unwrap();
+    assert!(response.protobuf.is_none());
+}
+
+#[tokio::test]
+async fn test_update_endpoint_protobuf_invalid() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    for (descriptor_set, message_type) in [
+        ("not base64!".to_string(), Some("acme.Event")),
+        ("AAAA".to_string(), Some("acme.Event")),
+        (event_descriptor_set(), Some("acme.Missing")),
+        (event_descriptor_set(), None),
+    ] {
+        let config = UpdateProtobufConfig {
+            descriptor_set: Some(descriptor_set),
+            message_type: message_type.map(str::to_string),
+            type_header: None,
+        };
+        let result = api::update_endpoint_protobuf(
+            Path(endpoint_id.clone()),
+            State(state.clone()),
+            Json(config),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn test_update_endpoint_signature() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config = UpdateSignatureConfig {
+        scheme: Some(Scheme::HmacSha256),
+        secrets: vec!["old".to_string(), "new".to_string()],
+        header: Some("X-Signature".to_string()),
+        tolerance_secs: None,
+        reject: true,
+    };
+    let Json(endpoint) = api::update_endpoint_signature(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.signature_scheme.as_deref(), Some("hmac-sha256"));
+    assert_eq!(endpoint.signature_header.as_deref(), Some("x-signature"));
+    assert!(endpoint.signature_reject);
+
+    // Secrets are stored but never returned
+    let json = serde_json::to_value(&endpoint).unwrap();
+    assert!(json.get("signature_secrets").is_none());
+
+    // Turning verification off clears the configuration
+    let Json(endpoint) = api::update_endpoint_signature(
+        Path(endpoint_id),
+        State(state),
+        Json(UpdateSignatureConfig::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.signature_scheme, None);
+    assert_eq!(endpoint.signature_secrets, None);
+    assert!(!endpoint.signature_reject);
+}
+
+#[tokio::test]
+async fn test_update_endpoint_signature_invalid() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    for config in [
+        UpdateSignatureConfig {
+            scheme: Some(Scheme::Github),
+            ..Default::default()
+        },
+        UpdateSignatureConfig {
+            scheme: Some(Scheme::HmacSha256),
+            secrets: vec!["secret".to_string()],
+            ..Default::default()
+        },
+        UpdateSignatureConfig {
+            scheme: Some(Scheme::Stripe),
+            secrets: vec!["secret".to_string()],
+            tolerance_secs: Some(0),
+            ..Default::default()
+        },
+    ] {
+        let result = api::update_endpoint_signature(
+            Path(endpoint_id.clone()),
+            State(state.clone()),
+            Json(config),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn test_update_endpoint_jwt() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config = UpdateJwtConfig {
+        key_type: Some(KeyType::Secret),
+        key: Some("jwt-secret".to_string()),
+        header: Some("X-Auth-Token".to_string()),
+        issuer: Some("https://issuer.example".to_string()),
+        ..Default::default()
+    };
+    let Json(endpoint) = api::update_endpoint_jwt(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.jwt_key_type.as_deref(), Some("secret"));
+    assert_eq!(endpoint.jwt_header.as_deref(), Some("x-auth-token"));
+    let json = serde_json::to_value(&endpoint).unwrap();
+    assert!(json.get("jwt_key").is_none());
+
+    for config in [
+        UpdateJwtConfig {
+            key_type: Some(KeyType::Pem),
+            key: Some("not a key".to_string()),
+            ..Default::default()
+        },
+        UpdateJwtConfig {
+            key_type: Some(KeyType::Jwks),
+            key: Some("[]".to_string()),
+            ..Default::default()
+        },
+        UpdateJwtConfig {
+            key_type: Some(KeyType::Secret),
+            ..Default::default()
+        },
+    ] {
+        let result = api::update_endpoint_jwt(
+            Path(endpoint_id.clone()),
+            State(state.clone()),
+            Json(config),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    let Json(endpoint) = api::update_endpoint_jwt(
+        Path(endpoint_id),
+        State(state),
+        Json(UpdateJwtConfig::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.jwt_key_type, None);
+    assert_eq!(endpoint.jwt_key, None);
+}
+
+#[tokio::test]
+async fn test_update_endpoint_auth() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config = UpdateAuthConfig {
+        auth_type: Some(AuthType::ApiKey),
+        username: Some("ignored".to_string()),
+        secret: Some("key-123".to_string()),
+        key_name: Some("api_key".to_string()),
+        key_location: Some(KeyLocation::Query),
+    };
+    let Json(endpoint) = api::update_endpoint_auth(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.auth_type.as_deref(), Some("api_key"));
+    assert_eq!(endpoint.auth_username, None);
+    assert_eq!(endpoint.auth_key_name.as_deref(), Some("api_key"));
+    assert_eq!(endpoint.auth_key_location.as_deref(), Some("query"));
+    let json = serde_json::to_value(&endpoint).unwrap();
+    assert!(json.get("auth_secret").is_none());
+
+    for config in [
+        UpdateAuthConfig {
+            auth_type: Some(AuthType::Bearer),
+            ..Default::default()
+        },
+        UpdateAuthConfig {
+            auth_type: Some(AuthType::Basic),
+            secret: Some("pw".to_string()),
+            ..Default::default()
+        },
+        UpdateAuthConfig {
+            auth_type: Some(AuthType::ApiKey),
+            secret: Some("key".to_string()),
+            ..Default::default()
+        },
+    ] {
+        let result = api::update_endpoint_auth(
+            Path(endpoint_id.clone()),
+            State(state.clone()),
+            Json(config),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    let Json(endpoint) = api::update_endpoint_auth(
+        Path(endpoint_id),
+        State(state),
+        Json(UpdateAuthConfig::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.auth_type, None);
+    assert_eq!(endpoint.auth_secret, None);
+
+    // A misspelt field is refused rather than read as turning auth off
+    let misspelt = serde_json::json!({"type": "bearer", "secret": "tok"});
+    assert!(serde_json::from_value::<UpdateAuthConfig>(misspelt).is_err());
+}
+
+#[tokio::test]
+async fn test_update_endpoint_ip_filter() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config = UpdateIpFilterConfig {
+        allow: vec!["10.0.0.0/8".to_string(), " 192.168.1.10 ".to_string()],
+        deny: vec!["10.0.0.5".to_string()],
+        block_status: Some(404),
+        record_blocked: true,
+    };
+    let Json(endpoint) = api::update_endpoint_ip_filter(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        endpoint.ip_allow.as_deref(),
+        Some(r#"["10.0.0.0/8","192.168.1.10/32"]"#)
+    );
+    assert_eq!(endpoint.ip_deny.as_deref(), Some(r#"["10.0.0.5/32"]"#));
+    assert_eq!(endpoint.ip_block_status, Some(404));
+    assert!(endpoint.ip_block_record);
+    let filter = endpoint.ip_filter().unwrap();
+    assert!(std::ptr::eq(filter, endpoint.ip_filter().unwrap()));
+    assert!(!filter.permits("10.0.0.5".parse().unwrap()));
+
+    for config in [
+        UpdateIpFilterConfig {
+            allow: vec!["10.0.0.0/33".to_string()],
+            ..Default::default()
+        },
+        UpdateIpFilterConfig {
+            block_status: Some(200),
+            ..Default::default()
+        },
+    ] {
+        let result = api::update_endpoint_ip_filter(
+            Path(endpoint_id.clone()),
+            State(state.clone()),
+            Json(config),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    let Json(endpoint) = api::update_endpoint_ip_filter(
+        Path(endpoint_id),
+        State(state),
+        Json(UpdateIpFilterConfig::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.ip_allow, None);
+    assert!(endpoint.ip_filter().is_none());
+}
+
+#[tokio::test]
+async fn test_update_endpoint_rate_limit() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config: UpdateRateLimitConfig =
+        serde_json::from_value(serde_json::json!({"limit": "600/m:50", "client_limit": "10/s"}))
+            .unwrap();
+    let Json(endpoint) = api::update_endpoint_rate_limit(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.rate_limit.as_deref(), Some("600/m:50"));
+    assert_eq!(endpoint.client_rate_limit.as_deref(), Some("10/s"));
+    assert_eq!(endpoint.throttled_count, 0);
+
+    assert!(
+        serde_json::from_value::<UpdateRateLimitConfig>(serde_json::json!({"limit": "10/d"}))
+            .is_err()
+    );
+
+    let Json(endpoint) = api::update_endpoint_rate_limit(
+        Path(endpoint_id),
+        State(state),
+        Json(UpdateRateLimitConfig::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.rate_limit, None);
+    assert_eq!(endpoint.client_rate_limit, None);
+}
+
+#[tokio::test]
+async fn test_update_endpoint_fingerprint() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config = UpdateFingerprintConfig {
+        headers: vec!["X-Event-Type".to_string(), " x-topic ".to_string()],
+    };
+    let Json(endpoint) = api::update_endpoint_fingerprint(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        endpoint.fingerprint_header_names(),
+        vec!["x-event-type", "x-topic"]
+    );
+
+    let result = api::update_endpoint_fingerprint(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(UpdateFingerprintConfig {
+            headers: vec!["bad header".to_string()],
+        }),
+    )
+    .await;
+    assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+
+    let Json(endpoint) = api::update_endpoint_fingerprint(
+        Path(endpoint_id),
+        State(state),
+        Json(UpdateFingerprintConfig::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.fingerprint_headers, None);
+}
+
+#[tokio::test]
+async fn test_update_endpoint_latency() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+    let latency =
+        |json: serde_json::Value| -> ResponseLatency { serde_json::from_value(json).unwrap() };
+
+    let Json(endpoint) = api::update_endpoint_latency(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(latency(serde_json::json!({
+            "delay": {"type": "normal", "mean_ms": 500, "std_dev_ms": 100},
+            "drip": {"duration_ms": 2000}
+        }))),
+    )
+    .await
+    .unwrap();
+    let configured = endpoint.latency().unwrap();
+    // Parsed once and kept with the endpoint
+    assert!(std::ptr::eq(configured, endpoint.latency().unwrap()));
+    assert!(configured.delay.is_some());
+    assert_eq!(configured.drip.unwrap().chunks, 10);
+
+    let result = api::update_endpoint_latency(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(latency(
+            serde_json::json!({"delay": {"type": "uniform", "min_ms": 10, "max_ms": 5}}),
+        )),
+    )
+    .await;
+    assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+
+    let result = api::update_endpoint_latency(
+        Path("nonexistent".to_string()),
+        State(state.clone()),
+        Json(ResponseLatency::default()),
+    )
+    .await;
+    assert_eq!(result.unwrap_err().0, axum::http::StatusCode::NOT_FOUND);
+
+    let Json(endpoint) = api::update_endpoint_latency(
+        Path(endpoint_id),
+        State(state),
+        Json(ResponseLatency::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.response_latency, None);
+}
+
+#[tokio::test]
+async fn test_update_endpoint_sequence() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+    let sequence =
+        |json: serde_json::Value| -> ResponseSequence { serde_json::from_value(json).unwrap() };
+
+    let Json(endpoint) = api::update_endpoint_sequence(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(sequence(serde_json::json!({
+            "steps": [{"status": 500}, {"status": 200, "body": "ok"}],
+            "mode": "cycle"
+        }))),
+    )
+    .await
+    .unwrap();
+    assert!(endpoint.sequence().is_some());
+
+    // Saving a sequence starts it over
+    state.sequences.advance(&endpoint_id, None);
+    let Json(endpoint) = api::update_endpoint_sequence(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(sequence(serde_json::json!({"steps": [{}]}))),
+    )
+    .await
+    .unwrap();
+    assert!(endpoint.sequence().is_some());
+    assert_eq!(state.sequences.advance(&endpoint_id, None), 0);
+
+    let result = api::update_endpoint_sequence(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(sequence(serde_json::json!({"steps": [{"status": 1000}]}))),
+    )
+    .await;
+    assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+
+    let status = api::reset_endpoint_sequence(
+        Path(endpoint_id.clone()),
+        Query(ResetSequenceParams::default()),
+        State(state.clone()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+    assert_eq!(state.sequences.advance(&endpoint_id, None), 0);
+
+    let result = api::reset_endpoint_sequence(
+        Path("nonexistent".to_string()),
+        Query(ResetSequenceParams::default()),
+        State(state.clone()),
+    )
+    .await;
+    assert_eq!(result.unwrap_err().0, axum::http::StatusCode::NOT_FOUND);
+
+    let Json(endpoint) = api::update_endpoint_sequence(
+        Path(endpoint_id),
+        State(state),
+        Json(ResponseSequence::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.response_sequence, None);
+}
+
+#[tokio::test]
+async fn test_hide_duplicates_filter() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let first = create_request(&state.pool, &endpoint_id, "POST").await;
+    sqlx::query(
+        "INSERT INTO requests (endpoint_id, method, path, headers, duplicate_of, retry)
+         VALUES (?, 'POST', '/test', '{}', ?, 1)",
+    )
+    .bind(&endpoint_id)
+    .bind(first)
+    .execute(&state.pool)
+    .await
+    .unwrap();
+
+    let list = |hide_duplicates: Option<bool>| {
+        api::get_endpoint_requests(
+            Path(endpoint_id.clone()),
+            Query(RequestQueryParams {
+                hide_duplicates,
+                ..Default::default()
+            }),
+            State(state.clone()),
+        )
+    };
+
+    let result = list(None).await.unwrap().0;
+    assert_eq!(result.total, 2);
+    let retry = result
+        .requests
+        .iter()
+        .find(|request| request.id != first)
+        .unwrap();
+    assert_eq!((retry.duplicate_of, retry.retry), (Some(first), 1));
+
+    let result = list(Some(true)).await.unwrap().0;
+    assert_eq!(result.total, 1);
+    assert_eq!(result.requests[0].id, first);
+}
+
+#[tokio::test]
+async fn test_response_rule_routes() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+    let config =
+        |json: serde_json::Value| -> ResponseRuleConfig { serde_json::from_value(json).unwrap() };
+
+    let Json(created) = rule::create_rule(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config(serde_json::json!({
+            "name": "pushes",
+            "matchers": [{"type": "header", "name": "x-github-event", "equals": "push"}],
+            "status": 201,
+            "body": "{{ request.method }}",
+            "template": true
+        }))),
+    )
+    .await
+    .unwrap();
+    assert_eq!(created.name.as_deref(), Some("pushes"));
+
+    // The endpoint is reloaded with the new rule
+    let endpoint = state
+        .endpoints
+        .get(&state.pool, &endpoint_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!endpoint.rule_set().is_empty());
+
+    for invalid in [
+        serde_json::json!({"status": 42}),
+        serde_json::json!({"delay_ms": 600000}),
+        serde_json::json!({"matchers": [{"type": "path", "glob": "[a"}]}),
+        serde_json::json!({"body": "{{ nope() }}", "template": true}),
+        serde_json::json!({"headers": {"X-Count": 1}}),
+    ] {
+        let result = rule::create_rule(
+            Path(endpoint_id.clone()),
+            State(state.clone()),
+            Json(config(invalid.clone())),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().0,
+            axum::http::StatusCode::BAD_REQUEST,
+            "{}",
+            invalid
+        );
+    }
+
+    let Json(updated) = rule::update_rule(
+        Path((endpoint_id.clone(), created.id)),
+        State(state.clone()),
+        Json(config(serde_json::json!({"status": 204}))),
+    )
+    .await
+    .unwrap();
+    assert_eq!((updated.status, updated.position), (204, created.position));
+    assert!(updated.matchers.is_empty());
+
+    let Json(rules) = rule::list_rules(Path(endpoint_id.clone()), State(state.clone()))
+        .await
+        .unwrap();
+    assert_eq!(rules.len(), 1);
+
+    let status = rule::delete_rule(
+        Path((endpoint_id.clone(), created.id)),
+        State(state.clone()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+    let result = rule::delete_rule(Path((endpoint_id, created.id)), State(state.clone())).await;
+    assert_eq!(result.unwrap_err().0, axum::http::StatusCode::NOT_FOUND);
+
+    let result = rule::create_rule(
+        Path("nonexistent".to_string()),
+        State(state),
+        Json(config(serde_json::json!({}))),
+    )
+    .await;
+    assert_eq!(result.unwrap_err().0, axum::http::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_update_endpoint_schema() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    let config = UpdateSchemaConfig {
+        schema: Some(serde_json::json!({"type": "object"})),
+        reject: true,
+    };
+    let Json(endpoint) = api::update_endpoint_schema(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(config),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        endpoint.json_schema.as_deref(),
+        Some(r#"{"type":"object"}"#)
+    );
+    assert!(endpoint.schema_reject);
+    assert!(endpoint.schema_validator().is_some());
+
+    let result = api::update_endpoint_schema(
+        Path(endpoint_id.clone()),
+        State(state.clone()),
+        Json(UpdateSchemaConfig {
+            schema: Some(serde_json::json!({"type": 5})),
+            reject: false,
+        }),
+    )
+    .await;
+    assert_eq!(result.unwrap_err().0, axum::http::StatusCode::BAD_REQUEST);
+
+    let Json(endpoint) = api::update_endpoint_schema(
+        Path(endpoint_id),
+        State(state),
+        Json(UpdateSchemaConfig::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(endpoint.json_schema, None);
+    assert!(!endpoint.schema_reject);
+}
+
+#[tokio::test]
+async fn test_request_jwts_decoded() {
+    let state = setup().await;
+    let endpoint_id = create_endpoint(&state).await;
+
+    // {"alg":"HS256","typ":"JWT"}.{"sub":"svc","aud":"hookshot"}
+    let token = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+                 eyJzdWIiOiJzdmMiLCJhdWQiOiJob29rc2hvdCJ9.c2lnbmF0dXJl";
+    let headers = serde_json::json!({"authorization": format!("Bearer {}", token)});
+    let request_id = sqlx::query(
+        "INSERT INTO requests (endpoint_id, method, path, headers, jwt_status, jwt_error)
+         VALUES (?, 'POST', '/test', ?, 'invalid', 'signature does not match')",
+    )
+    .bind(&endpoint_id)
+    .bind(headers.to_string())
+    .execute(&state.pool)
+    .await
+    .unwrap()
+    .last_insert_rowid();
+
+    let Json(response) = api::get_request_by_id(Path(request_id), State(state))
+        .await
+        .unwrap();
+    assert_eq!(response.jwts.len(), 1);
+    assert_eq!(response.jwts[0].header_name, "authorization");
+    assert_eq!(response.jwts[0].header["alg"], "HS256");
+    assert_eq!(
+        response.jwts[0].claims,
+        serde_json::json!({"sub": "svc", "aud": "hookshot"})
+    );
+    let jwt = response
+        .jwt
+        .expect("verification result should be returned");
+    assert_eq!(jwt.status, "invalid");
+    assert_eq!(jwt.error.as_deref(), Some("signature does not match"));
+}
+
 #[tokio::test]
 async fn test_pagination_limits() {
     let state = setup().await;
     let endpoint_id = create_endpoint(&state).await;
 
     for _ in 0..10 {
-        create_request(&state.0, &endpoint_id, "POST").await;
+        create_request(&state.pool, &endpoint_id, "POST").await;
     }
 
     // Test limit > 100 should be capped
@@ -331,6 +1158,7 @@ async fn test_pagination_limits() {
         page: 1,
         limit: 200,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(
         Path(endpoint_id.clone()),
@@ -347,6 +1175,7 @@ async fn test_pagination_limits() {
         page: 0,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let result = api::get_endpoint_requests(Path(endpoint_id), Query(params), State(state))
         .await
@@ -359,7 +1188,7 @@ async fn test_pagination_limits() {
 async fn test_json_response_format() {
     let state = setup().await;
     let endpoint_id = create_endpoint(&state).await;
-    create_request(&state.0, &endpoint_id, "POST").await;
+    create_request(&state.pool, &endpoint_id, "POST").await;
 
     // Test list response
     let list_result = endpoint::list_endpoints(State(state.clone()))
@@ -374,6 +1203,7 @@ async fn test_json_response_format() {
         page: 1,
         limit: 50,
         method: None,
+        ..Default::default()
     };
     let requests_result =
         api::get_endpoint_requests(Path(endpoint_id), Query(params), State(state))
@@ -3,7 +3,7 @@ use axum::{
     extract::connect_info::MockConnectInfo,
     http::{Method, Request, StatusCode},
 };
-use hookshot::{db, handlers, websocket::WebSocketManager};
+use hookshot::{config::Config, db, handlers, state::AppState, websocket::WebSocketManager};
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use tower::util::ServiceExt;
@@ -17,8 +17,8 @@ async fn create_test_pool() -> SqlitePool {
 }
 
 /// Helper to create test state
-fn create_test_state(pool: SqlitePool) -> (SqlitePool, Arc<WebSocketManager>) {
-    (pool, Arc::new(WebSocketManager::new()))
+fn create_test_state(pool: SqlitePool) -> AppState {
+    AppState::new(pool, Arc::new(WebSocketManager::new()), Config::default())
 }
 
 /// Helper to create a test endpoint
@@ -142,15 +142,19 @@ async fn test_webhook_get_request() {
 }
 
 #[tokio::test]
-async fn test_webhook_invalid_endpoint() {
+async fn test_webhook_structured_query_params() {
     let pool = create_test_pool().await;
-    let invalid_id = uuid::Uuid::new_v4().to_string();
+    let endpoint_id = create_test_endpoint(&pool).await;
 
     let app = axum::Router::new()
         .route(
             "/webhook/{id}",
             axum::routing::any(handlers::webhook::webhook_handler),
         )
+        .route(
+            "/api/requests/{id}",
+            axum::routing::get(handlers::api::get_request_by_id),
+        )
         .with_state(create_test_state(pool.clone()))
         .layer(MockConnectInfo(std::net::SocketAddr::from((
             [127, 0, 0, 1],
@@ -158,21 +162,53 @@ async fn test_webhook_invalid_endpoint() {
         ))));
 
     let request = Request::builder()
-        .method(Method::POST)
-        .uri(format!("/webhook/{}", invalid_id))
+        .method(Method::GET)
+        .uri(format!(
+            "/webhook/{}?tag=a&filter%5Bstatus%5D=open&tag=b",
+            endpoint_id
+        ))
         .body(Body::empty())
         .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
-    let response = app
-        .oneshot(request)
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let (id,): (i64,) = sqlx::query_as("SELECT id FROM requests WHERE endpoint_id = ?")
+        .bind(&endpoint_id)
+        .fetch_one(&pool)
         .await
-        .expect("Failed to execute request");
+        .expect("Failed to fetch captured request");
 
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let request = Request::builder()
+        .uri(format!("/api/requests/{}", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+    assert_eq!(
+        json["query_params"],
+        serde_json::json!({"tag": ["a", "b"], "filter": {"status": "open"}})
+    );
+    assert_eq!(
+        json["query_pairs"],
+        serde_json::json!([
+            {"name": "tag", "value": "a"},
+            {"name": "filter[status]", "value": "open"},
+            {"name": "tag", "value": "b"}
+        ])
+    );
 }
 
 #[tokio::test]
-async fn test_webhook_payload_too_large() {
+async fn test_webhook_provider_detection() {
+    use std::io::Write;
+
     let pool = create_test_pool().await;
     let endpoint_id = create_test_endpoint(&pool).await;
 
@@ -187,87 +223,140 @@ async fn test_webhook_payload_too_large() {
             8080,
         ))));
 
-    // Create 11MB payload
-    let large_payload = "x".repeat(11 * 1024 * 1024);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .header("content-type", "application/json")
+        .header("stripe-signature", "t=1700000000,v1=abc")
+        .body(Body::from(
+            r#"{"id":"evt_1","type":"checkout.session.completed"}"#,
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
+    // Fields are read from the body once its Content-Encoding is removed
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(br#"{"id":"evt_2","type":"invoice.paid"}"#)
+        .unwrap();
     let request = Request::builder()
         .method(Method::POST)
         .uri(format!("/webhook/{}", endpoint_id))
-        .body(Body::from(large_payload))
+        .header("content-type", "application/json")
+        .header("content-encoding", "gzip")
+        .header("stripe-signature", "t=1700000000,v1=abc")
+        .body(Body::from(encoder.finish().unwrap()))
         .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
-    let response = app
-        .oneshot(request)
-        .await
-        .expect("Failed to execute request");
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let results: Vec<(Option<String>, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT provider, event_type, delivery_id FROM requests WHERE endpoint_id = ? ORDER BY id",
+    )
+    .bind(&endpoint_id)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to fetch captured requests");
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0.as_deref(), Some("stripe"));
+    assert_eq!(results[0].1.as_deref(), Some("checkout.session.completed"));
+    assert_eq!(results[1].0.as_deref(), Some("stripe"));
+    assert_eq!(results[1].1.as_deref(), Some("invoice.paid"));
+    assert_eq!(results[1].2.as_deref(), Some("evt_2"));
 }
 
 #[tokio::test]
-async fn test_webhook_all_http_methods() {
+async fn test_webhook_signature_verification() {
+    use hmac::{Hmac, Mac};
+
     let pool = create_test_pool().await;
     let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query(
+        "UPDATE endpoints SET signature_scheme = 'github', signature_secrets = '[\"s3cret\"]',
+         signature_reject = TRUE WHERE id = ?",
+    )
+    .bind(&endpoint_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to configure signature verification");
 
-    let methods = vec![
-        Method::GET,
-        Method::POST,
-        Method::PUT,
-        Method::PATCH,
-        Method::DELETE,
-        Method::HEAD,
-        Method::OPTIONS,
-    ];
-
-    for method in methods {
-        let app = axum::Router::new()
-            .route(
-                "/webhook/{id}",
-                axum::routing::any(handlers::webhook::webhook_handler),
-            )
-            .with_state(create_test_state(pool.clone()))
-            .layer(MockConnectInfo(std::net::SocketAddr::from((
-                [127, 0, 0, 1],
-                8080,
-            ))));
-
-        let request = Request::builder()
-            .method(method.clone())
-            .uri(format!("/webhook/{}", endpoint_id))
-            .body(Body::empty())
-            .unwrap();
-
-        let response = app
-            .oneshot(request)
-            .await
-            .expect("Failed to execute request");
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
 
-        assert_eq!(
-            response.status(),
-            StatusCode::OK,
-            "Method {} should return 200 OK",
-            method
-        );
-    }
+    let body = r#"{"zen":"Keep it logically awesome."}"#;
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"s3cret").unwrap();
+    mac.update(body.as_bytes());
+    let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+    let send = |signature: Option<&str>| {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/webhook/{}", endpoint_id));
+        if let Some(signature) = signature {
+            request = request.header("x-hub-signature-256", signature);
+        }
+        app.clone().oneshot(request.body(Body::from(body)).unwrap())
+    };
+
+    assert_eq!(
+        send(Some(&signature)).await.unwrap().status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        send(Some("sha256=00")).await.unwrap().status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(send(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
 
-    // Wait for async database insertions
-    tokio::time::sleep(tokio::time::Duration::from_millis(700)).await;
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-    // Verify all requests were captured
-    let count: i32 = sqlx::query_scalar("SELECT COUNT(*) FROM requests WHERE endpoint_id = ?")
+    // Rejected requests are still captured with their result
+    let results: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT signature_status, signature_computed, signature_provided FROM requests
+         WHERE endpoint_id = ? ORDER BY id",
+    )
+    .bind(&endpoint_id)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to fetch captured requests");
+
+    assert_eq!(
+        results,
+        vec![
+            (
+                "valid".to_string(),
+                Some(signature.clone()),
+                Some(signature.clone())
+            ),
+            (
+                "invalid".to_string(),
+                Some(signature.clone()),
+                Some("sha256=00".to_string())
+            ),
+            ("missing".to_string(), None, None),
+        ]
+    );
+
+    // A stored config that no longer parses is reported, not skipped
+    sqlx::query("UPDATE endpoints SET signature_secrets = '[]' WHERE id = ?")
         .bind(&endpoint_id)
-        .fetch_one(&pool)
+        .execute(&pool)
         .await
-        .expect("Failed to count requests");
-
-    assert_eq!(count, 7);
-}
-
-#[tokio::test]
-async fn test_webhook_binary_body() {
-    let pool = create_test_pool().await;
-    let endpoint_id = create_test_endpoint(&pool).await;
-
+        .expect("Failed to break signature verification");
     let app = axum::Router::new()
         .route(
             "/webhook/{id}",
@@ -278,42 +367,48 @@ async fn test_webhook_binary_body() {
             [127, 0, 0, 1],
             8080,
         ))));
-
-    // Send binary data
-    let binary_data = vec![0u8, 1, 2, 3, 255, 254, 253];
-
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri(format!("/webhook/{}", endpoint_id))
-        .header("content-type", "application/octet-stream")
-        .body(Body::from(binary_data.clone()))
-        .unwrap();
-
     let response = app
-        .oneshot(request)
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}", endpoint_id))
+                .header("x-hub-signature-256", &signature)
+                .body(Body::from(body))
+                .unwrap(),
+        )
         .await
-        .expect("Failed to execute request");
-
-    assert_eq!(response.status(), StatusCode::OK);
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let text = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&text[..], b"Signature error");
 
-    // Wait for async database insertion
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
-    // Verify binary body was stored correctly
-    let stored_body: Option<Vec<u8>> =
-        sqlx::query_scalar("SELECT body FROM requests WHERE endpoint_id = ?")
-            .bind(&endpoint_id)
-            .fetch_one(&pool)
-            .await
-            .expect("Failed to fetch captured request");
-
-    assert_eq!(stored_body, Some(binary_data));
+    let status: String = sqlx::query_scalar(
+        "SELECT signature_status FROM requests WHERE endpoint_id = ? ORDER BY id DESC LIMIT 1",
+    )
+    .bind(&endpoint_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(status, "error");
 }
 
 #[tokio::test]
-async fn test_webhook_empty_body() {
+async fn test_webhook_jwt_verification() {
+    use jsonwebtoken::{encode, get_current_timestamp, EncodingKey, Header};
+
     let pool = create_test_pool().await;
     let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query(
+        "UPDATE endpoints SET jwt_key_type = 'secret', jwt_key = 'jwt-secret',
+         jwt_audience = 'hookshot' WHERE id = ?",
+    )
+    .bind(&endpoint_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to configure JWT verification");
 
     let app = axum::Router::new()
         .route(
@@ -326,38 +421,55 @@ async fn test_webhook_empty_body() {
             8080,
         ))));
 
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri(format!("/webhook/{}", endpoint_id))
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app
-        .oneshot(request)
-        .await
-        .expect("Failed to execute request");
-
-    assert_eq!(response.status(), StatusCode::OK);
+    let now = get_current_timestamp();
+    let token = |exp: u64| {
+        encode(
+            &Header::default(),
+            &serde_json::json!({"aud": "hookshot", "exp": exp}),
+            &EncodingKey::from_secret(b"jwt-secret"),
+        )
+        .unwrap()
+    };
+
+    for authorization in [
+        Some(token(now + 300)),
+        Some(token(now - 300)),
+        Some("Bearer not-a-jwt".to_string()),
+        None,
+    ] {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/webhook/{}", endpoint_id));
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        // Verification only records the result
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 
     // Wait for async database insertion
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-    // Verify empty body is stored as NULL
-    let stored_body: Option<Vec<u8>> =
-        sqlx::query_scalar("SELECT body FROM requests WHERE endpoint_id = ?")
+    let statuses: Vec<(String,)> =
+        sqlx::query_as("SELECT jwt_status FROM requests WHERE endpoint_id = ? ORDER BY id")
             .bind(&endpoint_id)
-            .fetch_one(&pool)
+            .fetch_all(&pool)
             .await
-            .expect("Failed to fetch captured request");
-
-    assert_eq!(stored_body, None);
-}
-
-#[tokio::test]
-async fn test_webhook_cors_headers() {
-    let pool = create_test_pool().await;
-    let endpoint_id = create_test_endpoint(&pool).await;
+            .expect("Failed to fetch captured requests");
+    let statuses: Vec<&str> = statuses.iter().map(|(s,)| s.as_str()).collect();
+    assert_eq!(statuses, ["valid", "expired", "invalid", "missing"]);
 
+    // A stored config that no longer parses is reported, not skipped
+    sqlx::query("UPDATE endpoints SET jwt_key_type = 'x509' WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to break JWT verification");
     let app = axum::Router::new()
         .route(
             "/webhook/{id}",
@@ -368,45 +480,46 @@ async fn test_webhook_cors_headers() {
             [127, 0, 0, 1],
             8080,
         ))));
-
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri(format!("/webhook/{}", endpoint_id))
-        .body(Body::empty())
-        .unwrap();
-
     let response = app
-        .oneshot(request)
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}", endpoint_id))
+                .header("authorization", token(now + 300))
+                .body(Body::empty())
+                .unwrap(),
+        )
         .await
-        .expect("Failed to execute request");
-
-    // Verify CORS header is present
-    let cors_header = response
-        .headers()
-        .get("access-control-allow-origin")
-        .expect("CORS header should be present");
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
-    assert_eq!(cors_header, "*");
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    let (status, error): (String, Option<String>) = sqlx::query_as(
+        "SELECT jwt_status, jwt_error FROM requests WHERE endpoint_id = ? ORDER BY id DESC LIMIT 1",
+    )
+    .bind(&endpoint_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(status, "error");
+    assert_eq!(
+        error.as_deref(),
+        Some("invalid JWT key type 'x509', expected jwks, pem or secret")
+    );
 }
 
 #[tokio::test]
-async fn test_webhook_custom_response() {
+async fn test_webhook_inbound_auth() {
     let pool = create_test_pool().await;
-
-    // Create endpoint with custom response
-    let endpoint_id = uuid::Uuid::new_v4().to_string();
+    let endpoint_id = create_test_endpoint(&pool).await;
     sqlx::query(
-        r#"
-        INSERT INTO endpoints (id, created_at, custom_response_enabled, response_status, response_headers, response_body, request_count)
-        VALUES (?, datetime('now'), true, 201, ?, ?, 0)
-        "#
+        "UPDATE endpoints SET auth_type = 'basic', auth_username = 'hook', auth_secret = 's3cret'
+         WHERE id = ?",
     )
     .bind(&endpoint_id)
-    .bind(r#"{"X-Custom-Header": "CustomValue", "Content-Type": "application/json"}"#)
-    .bind(r#"{"message": "Custom response body"}"#)
     .execute(&pool)
     .await
-    .expect("Failed to create test endpoint with custom response");
+    .expect("Failed to configure inbound auth");
 
     let app = axum::Router::new()
         .route(
@@ -419,49 +532,1966 @@ async fn test_webhook_custom_response() {
             8080,
         ))));
 
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri(format!("/webhook/{}", endpoint_id))
-        .body(Body::from(r#"{"test": "data"}"#))
-        .unwrap();
-
-    let response = app
-        .oneshot(request)
-        .await
-        .expect("Failed to execute request");
-
-    // Verify custom status code
-    assert_eq!(response.status(), StatusCode::CREATED);
+    // "hook:s3cret" and "hook:wrong"
+    for (authorization, expected) in [
+        (Some("Basic aG9vazpzM2NyZXQ="), StatusCode::OK),
+        (Some("Basic aG9vazp3cm9uZw=="), StatusCode::UNAUTHORIZED),
+        (None, StatusCode::UNAUTHORIZED),
+    ] {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/webhook/{}", endpoint_id));
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::from("payload")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected);
+        if expected == StatusCode::UNAUTHORIZED {
+            assert_eq!(
+                response.headers()["www-authenticate"],
+                r#"Basic realm="hookshot""#
+            );
+        }
+    }
 
-    // Verify custom headers
-    let custom_header = response
-        .headers()
-        .get("x-custom-header")
-        .expect("Custom header should be present");
-    assert_eq!(custom_header, "CustomValue");
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-    let content_type = response
+    // Refused attempts are recorded and flagged
+    let results: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT auth_status, auth_error FROM requests WHERE endpoint_id = ? ORDER BY id",
+    )
+    .bind(&endpoint_id)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to fetch captured requests");
+
+    assert_eq!(
+        results,
+        vec![
+            ("authorized".to_string(), None),
+            (
+                "unauthorized".to_string(),
+                Some("invalid credentials".to_string())
+            ),
+            (
+                "unauthorized".to_string(),
+                Some("missing credentials".to_string())
+            ),
+        ]
+    );
+
+    // A stored config that no longer parses refuses everyone instead of letting them in
+    sqlx::query("UPDATE endpoints SET auth_type = 'digest' WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to break inbound auth");
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}", endpoint_id))
+                .header("authorization", "Basic aG9vazpzM2NyZXQ=")
+                .body(Body::from("payload"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_webhook_ip_filter() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query(r#"UPDATE endpoints SET ip_allow = '["10.0.0.0/8"]' WHERE id = ?"#)
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to configure IP filter");
+
+    let state = create_test_state(pool.clone());
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(state.clone())
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let send = || {
+        app.clone().oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}", endpoint_id))
+                .body(Body::from("payload"))
+                .unwrap(),
+        )
+    };
+
+    // Blocked clients are counted but not captured by default
+    let response = send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // With recording on, they are stored and flagged, answered with the configured status
+    sqlx::query("UPDATE endpoints SET ip_block_status = 429, ip_block_record = TRUE WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to configure IP filter");
+    state.endpoints.invalidate(&endpoint_id);
+    let response = send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let counts: (i64, i64) =
+        sqlx::query_as("SELECT request_count, blocked_count FROM endpoints WHERE id = ?")
+            .bind(&endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to fetch endpoint counts");
+    assert_eq!(counts, (0, 2));
+
+    let results: Vec<(bool, Option<Vec<u8>>, Option<String>)> =
+        sqlx::query_as("SELECT blocked, body, ip_address FROM requests WHERE endpoint_id = ?")
+            .bind(&endpoint_id)
+            .fetch_all(&pool)
+            .await
+            .expect("Failed to fetch captured requests");
+    assert_eq!(results, vec![(true, None, Some("127.0.0.1".to_string()))]);
+}
+
+#[tokio::test]
+async fn test_webhook_invalid_ip_filter_blocks_everyone() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query(r#"UPDATE endpoints SET ip_allow = '["10.0.0.0/33"]' WHERE id = ?"#)
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to configure IP filter");
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}", endpoint_id))
+                .body(Body::from("payload"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_webhook_rate_limit() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query("UPDATE endpoints SET rate_limit = '3/h', client_rate_limit = '2/h' WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to configure rate limit");
+
+    let state = create_test_state(pool.clone());
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(state.clone())
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let mut statuses = Vec::new();
+    for _ in 0..3 {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/webhook/{}", endpoint_id))
+                    .body(Body::from("payload"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        statuses.push(response.status());
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            // One token every 30 minutes
+            let retry_after: u64 = response.headers()["retry-after"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!((1790..=1800).contains(&retry_after), "{}", retry_after);
+        }
+    }
+    assert_eq!(
+        statuses,
+        vec![
+            StatusCode::OK,
+            StatusCode::OK,
+            StatusCode::TOO_MANY_REQUESTS
+        ]
+    );
+
+    // Throttled requests are counted in memory and flushed to the endpoint
+    state.rate_limiter.flush_throttled(&pool).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    let counts: (i64, i64) =
+        sqlx::query_as("SELECT request_count, throttled_count FROM endpoints WHERE id = ?")
+            .bind(&endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to fetch endpoint counts");
+    assert_eq!(counts, (2, 1));
+}
+
+#[tokio::test]
+async fn test_webhook_unparseable_rate_limit() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query("UPDATE endpoints SET client_rate_limit = 'lots' WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to configure rate limit");
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    // A stored limit that does not parse applies the strictest limit, 1/h
+    let mut statuses = Vec::new();
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/webhook/{}", endpoint_id))
+                    .body(Body::from("payload"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        statuses.push(response.status());
+    }
+    assert_eq!(
+        statuses,
+        vec![StatusCode::OK, StatusCode::TOO_MANY_REQUESTS]
+    );
+}
+
+#[tokio::test]
+async fn test_webhook_schema_validation() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query(
+        r#"UPDATE endpoints SET json_schema = '{"type": "object", "required": ["id"]}'
+           WHERE id = ?"#,
+    )
+    .bind(&endpoint_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to configure schema");
+
+    let state = create_test_state(pool.clone());
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(state.clone())
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let send = |content_type: &'static str, body: &'static str| {
+        app.clone().oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}", endpoint_id))
+                .header("content-type", content_type)
+                .body(Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    // Invalid bodies are recorded but accepted unless the endpoint rejects them
+    assert_eq!(
+        send("application/json", r#"{"id": 1}"#)
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        send("application/json", "{}").await.unwrap().status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        send("text/plain", "hello").await.unwrap().status(),
+        StatusCode::OK
+    );
+
+    sqlx::query("UPDATE endpoints SET schema_reject = TRUE WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to configure schema");
+    state.endpoints.invalidate(&endpoint_id);
+    let response = send("application/json", "[]").await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["errors"][0]["pointer"], "");
+
+    // A body that can't be decoded is not refused as invalid JSON
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}", endpoint_id))
+                .header("content-type", "application/json")
+                .header("content-encoding", "compress")
+                .body(Body::from(&b"\x1f\x9d\x90"[..]))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let results: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT schema_status, schema_errors FROM requests WHERE endpoint_id = ? ORDER BY id",
+    )
+    .bind(&endpoint_id)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to fetch captured requests");
+    assert_eq!(results.len(), 5);
+    assert_eq!(results[0], (Some("valid".to_string()), None));
+    assert_eq!(results[1].0.as_deref(), Some("invalid"));
+    let errors: serde_json::Value = serde_json::from_str(results[1].1.as_deref().unwrap()).unwrap();
+    assert_eq!(errors[0]["pointer"], "");
+    assert!(errors[0]["message"].as_str().unwrap().contains("id"));
+    assert_eq!(results[2], (None, None));
+    assert_eq!(results[3].0.as_deref(), Some("invalid"));
+    assert_eq!(results[4].0.as_deref(), Some("skipped"));
+    assert!(results[4]
+        .1
+        .as_deref()
+        .unwrap()
+        .contains("unsupported content encoding 'compress'"));
+}
+
+#[tokio::test]
+async fn test_webhook_invalid_endpoint() {
+    let pool = create_test_pool().await;
+    let invalid_id = uuid::Uuid::new_v4().to_string();
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", invalid_id))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_webhook_payload_too_large() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    // Create 11MB payload
+    let large_payload = "x".repeat(11 * 1024 * 1024);
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .body(Body::from(large_payload))
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_webhook_all_http_methods() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let methods = vec![
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::PATCH,
+        Method::DELETE,
+        Method::HEAD,
+        Method::OPTIONS,
+    ];
+
+    for method in methods {
+        let app = axum::Router::new()
+            .route(
+                "/webhook/{id}",
+                axum::routing::any(handlers::webhook::webhook_handler),
+            )
+            .with_state(create_test_state(pool.clone()))
+            .layer(MockConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 1],
+                8080,
+            ))));
+
+        let request = Request::builder()
+            .method(method.clone())
+            .uri(format!("/webhook/{}", endpoint_id))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app
+            .oneshot(request)
+            .await
+            .expect("Failed to execute request");
+
+        assert_eq!(
+            response.status(),
+            StatusCode::OK,
+            "Method {} should return 200 OK",
+            method
+        );
+    }
+
+    // Wait for async database insertions
+    tokio::time::sleep(tokio::time::Duration::from_millis(700)).await;
+
+    // Verify all requests were captured
+    let count: i32 = sqlx::query_scalar("SELECT COUNT(*) FROM requests WHERE endpoint_id = ?")
+        .bind(&endpoint_id)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to count requests");
+
+    assert_eq!(count, 7);
+}
+
+#[tokio::test]
+async fn test_webhook_binary_body() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    // Send binary data
+    let binary_data = vec![0u8, 1, 2, 3, 255, 254, 253];
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .header("content-type", "application/octet-stream")
+        .body(Body::from(binary_data.clone()))
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    // Verify binary body was stored correctly
+    let stored_body: Option<Vec<u8>> =
+        sqlx::query_scalar("SELECT body FROM requests WHERE endpoint_id = ?")
+            .bind(&endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to fetch captured request");
+
+    assert_eq!(stored_body, Some(binary_data));
+}
+
+#[tokio::test]
+async fn test_webhook_empty_body() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    // Verify empty body is stored as NULL
+    let stored_body: Option<Vec<u8>> =
+        sqlx::query_scalar("SELECT body FROM requests WHERE endpoint_id = ?")
+            .bind(&endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to fetch captured request");
+
+    assert_eq!(stored_body, None);
+}
+
+#[tokio::test]
+async fn test_webhook_cors_headers() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    // Verify CORS header is present
+    let cors_header = response
+        .headers()
+        .get("access-control-allow-origin")
+        .expect("CORS header should be present");
+
+    assert_eq!(cors_header, "*");
+}
+
+#[tokio::test]
+async fn test_webhook_custom_response() {
+    let pool = create_test_pool().await;
+
+    // Create endpoint with custom response
+    let endpoint_id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        r#"
+        INSERT INTO endpoints (id, created_at, custom_response_enabled, response_status, response_headers, response_body, request_count)
+        VALUES (?, datetime('now'), true, 201, ?, ?, 0)
+        "#
+    )
+    .bind(&endpoint_id)
+    .bind(r#"{"X-Custom-Header": "CustomValue", "Content-Type": "application/json"}"#)
+    .bind(r#"{"message": "Custom response body"}"#)
+    .execute(&pool)
+    .await
+    .expect("Failed to create test endpoint with custom response");
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .body(Body::from(r#"{"test": "data"}"#))
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    // Verify custom status code
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Verify custom headers
+    let custom_header = response
+        .headers()
+        .get("x-custom-header")
+        .expect("Custom header should be present");
+    assert_eq!(custom_header, "CustomValue");
+
+    let content_type = response
         .headers()
         .get("content-type")
         .expect("Content-Type header should be present");
     assert_eq!(content_type, "application/json");
 
-    // Verify custom body
-    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    // Verify custom body
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let body_str = String::from_utf8(body_bytes.to_vec()).expect("Invalid UTF-8");
+    assert_eq!(body_str, r#"{"message": "Custom response body"}"#);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    // Verify request was still captured despite custom response
+    let count: i32 = sqlx::query_scalar("SELECT COUNT(*) FROM requests WHERE endpoint_id = ?")
+        .bind(&endpoint_id)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to count requests");
+
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn test_webhook_templated_response() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query(
+        "UPDATE endpoints SET custom_response_enabled = TRUE, response_template = TRUE,
+         response_headers = ?, response_body = ? WHERE id = ?",
+    )
+    .bind(r#"{"X-Echo-Id": "{{ request.headers['x-request-id'] }}"}"#)
+    .bind(r#"{"order": {{ request.json.order | tojson }}, "method": "{{ request.method }}", "page": "{{ request.query.page }}"}"#)
+    .bind(&endpoint_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to configure response template");
+
+    let state = create_test_state(pool.clone());
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(state.clone())
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let send = || {
+        app.clone().oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}?page=3", endpoint_id))
+                .header("content-type", "application/json")
+                .header("x-request-id", "req-42")
+                .body(Body::from(r#"{"order": 17}"#))
+                .unwrap(),
+        )
+    };
+
+    let response = send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-echo-id"], "req-42");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&body).unwrap(),
+        r#"{"order": 17, "method": "POST", "page": "3"}"#
+    );
+
+    // A template that fails to render answers 500 and the error is kept with the request
+    sqlx::query("UPDATE endpoints SET response_body = '{{ random(9, 1) }}' WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to configure response template");
+    state.endpoints.invalidate(&endpoint_id);
+    let response = send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(std::str::from_utf8(&body)
+        .unwrap()
+        .starts_with("Response template error:"));
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let errors: Vec<Option<String>> =
+        sqlx::query_scalar("SELECT response_error FROM requests WHERE endpoint_id = ? ORDER BY id")
+            .bind(&endpoint_id)
+            .fetch_all(&pool)
+            .await
+            .expect("Failed to fetch captured requests");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0], None);
+    assert!(errors[1]
+        .as_deref()
+        .is_some_and(|e| e.contains("greater than max")));
+}
+
+#[tokio::test]
+async fn test_webhook_response_rules() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    let mut rule_ids = Vec::new();
+    for (position, matchers, status, body, delay_ms) in [
+        (
+            0,
+            r#"[{"type": "path", "glob": "/orders/*"}, {"type": "json", "path": "status", "equals": "paid"}]"#,
+            202,
+            "paid",
+            0,
+        ),
+        (
+            1,
+            r#"[{"type": "header", "name": "x-mode", "regex": "^slow"}]"#,
+            503,
+            "slow",
+            50,
+        ),
+    ] {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO response_rules (endpoint_id, position, matchers, status, body, delay_ms)
+             VALUES (?, ?, ?, ?, ?, ?) RETURNING id",
+        )
+        .bind(&endpoint_id)
+        .bind(position)
+        .bind(matchers)
+        .bind(status)
+        .bind(body)
+        .bind(delay_ms)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to create response rule");
+        rule_ids.push(id);
+    }
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}/{*path}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let send = |sub_path: &str, mode: &str, body: &'static str| {
+        app.clone().oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}{}", endpoint_id, sub_path))
+                .header("content-type", "application/json")
+                .header("x-mode", mode)
+                .body(Body::from(body))
+                .unwrap(),
+        )
+    };
+    let read = |response: axum::response::Response| async move {
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    };
+
+    let response = send("/orders/7", "fast", r#"{"status": "paid"}"#)
+        .await
+        .unwrap();
+    assert_eq!(
+        read(response).await,
+        (StatusCode::ACCEPTED, "paid".to_string())
+    );
+
+    // Rules are tried in order, so an unpaid order falls through to the header rule
+    let started = std::time::Instant::now();
+    let response = send("/orders/7", "slow-please", r#"{"status": "open"}"#)
+        .await
+        .unwrap();
+    assert_eq!(
+        read(response).await,
+        (StatusCode::SERVICE_UNAVAILABLE, "slow".to_string())
+    );
+    assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+
+    // Without a match the endpoint's default response is sent
+    let response = send("/refunds/1", "fast", "{}").await.unwrap();
+    assert_eq!(read(response).await, (StatusCode::OK, String::new()));
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let matched: Vec<Option<i64>> =
+        sqlx::query_scalar("SELECT rule_id FROM requests WHERE endpoint_id = ? ORDER BY id")
+            .bind(&endpoint_id)
+            .fetch_all(&pool)
+            .await
+            .expect("Failed to fetch captured requests");
+    assert_eq!(matched, vec![Some(rule_ids[0]), Some(rule_ids[1]), None]);
+}
+
+#[tokio::test]
+async fn test_webhook_response_sequence() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query("UPDATE endpoints SET response_sequence = ? WHERE id = ?")
+        .bind(
+            serde_json::json!({
+                "steps": [
+                    {"status": 500},
+                    {"status": 503, "headers": {"Retry-After": "1"}},
+                    {"status": 200, "body": "ok"}
+                ],
+                "per_sender": true
+            })
+            .to_string(),
+        )
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to set response sequence");
+    // Rules still answer first, without moving the sequence on
+    sqlx::query(
+        "INSERT INTO response_rules (endpoint_id, position, matchers, status) VALUES (?, 0, ?, 204)",
+    )
+    .bind(&endpoint_id)
+    .bind(r#"[{"type": "path", "glob": "/health"}]"#)
+    .execute(&pool)
+    .await
+    .expect("Failed to create response rule");
+
+    let state = create_test_state(pool.clone());
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}/{*path}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(state.clone());
+    let send = |sub_path: &str, client: [u8; 4]| {
+        app.clone()
+            .layer(MockConnectInfo(std::net::SocketAddr::from((client, 8080))))
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/webhook/{}{}", endpoint_id, sub_path))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+    };
+
+    let mut statuses = Vec::new();
+    for sub_path in ["/a", "/health", "/a", "/a", "/a"] {
+        let response = send(sub_path, [10, 0, 0, 1]).await.unwrap();
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            assert_eq!(response.headers()["retry-after"], "1");
+        }
+        statuses.push(response.status().as_u16());
+    }
+    assert_eq!(statuses, vec![500, 204, 503, 200, 200]);
+
+    // Each sender has its own position
+    let response = send("/a", [10, 0, 0, 2]).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    state.sequences.reset(&endpoint_id, Some("10.0.0.1"));
+    let response = send("/a", [10, 0, 0, 1]).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let positions: Vec<Option<i64>> = sqlx::query_scalar(
+        "SELECT sequence_position FROM requests WHERE endpoint_id = ? ORDER BY id",
+    )
+    .bind(&endpoint_id)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to fetch captured requests");
+    assert_eq!(
+        positions,
+        vec![Some(0), None, Some(1), Some(2), Some(2), Some(0), Some(0)]
+    );
+}
+
+#[tokio::test]
+async fn test_webhook_response_latency() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query(
+        "UPDATE endpoints SET custom_response_enabled = true, response_body = 'slow body',
+         response_latency = ? WHERE id = ?",
+    )
+    .bind(
+        serde_json::json!({
+            "delay": {"type": "fixed", "ms": 300},
+            "drip": {"duration_ms": 200, "chunks": 3}
+        })
+        .to_string(),
+    )
+    .bind(&endpoint_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to set response latency");
+    let other_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let send = |id: &str| {
+        app.clone().oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/webhook/{}", id))
+                .body(Body::from("ping"))
+                .unwrap(),
+        )
+    };
+
+    let started = std::time::Instant::now();
+    let slow = tokio::spawn(send(&endpoint_id));
+
+    // The slow request is stored, and others are answered, while it waits
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    let response = send(&other_id).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stored: Option<i64> =
+        sqlx::query_scalar("SELECT response_delay_ms FROM requests WHERE endpoint_id = ?")
+            .bind(&endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Slow request should be stored before it is answered");
+    assert_eq!(stored, Some(300));
+    assert!(!slow.is_finished());
+
+    // The body drips out after the delay
+    let response = slow.await.unwrap().unwrap();
+    assert!(started.elapsed() >= std::time::Duration::from_millis(300));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
         .await
-        .expect("Failed to read response body");
-    let body_str = String::from_utf8(body_bytes.to_vec()).expect("Invalid UTF-8");
-    assert_eq!(body_str, r#"{"message": "Custom response body"}"#);
+        .unwrap();
+    assert_eq!(&body[..], b"slow body");
+    assert!(started.elapsed() >= std::time::Duration::from_millis(500));
+
+    let other: Option<i64> =
+        sqlx::query_scalar("SELECT response_delay_ms FROM requests WHERE endpoint_id = ?")
+            .bind(&other_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(other, None);
+}
+
+#[tokio::test]
+async fn test_webhook_drip_is_not_compressed() {
+    use futures_util::StreamExt;
+
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    let body = "dripping ".repeat(20);
+    sqlx::query(
+        "UPDATE endpoints SET custom_response_enabled = true, response_body = ?,
+         response_latency = ? WHERE id = ?",
+    )
+    .bind(&body)
+    .bind(serde_json::json!({"drip": {"duration_ms": 600, "chunks": 3}}).to_string())
+    .bind(&endpoint_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to set response latency");
+
+    // Routed the way the server is: compression covers everything but the webhook routes
+    let app = axum::Router::new()
+        .route("/health", axum::routing::get(handlers::health_check))
+        .layer(tower_http::compression::CompressionLayer::new())
+        .merge(handlers::webhook::routes())
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let send = |uri: String| {
+        app.clone().oneshot(
+            Request::builder()
+                .uri(uri)
+                .header("accept-encoding", "gzip")
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+
+    let health = send("/health".to_string()).await.unwrap();
+    assert_eq!(health.headers()["content-encoding"], "gzip");
+
+    let response = send(format!("/webhook/{}", endpoint_id)).await.unwrap();
+    assert!(response.headers().get("content-encoding").is_none());
+
+    // Each chunk arrives on its own, the first long before the drip ends
+    let started = std::time::Instant::now();
+    let mut stream = response.into_body().into_data_stream();
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.len(), body.len().div_ceil(3));
+    assert!(started.elapsed() < std::time::Duration::from_millis(500));
+
+    let mut received = first.to_vec();
+    while let Some(chunk) = stream.next().await {
+        received.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(received, body.as_bytes());
+}
+
+#[tokio::test]
+async fn test_webhook_sub_path_capture() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .route(
+            "/webhook/{id}/{*path}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}/github/push?ref=main", endpoint_id))
+        .body(Body::from(r#"{"test": "data"}"#))
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    // Verify the full path was stored
+    let (path, query_string): (String, Option<String>) =
+        sqlx::query_as("SELECT path, query_string FROM requests WHERE endpoint_id = ?")
+            .bind(&endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to fetch captured request");
+
+    assert_eq!(path, format!("/webhook/{}/github/push", endpoint_id));
+    assert_eq!(query_string, Some("ref=main".to_string()));
+}
+
+#[tokio::test]
+async fn test_webhook_endpoint_body_limit() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query("UPDATE endpoints SET max_body_size = 16 WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    // Endpoint limit applies even though the global limit is 10MB
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .body(Body::from("x".repeat(17)))
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_webhook_body_spill_to_disk() {
+    use hmac::{Hmac, Mac};
+
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    // The signature is still checked, by streaming the file; the schema is not
+    sqlx::query(
+        "UPDATE endpoints SET max_body_size = 16, body_overflow = 'spill',
+         signature_scheme = 'github', signature_secrets = '[\"s3cret\"]',
+         json_schema = '{\"type\": \"object\"}', schema_reject = TRUE WHERE id = ?",
+    )
+    .bind(&endpoint_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let spill_dir = std::env::temp_dir().join(format!("hookshot_spill_{}", endpoint_id));
+    let config = Config {
+        spill_dir: spill_dir.clone(),
+        ..Config::default()
+    };
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(AppState::new(
+            pool.clone(),
+            Arc::new(WebSocketManager::new()),
+            config,
+        ))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let payload = "y".repeat(64);
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"s3cret").unwrap();
+    mac.update(payload.as_bytes());
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .header("content-type", "application/json")
+        .header(
+            "x-hub-signature-256",
+            format!("sha256={}", hex::encode(mac.finalize().into_bytes())),
+        )
+        .body(Body::from(payload.clone()))
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    // Body is kept on disk rather than in the database
+    let (body, body_size, body_truncated, body_file): (
+        Option<Vec<u8>>,
+        Option<i64>,
+        bool,
+        Option<String>,
+    ) = sqlx::query_as(
+        "SELECT body, body_size, body_truncated, body_file FROM requests WHERE endpoint_id = ?",
+    )
+    .bind(&endpoint_id)
+    .fetch_one(&pool)
+    .await
+    .expect("Failed to fetch captured request");
+
+    assert_eq!(body, None);
+    assert_eq!(body_size, Some(64));
+    assert!(!body_truncated);
+    let body_file = body_file.expect("Body should be spilled");
+    assert_eq!(std::fs::read_to_string(&body_file).unwrap(), payload);
+
+    let (signature_status, schema_status): (String, String) = sqlx::query_as(
+        "SELECT signature_status, schema_status FROM requests WHERE endpoint_id = ?",
+    )
+    .bind(&endpoint_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(signature_status, "valid");
+    assert_eq!(schema_status, "skipped");
+
+    let _ = std::fs::remove_dir_all(spill_dir);
+}
+
+#[tokio::test]
+async fn test_webhook_content_encoding_decoded_on_read() {
+    use std::io::Write;
+
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .route(
+            "/api/requests/{id}",
+            axum::routing::get(handlers::api::get_request_by_id),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(br#"{"event":"push"}"#).unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .header("content-type", "application/json")
+        .header("content-encoding", "gzip")
+        .body(Body::from(gzipped.clone()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    // The compressed bytes are stored as sent
+    let (id, body): (i64, Vec<u8>) =
+        sqlx::query_as("SELECT id, body FROM requests WHERE endpoint_id = ?")
+            .bind(&endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to fetch captured request");
+    assert_eq!(body, gzipped);
+
+    let request = Request::builder()
+        .uri(format!("/api/requests/{}", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(json["body_size"], gzipped.len());
+    assert_eq!(json["decoded"]["encoding"], "gzip");
+    assert_eq!(json["decoded"]["body"], r#"{"event":"push"}"#);
+    assert_eq!(json["decoded"]["size"], 16);
+    assert_eq!(json["decoded"]["truncated"], false);
+}
+
+#[tokio::test]
+async fn test_webhook_multipart_parts() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .route(
+            "/api/requests/{id}",
+            axum::routing::get(handlers::api::get_request_by_id),
+        )
+        .route(
+            "/api/requests/{id}/parts/{n}",
+            axum::routing::get(handlers::api::get_request_part),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let mut body = b"--boundary42\r\n\
+        Content-Disposition: form-data; name=\"comment\"\r\n\r\n\
+        looks good\r\n\
+        --boundary42\r\n\
+        Content-Disposition: form-data; name=\"report\"; filename=\"report.bin\"\r\n\
+        Content-Type: application/octet-stream\r\n\r\n"
+        .to_vec();
+    body.extend_from_slice(&[0x00, 0xff, 0x10, 0x0d, 0x0a]);
+    body.extend_from_slice(b"\r\n--boundary42--\r\n");
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .header("content-type", "multipart/form-data; boundary=boundary42")
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
     // Wait for async database insertion
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-    // Verify request was still captured despite custom response
-    let count: i32 = sqlx::query_scalar("SELECT COUNT(*) FROM requests WHERE endpoint_id = ?")
+    let (id,): (i64,) = sqlx::query_as("SELECT id FROM requests WHERE endpoint_id = ?")
         .bind(&endpoint_id)
         .fetch_one(&pool)
         .await
-        .expect("Failed to count requests");
+        .expect("Failed to fetch captured request");
+
+    let request = Request::builder()
+        .uri(format!("/api/requests/{}", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+    let parts = json["parts"].as_array().expect("parts should be parsed");
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0]["name"], "comment");
+    assert_eq!(parts[0]["value"], "looks good");
+    assert_eq!(parts[1]["name"], "report");
+    assert_eq!(parts[1]["filename"], "report.bin");
+    assert_eq!(parts[1]["content_type"], "application/octet-stream");
+    assert_eq!(parts[1]["size"], 5);
+    assert!(parts[1]["value"].is_null());
+    assert!(json["form_params"].is_null());
+
+    let request = Request::builder()
+        .uri(format!("/api/requests/{}/parts/1", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-disposition"],
+        "attachment; filename=\"report.bin\""
+    );
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    assert_eq!(response.headers()["content-security-policy"], "sandbox");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&bytes[..], &[0x00, 0xff, 0x10, 0x0d, 0x0a]);
+
+    // Parts without a filename are downloads too, never rendered inline
+    let request = Request::builder()
+        .uri(format!("/api/requests/{}/parts/0", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.headers()["content-disposition"], "attachment");
+
+    let request = Request::builder()
+        .uri(format!("/api/requests/{}/parts/2", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_webhook_durable_capture() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+    sqlx::query("UPDATE endpoints SET durable_capture = TRUE WHERE id = ?")
+        .bind(&endpoint_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .body(Body::from(r#"{"test": "data"}"#))
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
 
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // No waiting: the request must already be committed
+    let (stored, count): (i64, i32) = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM requests WHERE endpoint_id = ?1), request_count FROM endpoints WHERE id = ?1",
+    )
+    .bind(&endpoint_id)
+    .fetch_one(&pool)
+    .await
+    .expect("Failed to fetch endpoint");
+
+    assert_eq!(stored, 1);
     assert_eq!(count, 1);
 }
+
+#[tokio::test]
+async fn test_webhook_durable_capture_storage_failure() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    // Force every insert to fail
+    sqlx::query(
+        "CREATE TRIGGER fail_insert BEFORE INSERT ON requests
+         BEGIN SELECT RAISE(ABORT, 'storage unavailable'); END",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = Config {
+        durable_capture: true,
+        ..Config::default()
+    };
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(AppState::new(
+            pool.clone(),
+            Arc::new(WebSocketManager::new()),
+            config,
+        ))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .body(Body::from(r#"{"test": "data"}"#))
+        .unwrap();
+
+    let response = app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // The counter update was rolled back with the insert
+    let count: i32 = sqlx::query_scalar("SELECT request_count FROM endpoints WHERE id = ?")
+        .bind(&endpoint_id)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to fetch endpoint");
+
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn test_webhook_response_update_invalidates_cache() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .route(
+            "/api/endpoints/{id}/response",
+            axum::routing::put(handlers::api::update_endpoint_response),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let webhook = || {
+        Request::builder()
+            .method(Method::POST)
+            .uri(format!("/webhook/{}", endpoint_id))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // First request caches the endpoint with the default response
+    let response = app.clone().oneshot(webhook()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri(format!("/api/endpoints/{}/response", endpoint_id))
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"enabled": true, "status": 202}"#))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The update is visible immediately
+    let response = app.oneshot(webhook()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+}
+
+/// Send `count` requests to a missing endpoint and return the endpoint cache's hits and misses
+async fn missing_endpoint_lookups(config: Config, count: usize) -> (u64, u64) {
+    let state = AppState::new(
+        create_test_pool().await,
+        Arc::new(WebSocketManager::new()),
+        config,
+    );
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(state.clone())
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    for _ in 0..count {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/webhook/missing-endpoint")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    state.endpoints.hits_and_misses()
+}
+
+#[tokio::test]
+async fn test_webhook_endpoint_cache_hits() {
+    // Only the first lookup of the missing endpoint reaches the database
+    assert_eq!(
+        missing_endpoint_lookups(Config::default(), 50).await,
+        (49, 1)
+    );
+
+    let uncached = Config {
+        endpoint_cache_size: 0,
+        ..Config::default()
+    };
+    assert_eq!(missing_endpoint_lookups(uncached, 50).await, (0, 50));
+}
+
+#[tokio::test]
+async fn test_webhook_lossless_headers_over_tcp() {
+    use hookshot::models::{parse_raw_headers, RawHeader};
+    use hookshot::net::{ConnectionInfo, WireListener};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            WireListener::new(listener).unwrap(),
+            app.into_make_service_with_connect_info::<ConnectionInfo>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    // Two requests on one keep-alive connection, the first with a body
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let mut wire = format!(
+        "POST /webhook/{id} HTTP/1.1\r\nHost: localhost\r\nX-Hub-Signature-256: sha256=ab\r\n\
+         Content-Length: 2\r\n\r\n{{}}",
+        id = endpoint_id
+    )
+    .into_bytes();
+    wire.extend_from_slice(
+        format!(
+            "GET /webhook/{id}?q=1 HTTP/1.1\r\nhost: localhost\r\nX-Binary: ",
+            id = endpoint_id
+        )
+        .as_bytes(),
+    );
+    wire.extend_from_slice(b"\xff\xfe\r\nConnection: close\r\n\r\n");
+    stream.write_all(&wire).await.unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&response)
+            .matches("HTTP/1.1 200 OK")
+            .count(),
+        2
+    );
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let stored: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT method, raw_headers FROM requests WHERE endpoint_id = ? ORDER BY id",
+    )
+    .bind(&endpoint_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(stored.len(), 2);
+
+    let post = parse_raw_headers(stored[0].1.as_deref());
+    let names: Vec<&str> = post.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(names, ["Host", "X-Hub-Signature-256", "Content-Length"]);
+
+    let get = parse_raw_headers(stored[1].1.as_deref());
+    assert_eq!(
+        get[1],
+        RawHeader {
+            name: "X-Binary".to_string(),
+            value: vec![0xff, 0xfe],
+        }
+    );
+
+    // Connection metadata comes from the real socket
+    let (version, remote_port, local_address, local_port, tls, size): (
+        String,
+        i64,
+        String,
+        i64,
+        Option<bool>,
+        i64,
+    ) = sqlx::query_as(
+        "SELECT http_version, remote_port, local_address, local_port, tls, request_size
+         FROM requests WHERE endpoint_id = ? ORDER BY id LIMIT 1",
+    )
+    .bind(&endpoint_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(version, "HTTP/1.1");
+    assert_eq!(remote_port as u16, stream.local_addr().unwrap().port());
+    assert_eq!(local_address, "127.0.0.1");
+    assert_eq!(local_port as u16, addr.port());
+    assert_eq!(tls, Some(false));
+    let head_size = wire.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+    assert_eq!(size as usize, head_size + 2);
+}
+
+#[tokio::test]
+async fn test_webhook_client_ip_from_trusted_proxy() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let config = Config {
+        trusted_proxies: vec!["127.0.0.0/8".parse().unwrap()],
+        ..Config::default()
+    };
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(AppState::new(
+            pool.clone(),
+            Arc::new(WebSocketManager::new()),
+            config,
+        ))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/webhook/{}", endpoint_id))
+        .header("x-forwarded-for", "203.0.113.50, 127.0.0.2")
+        .header("x-forwarded-proto", "https")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let (ip_address, peer_address, tls): (String, String, Option<bool>) =
+        sqlx::query_as("SELECT ip_address, peer_address, tls FROM requests WHERE endpoint_id = ?")
+            .bind(&endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(ip_address, "203.0.113.50");
+    assert_eq!(peer_address, "127.0.0.1");
+    assert_eq!(tls, Some(true));
+}
+
+#[tokio::test]
+async fn test_webhook_proxy_protocol_listener() {
+    use hookshot::net::{ConnectionInfo, WireListener};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            WireListener::with_proxy_protocol(listener).unwrap(),
+            app.into_make_service_with_connect_info::<ConnectionInfo>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let wire = format!(
+        "PROXY TCP4 198.51.100.7 10.0.0.1 40000 443\r\n\
+         GET /webhook/{} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        endpoint_id
+    );
+    stream.write_all(wire.as_bytes()).await.unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let (ip_address, peer_address, remote_port, tls): (String, String, i64, Option<bool>) =
+        sqlx::query_as(
+            "SELECT ip_address, peer_address, remote_port, tls FROM requests WHERE endpoint_id = ?",
+        )
+        .bind(&endpoint_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(ip_address, "198.51.100.7");
+    assert_eq!(peer_address, "127.0.0.1");
+    assert_eq!(remote_port, 40000);
+    // A v1 header can't say whether the client used TLS
+    assert_eq!(tls, None);
+
+    // A connection without a PROXY header is dropped
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response).await;
+    assert!(response.is_empty());
+}
+
+#[tokio::test]
+async fn test_webhook_duplicates() {
+    let pool = create_test_pool().await;
+    let endpoint_id = create_test_endpoint(&pool).await;
+
+    let app = axum::Router::new()
+        .route(
+            "/webhook/{id}",
+            axum::routing::any(handlers::webhook::webhook_handler),
+        )
+        .with_state(create_test_state(pool.clone()))
+        .layer(MockConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))));
+    let send = |delivery: Option<&str>, body: &'static str| {
+        let method = if body.is_empty() {
+            Method::GET
+        } else {
+            Method::POST
+        };
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("/webhook/{}", endpoint_id));
+        if let Some(delivery) = delivery {
+            request = request
+                .header("x-github-event", "push")
+                .header("x-github-delivery", delivery);
+        }
+        app.clone().oneshot(request.body(Body::from(body)).unwrap())
+    };
+
+    // A redelivery is linked by its delivery ID even when the body changed
+    send(Some("72d3162e"), r#"{"ref":"main"}"#).await.unwrap();
+    send(Some("72d3162e"), r#"{"ref": "main"}"#).await.unwrap();
+    // Without an ID, identical bodies are linked by their fingerprint
+    send(None, "ping").await.unwrap();
+    send(None, "ping").await.unwrap();
+    send(None, "pong").await.unwrap();
+    // Requests without a body, like pings, are never linked
+    send(None, "").await.unwrap();
+    send(None, "").await.unwrap();
+
+    // Wait for async database insertion
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let results: Vec<(i64, Option<String>, Option<i64>, i64)> = sqlx::query_as(
+        "SELECT id, delivery_id, duplicate_of, retry FROM requests WHERE endpoint_id = ? ORDER BY id",
+    )
+    .bind(&endpoint_id)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to fetch captured requests");
+    let delivery = Some("72d3162e".to_string());
+    assert_eq!(
+        results
+            .iter()
+            .map(|(_, delivery_id, duplicate_of, retry)| (
+                delivery_id.clone(),
+                *duplicate_of,
+                *retry
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (delivery.clone(), None, 0),
+            (delivery, Some(results[0].0), 1),
+            (None, None, 0),
+            (None, Some(results[2].0), 1),
+            (None, None, 0),
+            (None, None, 0),
+            (None, None, 0),
+        ]
+    );
+
+    let fingerprints: Vec<Option<String>> =
+        sqlx::query_scalar("SELECT fingerprint FROM requests WHERE endpoint_id = ? ORDER BY id")
+            .bind(&endpoint_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert!(fingerprints[2].is_some());
+    assert_eq!(fingerprints[2], fingerprints[3]);
+    assert_eq!(fingerprints[5..], [None, None]);
+}
@@ -67,6 +67,7 @@ async fn test_websocket_broadcast() {
             content_type: Some("application/json".to_string()),
             received_at: "2024-01-01T00:00:00Z".to_string(),
             ip_address: Some("127.0.0.1".to_string()),
+            ..Default::default()
         }),
     };
 
@@ -166,6 +167,7 @@ async fn test_websocket_message_serialization() {
             content_type: Some("text/plain".to_string()),
             received_at: "2024-01-01T12:00:00Z".to_string(),
             ip_address: Some("192.168.1.1".to_string()),
+            ..Default::default()
         }),
     };
 
@@ -227,6 +229,7 @@ async fn test_rapid_broadcast() {
                 content_type: None,
                 received_at: "2024-01-01T00:00:00Z".to_string(),
                 ip_address: None,
+                ..Default::default()
             }),
         };
         manager.broadcast("endpoint1", message).await;
@@ -263,6 +266,7 @@ async fn test_latency_requirement() {
             content_type: None,
             received_at: "2024-01-01T00:00:00Z".to_string(),
             ip_address: None,
+            ..Default::default()
         }),
     };
 